@@ -0,0 +1,171 @@
+use std::io::Write;
+
+use super::{numeric::Numeric, solver::Value};
+
+type Evaluate<N> = fn(&[Value<N>], &mut dyn Write) -> Option<Value<N>>;
+
+// Functions that are available without a declaration. They are resolved
+// before user defined functions, adding a function only needs an entry in
+// BUILTINS.
+pub struct Builtin<N: Numeric = f64> {
+    pub name: &'static str,
+    pub arity: usize,
+    // only builtins that don't compute with their arguments accept strings
+    accepts_strings: bool,
+    // returns None if the arguments are outside of the domain of the function
+    evaluate: Evaluate<N>,
+}
+
+// the functions are instantiated for every numeric backend
+impl<N: Numeric> Builtin<N> {
+    const BUILTINS: &'static [Builtin<N>] = &[
+        Builtin {
+            name: "sqrt",
+            arity: 1,
+            accepts_strings: false,
+            evaluate: sqrt::<N>,
+        },
+        Builtin {
+            name: "abs",
+            arity: 1,
+            accepts_strings: false,
+            evaluate: abs::<N>,
+        },
+        Builtin {
+            name: "min",
+            arity: 2,
+            accepts_strings: false,
+            evaluate: min::<N>,
+        },
+        Builtin {
+            name: "max",
+            arity: 2,
+            accepts_strings: false,
+            evaluate: max::<N>,
+        },
+        Builtin {
+            name: "pow",
+            arity: 2,
+            accepts_strings: false,
+            evaluate: pow::<N>,
+        },
+        Builtin {
+            name: "floor",
+            arity: 1,
+            accepts_strings: false,
+            evaluate: floor::<N>,
+        },
+        Builtin {
+            name: "ceil",
+            arity: 1,
+            accepts_strings: false,
+            evaluate: ceil::<N>,
+        },
+        Builtin {
+            name: "print",
+            arity: 1,
+            accepts_strings: true,
+            evaluate: print::<N>,
+        },
+    ];
+
+    pub fn lookup(name: &str) -> Option<&'static Builtin<N>> {
+        Self::BUILTINS.iter().find(|builtin| builtin.name == name)
+    }
+
+    // The number of arguments has to match the arity. Output of the builtin
+    // is written to `output`.
+    pub fn call(&self, arguments: &[Value<N>], output: &mut dyn Write) -> Option<Value<N>> {
+        if !self.accepts_strings && arguments.iter().any(Value::is_str) {
+            return None;
+        }
+        (self.evaluate)(arguments, output)
+    }
+}
+
+fn sqrt<N: Numeric>(arguments: &[Value<N>], _output: &mut dyn Write) -> Option<Value<N>> {
+    let value = arguments[0].as_f64();
+    if value < 0.0 {
+        return None;
+    }
+    Some(Value::Float(N::from_f64(value.sqrt())))
+}
+
+fn abs<N: Numeric>(arguments: &[Value<N>], _output: &mut dyn Write) -> Option<Value<N>> {
+    Some(match &arguments[0] {
+        Value::Int(i) => Value::Int(i.checked_abs()?),
+        Value::Float(f) if *f < N::from_i64(0) => Value::Float(-*f),
+        Value::Float(f) => Value::Float(*f),
+        Value::Str(_) | Value::Tuple(_) => return None,
+    })
+}
+
+fn min<N: Numeric>(arguments: &[Value<N>], _output: &mut dyn Write) -> Option<Value<N>> {
+    Some(match (&arguments[0], &arguments[1]) {
+        (Value::Int(left), Value::Int(right)) => Value::Int(*left.min(right)),
+        // like for floats NaN is only the result if both are NaN
+        (left, right) => {
+            let (left, right) = (left.as_number(), right.as_number());
+            Value::Float(if right < left || left.is_nan() {
+                right
+            } else {
+                left
+            })
+        }
+    })
+}
+
+fn max<N: Numeric>(arguments: &[Value<N>], _output: &mut dyn Write) -> Option<Value<N>> {
+    Some(match (&arguments[0], &arguments[1]) {
+        (Value::Int(left), Value::Int(right)) => Value::Int(*left.max(right)),
+        (left, right) => {
+            let (left, right) = (left.as_number(), right.as_number());
+            Value::Float(if right > left || left.is_nan() {
+                right
+            } else {
+                left
+            })
+        }
+    })
+}
+
+// a negative base with a fractional exponent has no real result, an integer
+// result that overflows is out of the domain as well
+fn pow<N: Numeric>(arguments: &[Value<N>], _output: &mut dyn Write) -> Option<Value<N>> {
+    let (base, exponent) = (&arguments[0], &arguments[1]);
+    let result = base.clone().pow(exponent.clone())?;
+    if let Value::Float(f) = result {
+        if f.is_nan() && !base.as_number().is_nan() && !exponent.as_number().is_nan() {
+            return None;
+        }
+    }
+    Some(result)
+}
+
+fn floor<N: Numeric>(arguments: &[Value<N>], _output: &mut dyn Write) -> Option<Value<N>> {
+    Some(match &arguments[0] {
+        Value::Int(i) => Value::Int(*i),
+        Value::Float(f) => Value::Float(f.floor()),
+        Value::Str(_) | Value::Tuple(_) => return None,
+    })
+}
+
+fn ceil<N: Numeric>(arguments: &[Value<N>], _output: &mut dyn Write) -> Option<Value<N>> {
+    Some(match &arguments[0] {
+        Value::Int(i) => Value::Int(*i),
+        Value::Float(f) => Value::Float(f.ceil()),
+        Value::Str(_) | Value::Tuple(_) => return None,
+    })
+}
+
+// Writes the value on its own line, strings without quotes. The value of the
+// call is the printed value.
+fn print<N: Numeric>(arguments: &[Value<N>], output: &mut dyn Write) -> Option<Value<N>> {
+    let value = arguments[0].clone();
+    // a closed output doesn't stop the program
+    let _ = match &value {
+        Value::Str(string) => writeln!(output, "{}", string),
+        value => writeln!(output, "{}", value),
+    };
+    Some(value)
+}
@@ -0,0 +1,396 @@
+use std::cmp::Ordering;
+
+use super::{
+    ASTBinaryOperatorKind, ASTExpression, ASTExpressionKind, ASTUnaryOperatorKind, ASTVisitorMut,
+    Ast,
+};
+
+#[derive(Clone, Copy)]
+enum Literal {
+    Integer(i64),
+    Floating(f64),
+    Boolean(bool),
+}
+
+impl Literal {
+    fn from_expression(expr: &ASTExpression) -> Option<Self> {
+        match expr.kind {
+            ASTExpressionKind::IntegerLiteral(i) => Some(Literal::Integer(i)),
+            ASTExpressionKind::FloatingLiteral(f) => Some(Literal::Floating(f)),
+            ASTExpressionKind::BooleanLiteral(b) => Some(Literal::Boolean(b)),
+            _ => None,
+        }
+    }
+
+    fn into_expression(self) -> ASTExpression {
+        match self {
+            Literal::Integer(i) => ASTExpression::integer(i),
+            Literal::Floating(f) => ASTExpression::float(f),
+            Literal::Boolean(b) => ASTExpression::boolean(b),
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Literal::Integer(i) => Some(*i as f64),
+            Literal::Floating(f) => Some(*f),
+            Literal::Boolean(_) => None,
+        }
+    }
+
+    // like the solver, integers are compared exactly and not as floats
+    fn compare(&self, right: &Literal) -> Option<Ordering> {
+        match (self, right) {
+            (Literal::Integer(l), Literal::Integer(r)) => Some(l.cmp(r)),
+            (l, r) => l.as_f64()?.partial_cmp(&r.as_f64()?),
+        }
+    }
+}
+
+// Rewrites the ast, folding unary and binary expressions over literal
+// operands into a single literal. Expressions are folded with the same
+// semantics as the solver, anything the solver would report (e.g. an
+// integer division by zero) is kept as is. The nodes are folded in place,
+// a long chain of operators only costs a few small frames per level.
+pub struct ConstantFolder;
+
+impl ConstantFolder {
+    pub fn fold(mut ast: Ast) -> Ast {
+        ast.visit_mut(&mut ConstantFolder);
+        ast
+    }
+
+    // the folded literal of an expression whose operands are already folded
+    fn fold_expression(expr: &ASTExpression) -> Option<Literal> {
+        match &expr.kind {
+            ASTExpressionKind::Unary(unary) => Literal::from_expression(&unary.expr)
+                .and_then(|operand| Self::fold_unary(&unary.operator.kind, operand)),
+            ASTExpressionKind::Binary(binary) => {
+                let left = Literal::from_expression(&binary.left)?;
+                let right = Literal::from_expression(&binary.right)?;
+                Self::fold_binary(&binary.operator.kind, left, right)
+            }
+            ASTExpressionKind::Parenthesized(parenthesized) => {
+                Literal::from_expression(&parenthesized.expr)
+            }
+            _ => None,
+        }
+    }
+
+    fn fold_unary(operator: &ASTUnaryOperatorKind, operand: Literal) -> Option<Literal> {
+        match (operator, operand) {
+            (ASTUnaryOperatorKind::Plus, Literal::Integer(_) | Literal::Floating(_)) => {
+                Some(operand)
+            }
+            // an overflow is left to the solver to report
+            (ASTUnaryOperatorKind::Minus, Literal::Integer(i)) => {
+                i.checked_neg().map(Literal::Integer)
+            }
+            (ASTUnaryOperatorKind::Minus, Literal::Floating(f)) => Some(Literal::Floating(-f)),
+            (ASTUnaryOperatorKind::BitwiseNOT, Literal::Integer(i)) => Some(Literal::Integer(!i)),
+            (ASTUnaryOperatorKind::LogicNot, Literal::Boolean(b)) => Some(Literal::Boolean(!b)),
+            _ => None,
+        }
+    }
+
+    fn fold_binary(
+        operator: &ASTBinaryOperatorKind,
+        left: Literal,
+        right: Literal,
+    ) -> Option<Literal> {
+        if let (Literal::Boolean(l), Literal::Boolean(r)) = (left, right) {
+            return match operator {
+                ASTBinaryOperatorKind::LogicAND => Some(Literal::Boolean(l && r)),
+                ASTBinaryOperatorKind::LogicOR => Some(Literal::Boolean(l || r)),
+                ASTBinaryOperatorKind::EqualTo => Some(Literal::Boolean(l == r)),
+                ASTBinaryOperatorKind::NotEqualTo => Some(Literal::Boolean(l != r)),
+                _ => None,
+            };
+        }
+
+        if let (Literal::Integer(l), Literal::Integer(r)) = (left, right) {
+            // division by zero and overflows are left to the solver to report,
+            // negative exponents are handled as floats, comparisons below
+            let folded = match operator {
+                ASTBinaryOperatorKind::Plus => Some(l.checked_add(r)),
+                ASTBinaryOperatorKind::Minus => Some(l.checked_sub(r)),
+                ASTBinaryOperatorKind::Multiply => Some(l.checked_mul(r)),
+                ASTBinaryOperatorKind::Divide => Some(l.checked_div(r)),
+                ASTBinaryOperatorKind::Modulo => Some(l.checked_rem(r)),
+                ASTBinaryOperatorKind::Power => u32::try_from(r).ok().map(|r| l.checked_pow(r)),
+                ASTBinaryOperatorKind::BitwiseOR => Some(Some(l | r)),
+                ASTBinaryOperatorKind::BitwiseAND => Some(Some(l & r)),
+                ASTBinaryOperatorKind::BitwiseXOR => Some(Some(l ^ r)),
+                _ => None,
+            };
+            if let Some(folded) = folded {
+                return folded.map(Literal::Integer);
+            }
+        }
+
+        // division by zero is reported by the solver
+        let (l, r) = (left.as_f64()?, right.as_f64()?);
+        match operator {
+            ASTBinaryOperatorKind::Plus => Some(Literal::Floating(l + r)),
+            ASTBinaryOperatorKind::Minus => Some(Literal::Floating(l - r)),
+            ASTBinaryOperatorKind::Multiply => Some(Literal::Floating(l * r)),
+            ASTBinaryOperatorKind::Divide | ASTBinaryOperatorKind::Modulo if r == 0.0 => None,
+            ASTBinaryOperatorKind::Divide => Some(Literal::Floating(l / r)),
+            ASTBinaryOperatorKind::Modulo => Some(Literal::Floating(l % r)),
+            ASTBinaryOperatorKind::Power => Some(Literal::Floating(l.powf(r))),
+            ASTBinaryOperatorKind::EqualTo => Some(Literal::Boolean(
+                left.compare(&right).is_some_and(Ordering::is_eq),
+            )),
+            ASTBinaryOperatorKind::NotEqualTo => Some(Literal::Boolean(
+                !left.compare(&right).is_some_and(Ordering::is_eq),
+            )),
+            ASTBinaryOperatorKind::GreaterThan => Some(Literal::Boolean(
+                left.compare(&right).is_some_and(Ordering::is_gt),
+            )),
+            ASTBinaryOperatorKind::GreaterThanOrEqual => Some(Literal::Boolean(
+                left.compare(&right).is_some_and(Ordering::is_ge),
+            )),
+            ASTBinaryOperatorKind::LessThan => Some(Literal::Boolean(
+                left.compare(&right).is_some_and(Ordering::is_lt),
+            )),
+            ASTBinaryOperatorKind::LessThanOrEqual => Some(Literal::Boolean(
+                left.compare(&right).is_some_and(Ordering::is_le),
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl ConstantFolder {
+    fn replace_with_literal(expr: &mut ASTExpression) {
+        if let Some(literal) = Self::fold_expression(expr) {
+            let mut folded = literal.into_expression();
+            std::mem::swap(&mut folded.span, &mut expr.span);
+            *expr = folded;
+        }
+    }
+}
+
+impl ASTVisitorMut for ConstantFolder {
+    // The left operands of a chain like `1 + 2 + 3` are folded without
+    // recursing, a flat chain of any length doesn't overflow the stack
+    fn visit_expression(&mut self, expr: &mut ASTExpression) {
+        let mut chain = Vec::new();
+        let mut current = std::mem::replace(expr, ASTExpression::integer(0));
+        while let ASTExpressionKind::Binary(binary) = &mut current.kind {
+            let left = std::mem::replace(binary.left.as_mut(), ASTExpression::integer(0));
+            chain.push(current);
+            current = left;
+        }
+        self.do_visit_expression(&mut current);
+        Self::replace_with_literal(&mut current);
+        while let Some(mut parent) = chain.pop() {
+            if let ASTExpressionKind::Binary(binary) = &mut parent.kind {
+                *binary.left = current;
+                self.visit_expression(&mut binary.right);
+            }
+            Self::replace_with_literal(&mut parent);
+            current = parent;
+        }
+        *expr = current;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::ast::{
+        parser::Parser, ASTBinaryOperatorKind, ASTExpression, ASTExpressionKind, ASTStatementKind,
+        Ast,
+    };
+    use crate::diagnostics::DiagnosticsColletion;
+
+    fn fold(input: &str) -> Ast {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        ast.fold_constants()
+    }
+
+    fn expression(ast: &Ast, index: usize) -> &ASTExpression {
+        match ast.statements()[index].kind() {
+            ASTStatementKind::Expr(expr) => expr,
+            ASTStatementKind::Let(statement) => statement.initializer(),
+            ASTStatementKind::Return(statement) => statement.expression(),
+            _ => panic!("statement {} has no expression", index),
+        }
+    }
+
+    #[test]
+    fn should_collapse_nested_literal_expression() {
+        let ast = fold("((1 + 2) * (3 - -4)) ** 2 / 7 % 100;");
+        assert!(matches!(
+            expression(&ast, 0).kind(),
+            ASTExpressionKind::IntegerLiteral(63)
+        ));
+    }
+
+    #[test]
+    fn should_keep_span_of_folded_expression() {
+        let input = "let a: i32 = 1 + (2 * 3);";
+        let ast = fold(input);
+        let folded = expression(&ast, 0);
+        assert!(matches!(
+            folded.kind(),
+            ASTExpressionKind::IntegerLiteral(7)
+        ));
+        assert_eq!(folded.span().literal(input), "1 + (2 * 3)");
+        assert_eq!(ast.statements()[0].span().start, 0);
+    }
+
+    #[test]
+    fn should_fold_with_solver_semantics() {
+        let ast = fold("7 / 2; 1 + 0.5; 2 ** (0 - 1); 3 > 2 && !false; ~0 ^ 5;");
+        assert!(matches!(
+            expression(&ast, 0).kind(),
+            ASTExpressionKind::IntegerLiteral(3)
+        ));
+        assert!(
+            matches!(expression(&ast, 1).kind(), ASTExpressionKind::FloatingLiteral(f) if *f == 1.5)
+        );
+        assert!(
+            matches!(expression(&ast, 2).kind(), ASTExpressionKind::FloatingLiteral(f) if *f == 0.5)
+        );
+        assert!(matches!(
+            expression(&ast, 3).kind(),
+            ASTExpressionKind::BooleanLiteral(true)
+        ));
+        assert!(matches!(
+            expression(&ast, 4).kind(),
+            ASTExpressionKind::IntegerLiteral(-6)
+        ));
+    }
+
+    #[test]
+    fn should_compare_integers_exactly() {
+        let ast =
+            fold("9007199254740993 == 9007199254740992; 9007199254740993 > 9007199254740992;");
+        for index in 0..2 {
+            assert!(
+                matches!(
+                    expression(&ast, index).kind(),
+                    ASTExpressionKind::BooleanLiteral(b) if *b == (index == 1)
+                ),
+                "{index}"
+            );
+        }
+    }
+
+    #[test]
+    fn should_fold_negative_numbers_into_literals() {
+        let ast = fold("-5; -2.5; -5 * -5;");
+        assert!(matches!(
+            expression(&ast, 0).kind(),
+            ASTExpressionKind::IntegerLiteral(-5)
+        ));
+        assert!(
+            matches!(expression(&ast, 1).kind(), ASTExpressionKind::FloatingLiteral(f) if *f == -2.5)
+        );
+        assert!(matches!(
+            expression(&ast, 2).kind(),
+            ASTExpressionKind::IntegerLiteral(25)
+        ));
+        assert_eq!(ast.metrics().expression_count(), 3);
+    }
+
+    #[test]
+    fn should_not_fold_integer_overflow() {
+        let ast = fold("9223372036854775807 + 1; 2 ** 63; 1 / 0;");
+        for index in 0..3 {
+            assert!(matches!(
+                expression(&ast, index).kind(),
+                ASTExpressionKind::Binary(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn should_not_fold_across_variables_and_calls() {
+        let ast = fold("let a: i32 = 1; a + 2 * 3; f(1 + 1) * 2;");
+
+        let ASTExpressionKind::Binary(binary) = expression(&ast, 1).kind() else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(*binary.operator(), ASTBinaryOperatorKind::Plus);
+        assert!(matches!(
+            binary.left().kind(),
+            ASTExpressionKind::Variable(_)
+        ));
+        assert!(matches!(
+            binary.right().kind(),
+            ASTExpressionKind::IntegerLiteral(6)
+        ));
+
+        let ASTExpressionKind::Binary(binary) = expression(&ast, 2).kind() else {
+            panic!("expected a binary expression");
+        };
+        let ASTExpressionKind::FunctionCall(call) = binary.left().kind() else {
+            panic!("expected a function call");
+        };
+        assert!(matches!(
+            call.arguments()[0].kind(),
+            ASTExpressionKind::IntegerLiteral(2)
+        ));
+    }
+
+    #[test]
+    fn should_fold_long_flat_chain() {
+        let terms = vec!["1"; 10000].join(" + ");
+        let ast = fold(&format!("{};", terms));
+        assert!(matches!(
+            expression(&ast, 0).kind(),
+            ASTExpressionKind::IntegerLiteral(10000)
+        ));
+    }
+
+    #[test]
+    fn should_keep_division_by_zero() {
+        let ast = fold("1 / (2 - 2); 4 % 0; 1.5 / 0.0;");
+        assert!(matches!(
+            expression(&ast, 0).kind(),
+            ASTExpressionKind::Binary(_)
+        ));
+        assert!(matches!(
+            expression(&ast, 1).kind(),
+            ASTExpressionKind::Binary(_)
+        ));
+        assert!(matches!(
+            expression(&ast, 2).kind(),
+            ASTExpressionKind::Binary(_)
+        ));
+    }
+
+    #[test]
+    fn should_fold_nested_statements() {
+        let ast = fold("func f() -> i32 { if 1 < 2 { return 2 * 3; } else { return 0; } }");
+        let ASTStatementKind::FuncDecl(function) = ast.statements()[0].kind() else {
+            panic!("expected a function declaration");
+        };
+        let ASTStatementKind::Compound(body) = function.body().kind() else {
+            panic!("expected a block");
+        };
+        let ASTStatementKind::If(statement) = body.statements()[0].kind() else {
+            panic!("expected an if statement");
+        };
+        assert!(matches!(
+            statement.condition().kind(),
+            ASTExpressionKind::BooleanLiteral(true)
+        ));
+        let ASTStatementKind::Compound(then_branch) = statement.then_branch().kind() else {
+            panic!("expected a block");
+        };
+        let ASTStatementKind::Return(ret) = then_branch.statements()[0].kind() else {
+            panic!("expected a return statement");
+        };
+        assert!(matches!(
+            ret.expression().kind(),
+            ASTExpressionKind::IntegerLiteral(6)
+        ));
+    }
+}
@@ -0,0 +1,607 @@
+use std::collections::VecDeque;
+
+use crate::diagnostics::Diagnostic;
+
+use super::{
+    lexer::{char_literal, Lexer, Token, TokenKind, TriviaToken},
+    parser::try_parse,
+    ASTBinaryOperatorKind, ASTExpression, ASTExpressionKind, ASTStatement, ASTStatementKind,
+    ASTUnaryOperatorKind, ASTVisitor, Ast,
+};
+
+// Formats the source like `Ast::format`, but its comments and blank lines are
+// kept. Source with errors is not formatted, code would get lost.
+#[allow(dead_code)]
+pub fn format_source(source: &str) -> Result<String, Vec<Diagnostic>> {
+    let (ast, mut diagnostics) = try_parse(source);
+    if diagnostics.has_errors() {
+        return Err(std::mem::take(&mut diagnostics.diagnostics));
+    }
+    let tokens = Lexer::tokenize_with_trivia(source);
+    Ok(Formatter::with_trivia(&tokens).format(&ast))
+}
+
+struct Comment {
+    // the comment as written, including `//` or `/* */`
+    text: String,
+    start: usize,
+    // end of the token the comment follows on its line, None if the comment
+    // is on a line of its own
+    after: Option<usize>,
+}
+
+// Writes the ast back as source text in a canonical layout: one statement per
+// line, blocks indented by four spaces and operators surrounded by spaces.
+// Parentheses are kept as written so the output parses to the same tree.
+pub struct Formatter {
+    output: String,
+    indentation: usize,
+    // comments and positions of blank lines of the source, in source order
+    comments: VecDeque<Comment>,
+    blank_lines: VecDeque<usize>,
+    // end of the block that is formatted next
+    block_end: usize,
+}
+
+impl Formatter {
+    const INDENTATION: usize = 4;
+
+    pub fn new() -> Self {
+        Self {
+            output: String::new(),
+            indentation: 0,
+            comments: VecDeque::new(),
+            blank_lines: VecDeque::new(),
+            block_end: 0,
+        }
+    }
+
+    // Comments are written above the statement they preceded, or after it
+    // if they followed it on the same line. Blank lines between statements
+    // are kept, several of them become one.
+    pub fn with_trivia(tokens: &[TriviaToken]) -> Self {
+        let mut formatter = Self::new();
+        for token in tokens {
+            let mut line_breaks = 0;
+            for trivia in token.leading_trivia.iter() {
+                if trivia.kind == TokenKind::Whitespace {
+                    line_breaks += trivia.literal.matches('\n').count();
+                    continue;
+                }
+                if line_breaks > 1 {
+                    formatter.blank_lines.push_back(trivia.span.start);
+                }
+                line_breaks = 0;
+                formatter.comments.push_back(Comment {
+                    text: trivia.literal.clone(),
+                    start: trivia.span.start,
+                    after: None,
+                });
+            }
+            if line_breaks > 1 {
+                formatter.blank_lines.push_back(token.token.span.start);
+            }
+            for trivia in token.trailing_trivia.iter() {
+                if trivia.kind != TokenKind::Whitespace {
+                    formatter.comments.push_back(Comment {
+                        text: trivia.literal.clone(),
+                        start: trivia.span.start,
+                        after: Some(token.token.span.end),
+                    });
+                }
+            }
+        }
+        formatter
+    }
+
+    pub fn format(mut self, ast: &Ast) -> String {
+        ast.visit(&mut self);
+        self.comments_before(usize::MAX);
+        self.output
+    }
+
+    fn indent(&mut self) {
+        self.output.push_str(&" ".repeat(self.indentation));
+    }
+
+    // Bodies of functions, conditionals and loops are always blocks, they
+    // start on the line of their keyword.
+    fn body(&mut self, statement: &ASTStatement) {
+        match &statement.kind {
+            ASTStatementKind::Compound(block) => {
+                self.block_end = statement.span.end;
+                self.visit_compound_statement(block)
+            }
+            _ => self.do_visit_statement(statement),
+        }
+    }
+
+    // Writes the comments before `position` on lines of their own, starting
+    // at the beginning of a line
+    fn comments_before(&mut self, position: usize) {
+        while let Some(comment) = self
+            .comments
+            .pop_front_if(|comment| comment.start < position)
+        {
+            if self.take_blank_lines(comment.start) {
+                self.blank_line();
+            }
+            self.indent();
+            self.output.push_str(&comment.text);
+            self.output.push('\n');
+        }
+    }
+
+    // Comments that followed the code ending at `end` on its line
+    fn trailing_comments(&mut self, end: usize) {
+        while let Some(comment) = self
+            .comments
+            .pop_front_if(|comment| comment.after.is_some_and(|after| after <= end))
+        {
+            self.output.push(' ');
+            self.output.push_str(&comment.text);
+        }
+    }
+
+    // whether the source had a blank line before `position`
+    fn take_blank_lines(&mut self, position: usize) -> bool {
+        let mut found = false;
+        while self
+            .blank_lines
+            .pop_front_if(|line| *line <= position)
+            .is_some()
+        {
+            found = true;
+        }
+        found
+    }
+
+    // not at the start of the output or of a block
+    fn blank_line(&mut self) {
+        if !self.output.is_empty() && !self.output.ends_with("{\n") {
+            self.output.push('\n');
+        }
+    }
+
+    fn binary_operator(kind: &ASTBinaryOperatorKind) -> &'static str {
+        match kind {
+            ASTBinaryOperatorKind::Plus => "+",
+            ASTBinaryOperatorKind::Minus => "-",
+            ASTBinaryOperatorKind::Multiply => "*",
+            ASTBinaryOperatorKind::Divide => "/",
+            ASTBinaryOperatorKind::Modulo => "%",
+            ASTBinaryOperatorKind::Power => "**",
+            ASTBinaryOperatorKind::EqualTo => "==",
+            ASTBinaryOperatorKind::NotEqualTo => "!=",
+            ASTBinaryOperatorKind::LogicAND => "&&",
+            ASTBinaryOperatorKind::LogicOR => "||",
+            ASTBinaryOperatorKind::GreaterThan => ">",
+            ASTBinaryOperatorKind::GreaterThanOrEqual => ">=",
+            ASTBinaryOperatorKind::LessThan => "<",
+            ASTBinaryOperatorKind::LessThanOrEqual => "<=",
+            ASTBinaryOperatorKind::BitwiseOR => "|",
+            ASTBinaryOperatorKind::BitwiseAND => "&",
+            ASTBinaryOperatorKind::BitwiseXOR => "^",
+        }
+    }
+}
+
+impl ASTVisitor for Formatter {
+    fn visit_statement(&mut self, statement: &ASTStatement) {
+        self.comments_before(statement.span.start);
+        if self.take_blank_lines(statement.span.start) {
+            self.blank_line();
+        }
+        self.indent();
+        match &statement.kind {
+            ASTStatementKind::Expr(expr) => {
+                self.visit_expression(expr);
+                self.output.push(';');
+            }
+            ASTStatementKind::Compound(block) => {
+                self.block_end = statement.span.end;
+                self.visit_compound_statement(block);
+            }
+            _ => self.do_visit_statement(statement),
+        }
+        self.trailing_comments(statement.span.end);
+        self.output.push('\n');
+    }
+
+    fn visit_expression(&mut self, expr: &ASTExpression) {
+        if let ASTExpressionKind::Block(_) = &expr.kind {
+            self.block_end = expr.span.end;
+        }
+        self.do_visit_expression(expr);
+    }
+
+    fn visit_compound_statement(&mut self, statement: &super::ASTCompoundStatement) {
+        let end = self.block_end;
+        self.output.push_str("{\n");
+        self.indentation += Self::INDENTATION;
+        let statements = statement.statements();
+        let value = statement.value();
+        let statements = match value {
+            Some(_) => &statements[..statements.len() - 1],
+            None => statements,
+        };
+        for statement in statements.iter() {
+            self.visit_statement(statement);
+        }
+        // the value of the block has no ';'
+        if let Some(value) = value {
+            self.comments_before(value.span.start);
+            if self.take_blank_lines(value.span.start) {
+                self.blank_line();
+            }
+            self.indent();
+            self.visit_expression(value);
+            self.trailing_comments(value.span.end);
+            self.output.push('\n');
+        }
+        // comments at the end of the block stay in it
+        self.comments_before(end);
+        self.take_blank_lines(end);
+        self.indentation -= Self::INDENTATION;
+        self.indent();
+        self.output.push('}');
+    }
+
+    fn visit_return_statement(&mut self, statement: &super::ASTReturnStatement) {
+        self.output.push_str("return ");
+        self.visit_expression(&statement.expr);
+        self.output.push(';');
+    }
+
+    fn visit_let_statement(&mut self, statement: &super::ASTLetStatement) {
+        self.output.push_str(&format!(
+            "let {}: {} = ",
+            statement.identifier.literal, statement.data_type.literal
+        ));
+        self.visit_expression(&statement.initializer);
+        self.output.push(';');
+    }
+
+    fn visit_var_statement(&mut self, statement: &super::ASTVarStatement) {
+        self.output.push_str(&format!(
+            "var {}: {} = ",
+            statement.identifier.literal, statement.data_type.literal
+        ));
+        self.visit_expression(&statement.initializer);
+        self.output.push(';');
+    }
+
+    fn visit_if_statement(&mut self, statement: &super::ASTIfStatement) {
+        self.output.push_str("if ");
+        self.visit_expression(&statement.condition);
+        self.output.push(' ');
+        self.body(&statement.then_branch);
+        if let Some(else_branch) = &statement.else_branch {
+            self.output.push_str(" else ");
+            self.body(&else_branch.else_branch);
+        }
+    }
+
+    fn visit_for_loop_statement(&mut self, statement: &super::ASTForStatement) {
+        self.output
+            .push_str(&format!("for {} in ", statement.loop_variable.literal));
+        self.visit_expression(&statement.range.0);
+        self.output.push_str("..");
+        self.visit_expression(&statement.range.1);
+        self.output.push(' ');
+        self.body(&statement.body);
+    }
+
+    fn visit_while_loop_statement(&mut self, statement: &super::ASTWhileStatement) {
+        self.output.push_str("while ");
+        self.visit_expression(&statement.condition);
+        self.output.push(' ');
+        self.body(&statement.body);
+    }
+
+    fn visit_match_statement(&mut self, statement: &super::ASTMatchStatement) {
+        self.output.push_str("match ");
+        self.visit_expression(&statement.scrutinee);
+        self.output.push_str(" {\n");
+        self.indentation += Self::INDENTATION;
+        for arm in statement.arms.iter() {
+            if let Some(pattern) = &arm.pattern {
+                self.comments_before(pattern.span.start);
+            }
+            self.indent();
+            match &arm.pattern {
+                Some(pattern) => self.visit_expression(pattern),
+                None => self.output.push('_'),
+            }
+            self.output.push_str(" => ");
+            self.body(&arm.body);
+            self.output.push('\n');
+        }
+        self.indentation -= Self::INDENTATION;
+        self.indent();
+        self.output.push('}');
+    }
+
+    fn visit_break_statement(&mut self, _statement: &super::ASTBreakStatement) {
+        self.output.push_str("break;");
+    }
+
+    fn visit_continue_statement(&mut self, _statement: &super::ASTContinueStatement) {
+        self.output.push_str("continue;");
+    }
+
+    fn visit_assert_statement(&mut self, statement: &super::ASTAssertStatement) {
+        self.output.push_str("assert ");
+        self.visit_expression(&statement.condition);
+        self.output.push(';');
+    }
+
+    fn visit_funtion_statement(&mut self, function: &super::ASTFunctionStatement) {
+        self.output
+            .push_str(&format!("func {}(", function.identifier.literal));
+        for (i, argument) in function.arguments.iter().enumerate() {
+            if i != 0 {
+                self.output.push_str(", ");
+            }
+            self.output.push_str(&argument.identifier.literal);
+            if let Some(data_type) = &argument.data_type {
+                self.output.push_str(&format!(": {}", data_type.literal));
+            }
+            if let Some(default) = &argument.default {
+                self.output.push_str(" = ");
+                self.visit_expression(default);
+            }
+        }
+        self.output.push(')');
+        // a missing return type is parsed as void
+        if function.return_type.kind != TokenKind::Void {
+            self.output
+                .push_str(&format!(" -> {}", function.return_type.literal));
+        }
+        self.output.push(' ');
+        self.body(&function.body);
+    }
+
+    fn visit_assignment_expression(&mut self, expr: &super::ASTAssignmentExpression) {
+        self.output
+            .push_str(&format!("{} = ", expr.identifier.literal));
+        self.visit_expression(&expr.expr);
+    }
+
+    fn visit_function_call_expression(&mut self, expr: &super::ASTFunctionCallExpression) {
+        self.output
+            .push_str(&format!("{}(", expr.identifier.literal));
+        for (i, argument) in expr.arguments.iter().enumerate() {
+            if i != 0 {
+                self.output.push_str(", ");
+            }
+            self.visit_expression(argument);
+        }
+        self.output.push(')');
+    }
+
+    fn visit_variable_expression(&mut self, expr: &super::ASTVariableExpression) {
+        self.output.push_str(&expr.identifier.literal);
+    }
+
+    fn visit_unary_expression(&mut self, expr: &super::ASTUnaryExpression) {
+        self.output.push(match expr.operator.kind {
+            ASTUnaryOperatorKind::Plus => '+',
+            ASTUnaryOperatorKind::Minus => '-',
+            ASTUnaryOperatorKind::BitwiseNOT => '~',
+            ASTUnaryOperatorKind::LogicNot => '!',
+        });
+        self.visit_expression(&expr.expr);
+    }
+
+    fn visit_binary_expression(&mut self, expr: &super::ASTBinaryExpression) {
+        self.visit_expression(&expr.left);
+        self.output.push(' ');
+        self.visit_binary_operator(&expr.operator);
+        self.output.push(' ');
+        self.visit_expression(&expr.right);
+    }
+
+    fn visit_parenthesised_expression(&mut self, expr: &super::ASTParenthesizedExpression) {
+        self.output.push('(');
+        self.visit_expression(&expr.expr);
+        self.output.push(')');
+    }
+
+    fn visit_ternary_expression(&mut self, expr: &super::ASTTernaryExpression) {
+        self.visit_expression(&expr.condition);
+        self.output.push_str(" ? ");
+        self.visit_expression(&expr.then_expr);
+        self.output.push_str(" : ");
+        self.visit_expression(&expr.else_expr);
+    }
+
+    fn visit_tuple_expression(&mut self, expr: &super::ASTTupleExpression) {
+        self.output.push('(');
+        for (i, element) in expr.elements.iter().enumerate() {
+            if i != 0 {
+                self.output.push_str(", ");
+            }
+            self.visit_expression(element);
+        }
+        // a single element needs the comma to stay a tuple
+        if expr.elements.len() == 1 {
+            self.output.push(',');
+        }
+        self.output.push(')');
+    }
+
+    fn visit_binary_operator(&mut self, op: &super::ASTBinaryOperator) {
+        self.output.push_str(Self::binary_operator(&op.kind));
+    }
+
+    fn visit_integer(&mut self, integer: &i64) {
+        self.output.push_str(&integer.to_string());
+    }
+
+    fn visit_float(&mut self, float: &f64) {
+        // keep the dot so the literal is lexed as a float again
+        let literal = float.to_string();
+        self.output.push_str(&literal);
+        if !literal.contains('.') {
+            self.output.push_str(".0");
+        }
+    }
+
+    fn visit_boolean(&mut self, boolean: &bool) {
+        self.output.push_str(&boolean.to_string());
+    }
+
+    fn visit_char(&mut self, c: &char) {
+        self.output.push_str(&char_literal(*c));
+    }
+
+    fn visit_string(&mut self, string: &Token) {
+        self.output.push_str(&string.literal);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::format_source;
+    use crate::ast::{parser::Parser, printer::ASTTreePrinter, Ast};
+    use crate::diagnostics::DiagnosticsColletion;
+
+    fn parse(input: &str) -> Ast {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        ast
+    }
+
+    fn print_tree(ast: &Ast) -> String {
+        let mut printer = ASTTreePrinter::with_writer(Vec::new());
+        printer.set_no_color(true);
+        ast.visit(&mut printer);
+        String::from_utf8(printer.into_writer()).unwrap()
+    }
+
+    // Formats the input, checks that the output parses to the same tree and
+    // that formatting it again does not change it.
+    fn assert_round_trip(input: &str) -> String {
+        let ast = parse(input);
+        let formatted = ast.format();
+        let reparsed = parse(&formatted);
+        assert_eq!(print_tree(&ast), print_tree(&reparsed));
+        assert_eq!(reparsed.format(), formatted);
+        formatted
+    }
+
+    #[test]
+    fn should_format_function() {
+        let formatted = assert_round_trip("func f(x){return x+1;}");
+        assert_eq!(formatted, "func f(x) {\n    return x + 1;\n}\n");
+    }
+
+    #[test]
+    fn should_format_block_expression() {
+        let formatted = assert_round_trip("let x: i32 = {let a: i32 = 2; a * a};");
+        assert_eq!(
+            formatted,
+            "let x: i32 = {\n    let a: i32 = 2;\n    a * a\n};\n"
+        );
+        let formatted = assert_round_trip("let y: i32 = {let b: i32 = 3; {b * 2}};");
+        assert_eq!(
+            formatted,
+            "let y: i32 = {\n    let b: i32 = 3;\n    {\n        b * 2\n    }\n};\n"
+        );
+    }
+
+    #[test]
+    fn should_format_char_literals() {
+        let formatted = assert_round_trip("let c: char='\\n'; 'a';");
+        assert_eq!(formatted, "let c: char = '\\n';\n'a';\n");
+    }
+
+    #[test]
+    fn should_format_ternary() {
+        let formatted = assert_round_trip("x=a>1?b:c?d:(e?f:g);");
+        assert_eq!(formatted, "x = a > 1 ? b : c ? d : (e ? f : g);\n");
+    }
+
+    #[test]
+    fn should_format_tuples() {
+        let formatted = assert_round_trip("func f(a){return (a,(a+1,),(a));}");
+        assert_eq!(
+            formatted,
+            "func f(a) {\n    return (a, (a + 1,), (a));\n}\n"
+        );
+    }
+
+    #[test]
+    fn should_format_match() {
+        let formatted = assert_round_trip("match x {1=>{a;}, -2 => {} _=>{b;}}");
+        assert_eq!(
+            formatted,
+            "match x {\n    1 => {\n        a;\n    }\n    -2 => {\n    }\n    _ => {\n        b;\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn should_format_control_flow() {
+        let formatted = assert_round_trip(
+            "func g(a: i32, b = 1 + 1) -> i32 { var r: i32 = 0; for i in 0..a { if i % 2 == 0 { r = r + (i * b); } else if i > 5 { r = r - 1; } else { r = -r; } } while r > 100 { r = r / 2; } return r; } let x: i32 = g(3, 2); { let y: f64 = 2.0 ** 0.5; } print(\"done\", !true, ~x);",
+        );
+        assert_eq!(
+            formatted,
+            "\
+func g(a: i32, b = 1 + 1) -> i32 {
+    var r: i32 = 0;
+    for i in 0..a {
+        if i % 2 == 0 {
+            r = r + (i * b);
+        } else if i > 5 {
+            r = r - 1;
+        } else {
+            r = -r;
+        }
+    }
+    while r > 100 {
+        r = r / 2;
+    }
+    return r;
+}
+let x: i32 = g(3, 2);
+{
+    let y: f64 = 2.0 ** 0.5;
+}
+print(\"done\", !true, ~x);
+"
+        );
+    }
+
+    #[test]
+    fn should_keep_comments_and_blank_lines() {
+        let input = "\
+// the answer
+let x: i32 = 42; // trailing
+
+/* before the function */
+func f(a) {
+    // inside
+    let b: i32 = a;
+
+    b // value
+    // at the end
+}
+// at the end of the file
+";
+        let formatted = format_source(input).unwrap();
+        assert_eq!(formatted, input);
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn should_keep_comment_above_let_when_formatting() {
+        let formatted = format_source("  // answer\n  let   x:i32=42;\n\n\n\nx;").unwrap();
+        assert_eq!(formatted, "// answer\nlet x: i32 = 42;\n\nx;\n");
+        assert!(format_source("let x = ; // lost").is_err());
+    }
+}
@@ -0,0 +1,483 @@
+use super::{
+    lexer::{TextSpan, Token},
+    ASTStatement, ASTVisitor, Ast,
+};
+
+// Serializes the ast into compact JSON, every node is an object with a
+// `kind` field, tokens carry their span.
+pub struct ASTJsonSerializer {
+    output: String,
+}
+
+impl ASTJsonSerializer {
+    pub fn new() -> Self {
+        Self {
+            output: String::new(),
+        }
+    }
+
+    pub fn serialize(mut self, ast: &Ast) -> String {
+        self.begin_node("Program");
+        self.key("statements");
+        self.statements(&ast.statements);
+        self.end_node();
+        self.output
+    }
+
+    fn begin_node(&mut self, kind: &str) {
+        self.output.push_str("{\"kind\":");
+        self.string(kind);
+    }
+
+    fn end_node(&mut self) {
+        self.output.push('}');
+    }
+
+    fn key(&mut self, key: &str) {
+        self.output.push(',');
+        self.string(key);
+        self.output.push(':');
+    }
+
+    fn string(&mut self, value: &str) {
+        self.output.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => self.output.push_str("\\\""),
+                '\\' => self.output.push_str("\\\\"),
+                '\n' => self.output.push_str("\\n"),
+                '\r' => self.output.push_str("\\r"),
+                '\t' => self.output.push_str("\\t"),
+                c if c.is_control() => self.output.push_str(&format!("\\u{:04x}", c as u32)),
+                c => self.output.push(c),
+            }
+        }
+        self.output.push('"');
+    }
+
+    fn span(&mut self, span: &TextSpan) {
+        self.output.push_str(&format!(
+            "{{\"start\":{},\"end\":{}}}",
+            span.start, span.end
+        ));
+    }
+
+    // the span of a token also carries its text
+    fn token_span(&mut self, token: &Token) {
+        self.output.push_str(&format!(
+            "{{\"start\":{},\"end\":{},\"literal\":",
+            token.span.start, token.span.end
+        ));
+        self.string(&token.literal);
+        self.output.push('}');
+    }
+
+    fn token(&mut self, token: &Token) {
+        self.begin_node(&token.kind.to_string());
+        self.key("span");
+        self.token_span(token);
+        self.end_node();
+    }
+
+    fn statements(&mut self, statements: &[ASTStatement]) {
+        self.output.push('[');
+        for (i, statement) in statements.iter().enumerate() {
+            if i != 0 {
+                self.output.push(',');
+            }
+            self.visit_statement(statement);
+        }
+        self.output.push(']');
+    }
+
+    fn declaration(
+        &mut self,
+        kind: &str,
+        identifier: &Token,
+        data_type: &Token,
+        initializer: &super::ASTExpression,
+    ) {
+        self.begin_node(kind);
+        self.key("identifier");
+        self.token(identifier);
+        self.key("data_type");
+        self.token(data_type);
+        self.key("initializer");
+        self.visit_expression(initializer);
+        self.end_node();
+    }
+}
+
+impl ASTVisitor for ASTJsonSerializer {
+    fn visit_return_statement(&mut self, statement: &super::ASTReturnStatement) {
+        self.begin_node("Return");
+        self.key("expression");
+        self.visit_expression(&statement.expr);
+        self.end_node();
+    }
+
+    fn visit_let_statement(&mut self, statement: &super::ASTLetStatement) {
+        self.declaration(
+            "Let",
+            &statement.identifier,
+            &statement.data_type,
+            &statement.initializer,
+        );
+    }
+
+    fn visit_var_statement(&mut self, statement: &super::ASTVarStatement) {
+        self.declaration(
+            "Var",
+            &statement.identifier,
+            &statement.data_type,
+            &statement.initializer,
+        );
+    }
+
+    fn visit_compound_statement(&mut self, statement: &super::ASTCompoundStatement) {
+        self.begin_node("Compound");
+        self.key("statements");
+        self.statements(&statement.statements);
+        self.end_node();
+    }
+
+    fn visit_block_expression(&mut self, block: &super::ASTCompoundStatement) {
+        self.begin_node("Block");
+        self.key("statements");
+        self.statements(&block.statements);
+        self.key("has_value");
+        self.output.push_str(&block.has_value.to_string());
+        self.end_node();
+    }
+
+    fn visit_if_statement(&mut self, statement: &super::ASTIfStatement) {
+        self.begin_node("If");
+        self.key("keyword");
+        self.token(&statement.keyword);
+        self.key("condition");
+        self.visit_expression(&statement.condition);
+        self.key("then_branch");
+        self.visit_statement(&statement.then_branch);
+        self.key("else_branch");
+        match &statement.else_branch {
+            Some(else_branch) => {
+                self.begin_node("Else");
+                self.key("keyword");
+                self.token(&else_branch.else_keyword);
+                self.key("body");
+                self.visit_statement(&else_branch.else_branch);
+                self.end_node();
+            }
+            None => self.output.push_str("null"),
+        }
+        self.end_node();
+    }
+
+    fn visit_match_statement(&mut self, statement: &super::ASTMatchStatement) {
+        self.begin_node("Match");
+        self.key("keyword");
+        self.token(&statement.keyword);
+        self.key("scrutinee");
+        self.visit_expression(&statement.scrutinee);
+        self.key("arms");
+        self.output.push('[');
+        for (i, arm) in statement.arms.iter().enumerate() {
+            if i != 0 {
+                self.output.push(',');
+            }
+            self.begin_node("MatchArm");
+            self.key("pattern");
+            match &arm.pattern {
+                Some(pattern) => self.visit_expression(pattern),
+                None => self.output.push_str("null"),
+            }
+            self.key("body");
+            self.visit_statement(&arm.body);
+            self.end_node();
+        }
+        self.output.push(']');
+        self.end_node();
+    }
+
+    fn visit_for_loop_statement(&mut self, statement: &super::ASTForStatement) {
+        self.begin_node("For");
+        self.key("keyword");
+        self.token(&statement.keyword);
+        self.key("loop_variable");
+        self.token(&statement.loop_variable);
+        self.key("range_start");
+        self.visit_expression(&statement.range.0);
+        self.key("range_end");
+        self.visit_expression(&statement.range.1);
+        self.key("body");
+        self.visit_statement(&statement.body);
+        self.end_node();
+    }
+
+    fn visit_while_loop_statement(&mut self, statement: &super::ASTWhileStatement) {
+        self.begin_node("While");
+        self.key("keyword");
+        self.token(&statement.keyword);
+        self.key("condition");
+        self.visit_expression(&statement.condition);
+        self.key("body");
+        self.visit_statement(&statement.body);
+        self.end_node();
+    }
+
+    fn visit_break_statement(&mut self, statement: &super::ASTBreakStatement) {
+        self.begin_node("Break");
+        self.key("keyword");
+        self.token(&statement.keyword);
+        self.end_node();
+    }
+
+    fn visit_continue_statement(&mut self, statement: &super::ASTContinueStatement) {
+        self.begin_node("Continue");
+        self.key("keyword");
+        self.token(&statement.keyword);
+        self.end_node();
+    }
+
+    fn visit_assert_statement(&mut self, statement: &super::ASTAssertStatement) {
+        self.begin_node("Assert");
+        self.key("keyword");
+        self.token(&statement.keyword);
+        self.key("condition");
+        self.visit_expression(&statement.condition);
+        self.end_node();
+    }
+
+    fn visit_funtion_statement(&mut self, function: &super::ASTFunctionStatement) {
+        self.begin_node("Function");
+        self.key("identifier");
+        self.token(&function.identifier);
+        self.key("arguments");
+        self.output.push('[');
+        for (i, arg) in function.arguments.iter().enumerate() {
+            if i != 0 {
+                self.output.push(',');
+            }
+            self.begin_node("Argument");
+            self.key("identifier");
+            self.token(&arg.identifier);
+            self.key("data_type");
+            match &arg.data_type {
+                Some(data_type) => self.token(data_type),
+                None => self.output.push_str("null"),
+            }
+            self.key("default");
+            match &arg.default {
+                Some(default) => self.visit_expression(default),
+                None => self.output.push_str("null"),
+            }
+            self.end_node();
+        }
+        self.output.push(']');
+        self.key("return_type");
+        self.token(&function.return_type);
+        self.key("body");
+        self.visit_statement(&function.body);
+        self.end_node();
+    }
+
+    fn visit_assignment_expression(&mut self, expr: &super::ASTAssignmentExpression) {
+        self.begin_node("Assignment");
+        self.key("identifier");
+        self.token(&expr.identifier);
+        self.key("expression");
+        self.visit_expression(&expr.expr);
+        self.end_node();
+    }
+
+    fn visit_function_call_expression(&mut self, expr: &super::ASTFunctionCallExpression) {
+        self.begin_node("FunctionCall");
+        self.key("identifier");
+        self.token(&expr.identifier);
+        self.key("arguments");
+        self.output.push('[');
+        for (i, arg) in expr.arguments.iter().enumerate() {
+            if i != 0 {
+                self.output.push(',');
+            }
+            self.visit_expression(arg);
+        }
+        self.output.push(']');
+        self.end_node();
+    }
+
+    fn visit_variable_expression(&mut self, expr: &super::ASTVariableExpression) {
+        self.begin_node("Variable");
+        self.key("identifier");
+        self.token(&expr.identifier);
+        self.end_node();
+    }
+
+    fn visit_unary_expression(&mut self, expr: &super::ASTUnaryExpression) {
+        self.begin_node("Unary");
+        self.key("operator");
+        self.begin_node(&format!("{:?}", expr.operator.kind));
+        self.key("span");
+        self.token_span(&expr.operator.token);
+        self.end_node();
+        self.key("operand");
+        self.visit_expression(&expr.expr);
+        self.end_node();
+    }
+
+    fn visit_binary_expression(&mut self, expr: &super::ASTBinaryExpression) {
+        self.begin_node("Binary");
+        self.key("operator");
+        self.visit_binary_operator(&expr.operator);
+        self.key("left");
+        self.visit_expression(&expr.left);
+        self.key("right");
+        self.visit_expression(&expr.right);
+        self.end_node();
+    }
+
+    fn visit_parenthesised_expression(&mut self, expr: &super::ASTParenthesizedExpression) {
+        self.begin_node("Parenthesized");
+        self.key("expression");
+        self.visit_expression(&expr.expr);
+        self.end_node();
+    }
+
+    fn visit_ternary_expression(&mut self, expr: &super::ASTTernaryExpression) {
+        self.begin_node("Ternary");
+        self.key("condition");
+        self.visit_expression(&expr.condition);
+        self.key("then");
+        self.visit_expression(&expr.then_expr);
+        self.key("else");
+        self.visit_expression(&expr.else_expr);
+        self.end_node();
+    }
+
+    fn visit_tuple_expression(&mut self, expr: &super::ASTTupleExpression) {
+        self.begin_node("Tuple");
+        self.key("elements");
+        self.output.push('[');
+        for (i, element) in expr.elements.iter().enumerate() {
+            if i != 0 {
+                self.output.push(',');
+            }
+            self.visit_expression(element);
+        }
+        self.output.push(']');
+        self.end_node();
+    }
+
+    fn visit_binary_operator(&mut self, op: &super::ASTBinaryOperator) {
+        self.begin_node(&format!("{:?}", op.kind));
+        self.key("span");
+        self.token_span(&op.token);
+        self.end_node();
+    }
+
+    fn visit_error(&mut self, span: &TextSpan) {
+        self.begin_node("Error");
+        self.key("span");
+        self.span(span);
+        self.end_node();
+    }
+
+    fn visit_integer(&mut self, integer: &i64) {
+        self.begin_node("Integer");
+        self.key("value");
+        self.output.push_str(&integer.to_string());
+        self.end_node();
+    }
+
+    fn visit_float(&mut self, float: &f64) {
+        self.begin_node("Floating");
+        self.key("value");
+        // JSON has no representation for NaN and infinity
+        if float.is_finite() {
+            self.output.push_str(&format!("{:?}", float));
+        } else {
+            self.output.push_str("null");
+        }
+        self.end_node();
+    }
+
+    fn visit_boolean(&mut self, boolean: &bool) {
+        self.begin_node("Boolean");
+        self.key("value");
+        self.output.push_str(&boolean.to_string());
+        self.end_node();
+    }
+
+    fn visit_char(&mut self, c: &char) {
+        self.begin_node("Char");
+        self.key("value");
+        self.string(&c.to_string());
+        self.end_node();
+    }
+
+    fn visit_string(&mut self, string: &Token) {
+        self.begin_node("String");
+        self.key("span");
+        self.token_span(string);
+        self.end_node();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::ast::parser::Parser;
+    use crate::diagnostics::DiagnosticsColletion;
+
+    fn to_json(input: &str) -> String {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut parser = Parser::from_input(input.to_string(), Rc::clone(&diagnostics));
+        let ast = parser.parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        ast.to_json()
+    }
+
+    #[test]
+    fn should_serialize_let_statement() {
+        assert_eq!(
+            to_json("let a: i32 = 1 + b;"),
+            concat!(
+                r#"{"kind":"Program","statements":[{"kind":"Let","#,
+                r#""identifier":{"kind":"Identifier","span":{"start":4,"end":5,"literal":"a"}},"#,
+                r#""data_type":{"kind":"I32","span":{"start":7,"end":10,"literal":"i32"}},"#,
+                r#""initializer":{"kind":"Binary","#,
+                r#""operator":{"kind":"Plus","span":{"start":15,"end":16,"literal":"+"}},"#,
+                r#""left":{"kind":"Integer","value":1},"#,
+                r#""right":{"kind":"Variable","identifier":{"kind":"Identifier","#,
+                r#""span":{"start":17,"end":18,"literal":"b"}}}}}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn should_serialize_nested_statements() {
+        assert_eq!(
+            to_json("if true { return 2.5; }"),
+            concat!(
+                r#"{"kind":"Program","statements":[{"kind":"If","#,
+                r#""keyword":{"kind":"If","span":{"start":0,"end":2,"literal":"if"}},"#,
+                r#""condition":{"kind":"Boolean","value":true},"#,
+                r#""then_branch":{"kind":"Compound","statements":[{"kind":"Return","#,
+                r#""expression":{"kind":"Floating","value":2.5}}]},"#,
+                r#""else_branch":null}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn should_escape_string_literals() {
+        assert_eq!(
+            to_json("\"a\\n\";"),
+            concat!(
+                r#"{"kind":"Program","statements":[{"kind":"String","#,
+                r#""span":{"start":0,"end":5,"literal":"\"a\\n\""}}]}"#
+            )
+        );
+    }
+}
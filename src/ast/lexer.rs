@@ -1,10 +1,15 @@
 use core::fmt;
 
+use crate::diagnostics::{DiagnosticsColletion, DiagnosticsColletionCell};
+use std::{cell::RefCell, rc::Rc};
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenKind {
     // Litarals
     Integer(i64),
     Floating(f64),
+    StringLiteral(String),
+    CharLiteral(char),
     Identifier,
 
     // Keywords
@@ -17,6 +22,10 @@ pub enum TokenKind {
     For,
     In,
     While,
+    Break,
+    Continue,
+    Match,
+    Assert,
     I8,
     I16,
     I32,
@@ -33,18 +42,23 @@ pub enum TokenKind {
     Struct,
     Void,
     Null,
+    True,
+    False,
 
     // Arithmetic Operators
     Plus,
     Minus,
     Astrisk,
+    AstriskAstrisk,
     Slash,
+    Percent,
     Equal,
 
     PlusEqual,
     MinusEqual,
     AstriskEqual,
     SlashEqual,
+    PercentEqual,
 
     // Bitwise Operators
     Pipe,           // OR
@@ -61,10 +75,10 @@ pub enum TokenKind {
     EqualEqual,             // equal to
     AmpersandAmpersand,     // AND
     PipePipe,               // OR
-    LeftAngleBracket,       // greater than
-    LeftAngleBracketEqual,  // greater than or equal
-    RightAngleBracket,      // less than
-    RightAngleBracketEqual, // less than or equal
+    LeftAngleBracket,       // less than
+    LeftAngleBracketEqual,  // less than or equal
+    RightAngleBracket,      // greater than
+    RightAngleBracketEqual, // greater than or equal
 
     // Misc & other lexical symbols
     SingleLineComment(String),
@@ -81,10 +95,12 @@ pub enum TokenKind {
     RightBracket,
 
     MinusRightAngleBracket, // -> for return types
+    EqualRightAngleBracket, // => for match arms
     Comma,
     Dot,
     SemiColon,
     Colon,
+    QuestionMark,
 
     Whitespace,
     Bad,
@@ -94,7 +110,11 @@ pub enum TokenKind {
 impl fmt::Display for TokenKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            TokenKind::Integer(_) => write!(f, "Integer"),
+            TokenKind::Floating(_) => write!(f, "Floating"),
             TokenKind::Identifier => write!(f, "Identifier"),
+            TokenKind::StringLiteral(_) => write!(f, "String"),
+            TokenKind::CharLiteral(_) => write!(f, "Character"),
             TokenKind::Let => write!(f, "Let"),
             TokenKind::Var => write!(f, "Var"),
             TokenKind::Func => write!(f, "Func"),
@@ -104,6 +124,10 @@ impl fmt::Display for TokenKind {
             TokenKind::For => write!(f, "For"),
             TokenKind::In => write!(f, "In"),
             TokenKind::While => write!(f, "While"),
+            TokenKind::Break => write!(f, "Break"),
+            TokenKind::Continue => write!(f, "Continue"),
+            TokenKind::Match => write!(f, "Match"),
+            TokenKind::Assert => write!(f, "Assert"),
             TokenKind::I8 => write!(f, "I8"),
             TokenKind::I16 => write!(f, "I16"),
             TokenKind::I32 => write!(f, "I32"),
@@ -120,15 +144,20 @@ impl fmt::Display for TokenKind {
             TokenKind::Struct => write!(f, "Struct"),
             TokenKind::Void => write!(f, "Void"),
             TokenKind::Null => write!(f, "Null"),
+            TokenKind::True => write!(f, "True"),
+            TokenKind::False => write!(f, "False"),
             TokenKind::Plus => write!(f, "+"),
             TokenKind::Minus => write!(f, "-"),
             TokenKind::Astrisk => write!(f, "*"),
+            TokenKind::AstriskAstrisk => write!(f, "**"),
             TokenKind::Slash => write!(f, "/"),
+            TokenKind::Percent => write!(f, "%"),
             TokenKind::Equal => write!(f, "="),
             TokenKind::PlusEqual => write!(f, "+="),
             TokenKind::MinusEqual => write!(f, "-="),
             TokenKind::AstriskEqual => write!(f, "*="),
             TokenKind::SlashEqual => write!(f, "/="),
+            TokenKind::PercentEqual => write!(f, "%="),
             TokenKind::Pipe => write!(f, "|"),
             TokenKind::Ampersand => write!(f, "&"),
             TokenKind::Caret => write!(f, "^"),
@@ -141,7 +170,7 @@ impl fmt::Display for TokenKind {
             TokenKind::EqualEqual => write!(f, "=="),
             TokenKind::AmpersandAmpersand => write!(f, "&&"),
             TokenKind::PipePipe => write!(f, "||"),
-            TokenKind::LeftAngleBracket => write!(f, ">"),
+            TokenKind::LeftAngleBracket => write!(f, "<"),
             TokenKind::LeftAngleBracketEqual => write!(f, "<="),
             TokenKind::RightAngleBracket => write!(f, ">"),
             TokenKind::RightAngleBracketEqual => write!(f, ">="),
@@ -150,62 +179,229 @@ impl fmt::Display for TokenKind {
             TokenKind::AstriskSlash => write!(f, "*/"),
             TokenKind::LeftParen => write!(f, "("),
             TokenKind::RightParen => write!(f, ")"),
-            TokenKind::LeftBrace => write!(f, "{}", '{'),
-            TokenKind::RightBrace => write!(f, "{}", '}'),
+            TokenKind::LeftBrace => write!(f, "{{"),
+            TokenKind::RightBrace => write!(f, "}}"),
             TokenKind::LeftBracket => write!(f, "["),
             TokenKind::RightBracket => write!(f, "]"),
             TokenKind::MinusRightAngleBracket => write!(f, "->"),
+            TokenKind::EqualRightAngleBracket => write!(f, "=>"),
             TokenKind::Comma => write!(f, ","),
             TokenKind::Dot => write!(f, "."),
             TokenKind::SemiColon => write!(f, ";"),
             TokenKind::Colon => write!(f, ":"),
+            TokenKind::QuestionMark => write!(f, "?"),
             TokenKind::Whitespace => write!(f, "Whitespace"),
             TokenKind::Bad => write!(f, "Bad"),
             TokenKind::Eof => write!(f, "Eof"),
             TokenKind::SingleLineComment(_) => write!(f, "//"),
             TokenKind::MultiLineComment(_) => write!(f, "/* */"),
-            _ => todo!(),
+        }
+    }
+}
+
+impl TokenKind {
+    // Whitespace and comments, the parser skips them
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::Whitespace
+                | TokenKind::SingleLineComment(_)
+                | TokenKind::MultiLineComment(_)
+        )
+    }
+
+    // Readable name of the token including its value, e.g. `int '42'` or
+    // `keyword 'let'`, used when dumping the token stream
+    pub fn description(&self) -> String {
+        match self {
+            TokenKind::Integer(i) => format!("int '{}'", i),
+            TokenKind::Floating(f) => format!("float '{}'", f),
+            TokenKind::StringLiteral(s) => format!("string '{}'", s),
+            TokenKind::CharLiteral(c) => format!("char {}", char_literal(*c)),
+            TokenKind::SingleLineComment(c) | TokenKind::MultiLineComment(c) => {
+                format!("comment '{}'", c)
+            }
+            TokenKind::Identifier => "identifier".to_string(),
+            TokenKind::Let
+            | TokenKind::Var
+            | TokenKind::Func
+            | TokenKind::Return
+            | TokenKind::If
+            | TokenKind::Else
+            | TokenKind::For
+            | TokenKind::In
+            | TokenKind::While
+            | TokenKind::Break
+            | TokenKind::Continue
+            | TokenKind::Match
+            | TokenKind::Assert
+            | TokenKind::Struct
+            | TokenKind::Null
+            | TokenKind::True
+            | TokenKind::False => format!("keyword '{}'", self.to_string().to_lowercase()),
+            TokenKind::I8
+            | TokenKind::I16
+            | TokenKind::I32
+            | TokenKind::I64
+            | TokenKind::U8
+            | TokenKind::U16
+            | TokenKind::U32
+            | TokenKind::U64
+            | TokenKind::F32
+            | TokenKind::F64
+            | TokenKind::Bool
+            | TokenKind::Char
+            | TokenKind::Str
+            | TokenKind::Void => format!("type '{}'", self.to_string().to_lowercase()),
+            TokenKind::Whitespace => "whitespace".to_string(),
+            TokenKind::Bad => "bad".to_string(),
+            TokenKind::Eof => "end of file".to_string(),
+            _ => format!("'{}'", self),
         }
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct TextSpan {
+    // byte offsets into the source, the text itself is sliced from it on demand
     pub(crate) start: usize,
     pub(crate) end: usize,
-    pub(crate) literal: String,
+    // 1-based line and column of `start`, the column counts characters.
+    // Spans that don't originate from the source have line and column 0.
+    pub(crate) line: usize,
+    pub(crate) column: usize,
 }
 
 impl TextSpan {
-    pub fn new(start: usize, end: usize, literal: String) -> Self {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self::with_location(start, end, 0, 0)
+    }
+
+    pub fn with_location(start: usize, end: usize, line: usize, column: usize) -> Self {
         Self {
             start,
             end,
-            literal,
+            line,
+            column,
         }
     }
+
+    // The spanned text of `source`, empty if the span lies outside of it
+    pub fn literal<'a>(&self, source: &'a str) -> &'a str {
+        source.get(self.start..self.end).unwrap_or_default()
+    }
+
+    // Converts a byte offset into `input` to a 1-based (line, column) pair
+    pub fn line_column(input: &str, offset: usize) -> (usize, usize) {
+        let before = &input[..offset.min(input.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (line, before[line_start..].chars().count() + 1)
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     pub kind: TokenKind,
     pub(crate) span: TextSpan,
+    // the text of the token, nodes made of several tokens only keep a span
+    pub(crate) literal: String,
 }
 
 impl Token {
-    fn new(kind: TokenKind, span: TextSpan) -> Self {
-        Self { kind, span }
+    pub(crate) fn new(kind: TokenKind, span: TextSpan, literal: String) -> Self {
+        Self {
+            kind,
+            span,
+            literal,
+        }
     }
 }
 
+// One line per token for debugging the lexer: location, kind, byte range and literal
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>4}:{:<4} {:<24} {:>5}..{:<5} {:?}",
+            self.span.line,
+            self.span.column,
+            self.kind.description(),
+            self.span.start,
+            self.span.end,
+            self.literal
+        )
+    }
+}
+
+// A token with the whitespace and comments around it. The trivia up to the
+// end of its line belongs to the token, everything after that to the next one.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TriviaToken {
+    pub token: Token,
+    pub leading_trivia: Vec<Token>,
+    pub trailing_trivia: Vec<Token>,
+}
+
 pub struct Lexer {
     input: String,
+    // byte offset of the current character
     cursor: usize,
+    line: usize,
+    column: usize,
+    diagnostics_colletion: DiagnosticsColletionCell,
 }
 
 impl Lexer {
-    pub fn new(input: String) -> Self {
-        Self { input, cursor: 0 }
+    pub fn new(input: String, diagnostics_colletion: DiagnosticsColletionCell) -> Self {
+        Self {
+            input,
+            cursor: 0,
+            line: 1,
+            column: 1,
+            diagnostics_colletion,
+        }
+    }
+
+    // Lexes the whole input, including whitespace, comments and the final Eof.
+    // Diagnostics are dropped, invalid characters show up as Bad tokens.
+    pub fn tokenize(input: &str) -> Vec<Token> {
+        let diagnostics_colletion = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut lexer = Lexer::new(input.to_string(), diagnostics_colletion);
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            tokens.push(token);
+        }
+        tokens
+    }
+
+    // Like `tokenize`, but whitespace and comments are attached to the tokens
+    // instead of being tokens of their own. The end of file token gets the
+    // trivia after the last token.
+    pub fn tokenize_with_trivia(input: &str) -> Vec<TriviaToken> {
+        let mut tokens: Vec<TriviaToken> = Vec::new();
+        let mut leading_trivia = Vec::new();
+        for token in Self::tokenize(input) {
+            if !token.kind.is_trivia() {
+                tokens.push(TriviaToken {
+                    token,
+                    leading_trivia: std::mem::take(&mut leading_trivia),
+                    trailing_trivia: Vec::new(),
+                });
+                continue;
+            }
+            match tokens.last_mut() {
+                Some(previous)
+                    if leading_trivia.is_empty()
+                        && previous.token.kind != TokenKind::Eof
+                        && !token.literal.contains('\n') =>
+                {
+                    previous.trailing_trivia.push(token)
+                }
+                _ => leading_trivia.push(token),
+            }
+        }
+        tokens
     }
 
     pub fn next_token(&mut self) -> Option<Token> {
@@ -216,19 +412,20 @@ impl Lexer {
             self.cursor += 1;
             return Some(Token::new(
                 TokenKind::Eof,
-                TextSpan::new(0, 0, '\0'.to_string()),
+                TextSpan::new(0, 0),
+                '\0'.to_string(),
             ));
         }
 
         let start = self.cursor;
-        let mut kind = TokenKind::Bad;
+        let (line, column) = (self.line, self.column);
         let c = self.current_char()?;
 
-        if Self::is_number_start(&c) {
-            kind = self.consume_number();
+        let kind = if Self::is_number_start(&c) {
+            self.consume_number()
         } else if Self::is_identifier_start(&c) {
             let identifier = self.consume_identifier();
-            kind = match identifier.as_str() {
+            match identifier.as_str() {
                 "let" => TokenKind::Let,
                 "var" => TokenKind::Var,
                 "func" => TokenKind::Func,
@@ -238,6 +435,10 @@ impl Lexer {
                 "for" => TokenKind::For,
                 "in" => TokenKind::In,
                 "while" => TokenKind::While,
+                "break" => TokenKind::Break,
+                "continue" => TokenKind::Continue,
+                "match" => TokenKind::Match,
+                "assert" => TokenKind::Assert,
                 "i8" => TokenKind::I8,
                 "i16" => TokenKind::I16,
                 "i32" => TokenKind::I32,
@@ -254,27 +455,39 @@ impl Lexer {
                 "struct" => TokenKind::Struct,
                 "void" => TokenKind::Void,
                 "null" => TokenKind::Null,
+                "true" => TokenKind::True,
+                "false" => TokenKind::False,
                 _ => TokenKind::Identifier,
-            };
-        } else if c == '/' && self.peek(1)? == '/' {
-            kind = self.consume_single_line_comment();
-        } else if c == '/' && self.peek(1)? == '*' {
-            kind = self.consume_multi_line_comment();
+            }
+        } else if c == '"' {
+            self.consume_string()
+        } else if c == '\'' {
+            self.consume_char()
+        } else if c == '/' && self.peek(1) == Some('/') {
+            self.consume_single_line_comment()
+        } else if c == '/' && self.peek(1) == Some('*') {
+            self.consume_multi_line_comment()
         } else if Self::is_whitespace(&c) {
             self.consume();
-            kind = TokenKind::Whitespace;
+            TokenKind::Whitespace
         } else {
-            kind = self.consume_punctuation();
-        }
+            self.consume_punctuation()
+        };
 
         let end = self.cursor;
         let literal = self.input[start..end].to_string();
+        let span = TextSpan::with_location(start, end, line, column);
+        if kind == TokenKind::Bad {
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_unknown_character(c, span.clone());
+        }
 
-        Some(Token::new(kind, TextSpan::new(start, end, literal)))
+        Some(Token::new(kind, span, literal))
     }
 
     fn is_number_start(c: &char) -> bool {
-        c.is_digit(10)
+        c.is_ascii_digit()
     }
 
     fn is_identifier_start(c: &char) -> bool {
@@ -298,110 +511,381 @@ impl Lexer {
     }
 
     fn consume(&mut self) -> Option<char> {
-        if self.cursor >= self.input.len() {
-            return None;
+        let c = self.current_char()?;
+        self.cursor += c.len_utf8();
+        if Self::is_linebreak(&c) {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
         }
-        let c = self.current_char();
-        self.cursor += 1;
-        c
+        Some(c)
     }
 
     fn peek(&mut self, offset: usize) -> Option<char> {
-        if self.cursor + offset >= self.input.len() {
-            return None;
-        }
-        self.input.chars().nth(self.cursor + offset)
+        self.input.get(self.cursor..)?.chars().nth(offset)
+    }
+
+    // Span of source text that is not a whole token, e.g. for diagnostics
+    fn span(&self, start: usize, end: usize) -> TextSpan {
+        let (line, column) = TextSpan::line_column(&self.input, start);
+        TextSpan::with_location(start, end, line, column)
     }
 
     fn consume_single_line_comment(&mut self) -> TokenKind {
         self.consume();
         self.consume();
         let mut comment: String = "".to_string();
-        while let Some(c) = self.consume() {
-            comment.push(c);
+        // the line break is left for the whitespace token
+        while let Some(c) = self.current_char() {
             if Self::is_linebreak(&c) {
                 break;
             }
+            comment.push(c);
+            self.consume();
         }
         TokenKind::SingleLineComment(comment)
     }
 
     fn consume_multi_line_comment(&mut self) -> TokenKind {
+        let start = self.cursor;
         self.consume();
         self.consume();
         let mut comment: String = "".to_string();
+        // block comments nest, `/* a /* b */ c */` is a single comment
+        let mut depth = 1;
         while let Some(c) = self.consume() {
-            if c == '*' {
-                if let Some(slash) = self.current_char() {
-                    if slash == '/' {
-                        self.consume();
-                        break;
-                    }
+            if c == '/' && self.current_char() == Some('*') {
+                self.consume();
+                depth += 1;
+                comment.push_str("/*");
+                continue;
+            }
+            if c == '*' && self.current_char() == Some('/') {
+                self.consume();
+                depth -= 1;
+                if depth == 0 {
+                    break;
                 }
+                comment.push_str("*/");
+                continue;
             }
             comment.push(c);
         }
+
+        if depth > 0 {
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_unterminated_block_comment(self.span(start, start + 2));
+        }
         TokenKind::MultiLineComment(comment)
     }
 
-    fn consume_number(&mut self) -> TokenKind {
-        let mut integer_part: i64 = 0;
-        let mut fractional_part: i64 = 0;
-        let mut divisior_for_fraction: i64 = 1;
+    // the escape sequences are decoded, the span keeps the source text
+    fn consume_string(&mut self) -> TokenKind {
+        let start = self.cursor;
+        self.consume();
+        let mut string = String::new();
+        let mut terminated = false;
+        while let Some(c) = self.consume() {
+            match c {
+                '"' => {
+                    terminated = true;
+                    break;
+                }
+                '\\' => string.push(self.consume_escape()),
+                c => string.push(c),
+            }
+        }
+        // the rest of the source is part of the string
+        if !terminated {
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_unterminated_string_literal(self.span(start, start + 1));
+        }
+        TokenKind::StringLiteral(string)
+    }
 
-        let mut oct_format = false;
-        let mut hex_format = false;
-        if self.current_char().unwrap() == '0' {
+    // `'a'` or an escape sequence like `'\n'`, the literal has to hold exactly
+    // one character and ends at the line break
+    fn consume_char(&mut self) -> TokenKind {
+        let start = self.cursor;
+        self.consume();
+        let mut chars: Vec<char> = Vec::new();
+        let mut terminated = false;
+        while let Some(c) = self.current_char() {
+            if Self::is_linebreak(&c) {
+                break;
+            }
             self.consume();
-            oct_format = true;
+            match c {
+                '\'' => {
+                    terminated = true;
+                    break;
+                }
+                '\\' => chars.push(self.consume_escape()),
+                c => chars.push(c),
+            }
+        }
+
+        let span = self.span(start, self.cursor);
+        if !terminated {
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_unterminated_char_literal(span);
+        } else if chars.len() != 1 {
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_invalid_char_literal(span);
+        }
+        TokenKind::CharLiteral(chars.first().copied().unwrap_or('\0'))
+    }
+
+    // the character after a backslash, an unknown escape stands for itself
+    fn consume_escape(&mut self) -> char {
+        let start = self.cursor - 1;
+        let Some(c) = self.current_char() else {
+            return '\\';
+        };
+        if Self::is_linebreak(&c) {
+            return '\\';
+        }
+        self.consume();
+        match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' | '\'' | '"' => c,
+            'u' => self.consume_unicode_escape(start),
+            _ => {
+                self.diagnostics_colletion
+                    .borrow_mut()
+                    .report_unknown_escape_sequence(
+                        &self.input[start..self.cursor],
+                        self.span(start, self.cursor),
+                    );
+                c
+            }
+        }
+    }
 
-            if self.current_char().unwrap() == 'x' {
+    // `\u{1F600}`, the code point in up to six hex digits
+    fn consume_unicode_escape(&mut self, start: usize) -> char {
+        let mut digits = String::new();
+        let mut closed = false;
+        if self.current_char() == Some('{') {
+            self.consume();
+            while let Some(c) = self.current_char() {
+                if c == '}' {
+                    self.consume();
+                    closed = true;
+                    break;
+                }
+                if !c.is_ascii_hexdigit() {
+                    break;
+                }
                 self.consume();
-                hex_format = true;
-                oct_format = false;
+                digits.push(c);
+            }
+        }
+        let c = if closed && digits.len() <= 6 {
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+        } else {
+            None
+        };
+        c.unwrap_or_else(|| {
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_invalid_unicode_escape(
+                    &self.input[start..self.cursor],
+                    self.span(start, self.cursor),
+                );
+            char::REPLACEMENT_CHARACTER
+        })
+    }
+
+    fn consume_number(&mut self) -> TokenKind {
+        if self.current_char() == Some('0') {
+            let radix = match self.peek(1) {
+                Some('x') | Some('X') => Some(16),
+                Some('b') | Some('B') => Some(2),
+                Some(c) if c.is_ascii_digit() => Some(8),
+                _ => None,
+            };
+            if let Some(radix) = radix {
+                return self.consume_radix_integer(radix);
             }
         }
 
+        let start = self.cursor;
+        let mut integer_part: Option<i64> = Some(0);
+        // the literal without digit separators, a floating literal is parsed
+        // from it so it's the float closest to what was written
+        let mut digits = String::new();
         let mut dot_found = false;
+        let mut previous = None;
 
         while let Some(c) = self.current_char() {
-            if !dot_found && oct_format && c.is_digit(8) {
-                self.consume();
-                integer_part = integer_part * 8 + c.to_digit(8).unwrap() as i64;
-            } else if !dot_found && hex_format && c.is_digit(16) {
-                self.consume();
-                integer_part = integer_part * 16 + c.to_digit(16).unwrap() as i64;
-            } else if c.is_digit(10) {
+            if c == '_' {
+                self.consume_digit_separator(previous, 10);
+            } else if c.is_ascii_digit() {
                 self.consume();
+                digits.push(c);
+                let digit = c.to_digit(10).unwrap() as i64;
                 if !dot_found {
-                    integer_part = integer_part * 10 + c.to_digit(10).unwrap() as i64;
-                } else {
-                    fractional_part = fractional_part * 10 + c.to_digit(10).unwrap() as i64;
-                    divisior_for_fraction *= 10;
+                    integer_part = integer_part
+                        .and_then(|integer| integer.checked_mul(10))
+                        .and_then(|integer| integer.checked_add(digit));
                 }
-            } else if !oct_format
-                && !hex_format
+            } else if !dot_found
                 && Self::is_decimal_dot(&c)
-                && self.peek(1).unwrap().is_digit(10)
+                && self.peek(1).is_some_and(|c| c.is_ascii_digit())
             {
                 self.consume();
-                if dot_found {
-                    break;
-                }
+                digits.push('.');
                 dot_found = true;
             } else {
                 break;
             }
+            previous = Some(c);
         }
-        if dot_found {
-            return TokenKind::Floating(
-                integer_part as f64 + (fractional_part as f64 / divisior_for_fraction as f64),
-            );
+
+        let exponent = match self.current_char() {
+            Some('e') | Some('E') => Some(self.consume_exponent(start)),
+            _ => None,
+        };
+        if dot_found || exponent.is_some() {
+            // only digits, a '.' and the exponent, it always parses
+            let literal = format!("{}e{}", digits, exponent.unwrap_or(0));
+            TokenKind::Floating(literal.parse().unwrap())
         } else {
-            return TokenKind::Integer(integer_part);
+            TokenKind::Integer(integer_part.unwrap_or_else(|| {
+                let span = self.span(start, self.cursor);
+                self.diagnostics_colletion
+                    .borrow_mut()
+                    .report_integer_literal_too_large(span);
+                0
+            }))
         }
     }
 
+    // Exponent of a floating literal: `e`/`E`, an optional sign and decimal digits
+    fn consume_exponent(&mut self, start: usize) -> i32 {
+        self.consume();
+        let negative = match self.current_char() {
+            Some('+') => {
+                self.consume();
+                false
+            }
+            Some('-') => {
+                self.consume();
+                true
+            }
+            _ => false,
+        };
+
+        let mut exponent: i32 = 0;
+        let mut digits = 0;
+        let mut previous = None;
+        while let Some(c) = self.current_char() {
+            if c == '_' && digits > 0 {
+                self.consume_digit_separator(previous, 10);
+            } else if c.is_ascii_digit() {
+                self.consume();
+                exponent = exponent
+                    .saturating_mul(10)
+                    .saturating_add(c.to_digit(10).unwrap() as i32);
+                digits += 1;
+            } else {
+                break;
+            }
+            previous = Some(c);
+        }
+
+        if digits == 0 {
+            let span = self.span(start, self.cursor);
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_missing_exponent_digits(span);
+        }
+        if negative {
+            -exponent
+        } else {
+            exponent
+        }
+    }
+
+    // Integers with a base prefix: `0x`/`0X` hex, `0b`/`0B` binary and `0` octal
+    fn consume_radix_integer(&mut self, radix: u32) -> TokenKind {
+        let start = self.cursor;
+        self.consume();
+        if radix != 8 {
+            self.consume();
+        }
+
+        let radix_name = match radix {
+            16 => "hexadecimal",
+            2 => "binary",
+            _ => "octal",
+        };
+
+        let mut integer: Option<i64> = Some(0);
+        let mut digits = 0;
+        // the leading zero of an octal literal is a digit, other prefixes are not
+        let mut previous = if radix == 8 { Some('0') } else { None };
+        while let Some(c) = self.current_char() {
+            if c == '_' {
+                self.consume_digit_separator(previous, radix);
+                previous = Some(c);
+                continue;
+            } else if c.is_digit(radix) {
+                integer = integer
+                    .and_then(|integer| integer.checked_mul(radix as i64))
+                    .and_then(|integer| integer.checked_add(c.to_digit(radix).unwrap() as i64));
+                digits += 1;
+            } else if c.is_ascii_alphanumeric() {
+                let span = self.span(self.cursor, self.cursor + 1);
+                self.diagnostics_colletion
+                    .borrow_mut()
+                    .report_invalid_digit_in_literal(c, radix_name, span);
+            } else {
+                break;
+            }
+            self.consume();
+            previous = Some(c);
+        }
+
+        if digits == 0 {
+            let span = self.span(start, self.cursor);
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_missing_digits_in_literal(radix_name, span);
+        }
+        TokenKind::Integer(integer.unwrap_or_else(|| {
+            let span = self.span(start, self.cursor);
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_integer_literal_too_large(span);
+            0
+        }))
+    }
+
+    // `_` may only separate two digits, `_1` is an identifier while `1_` and `1__2` are errors
+    fn consume_digit_separator(&mut self, previous: Option<char>, radix: u32) {
+        let after_digit = previous.is_some_and(|c| c.is_digit(radix)) || previous == Some('_');
+        let before_digit = self.peek(1).is_some_and(|c| c.is_digit(radix));
+        if !after_digit || !before_digit {
+            let span = self.span(self.cursor, self.cursor + 1);
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_invalid_digit_separator(span);
+        }
+        self.consume();
+    }
+
     fn consume_identifier(&mut self) -> String {
         let mut identifier = String::new();
         while let Some(c) = self.current_char() {
@@ -418,76 +902,91 @@ impl Lexer {
     fn consume_punctuation(&mut self) -> TokenKind {
         match self.consume().unwrap() {
             '+' => {
-                if self.current_char().unwrap() == '=' {
+                if self.current_char() == Some('=') {
                     self.consume();
                     return TokenKind::PlusEqual;
                 }
                 TokenKind::Plus
             }
             '-' => {
-                if self.current_char().unwrap() == '=' {
+                if self.current_char() == Some('=') {
                     self.consume();
                     return TokenKind::MinusEqual;
                 }
-                if self.current_char().unwrap() == '>' {
+                if self.current_char() == Some('>') {
                     self.consume();
                     return TokenKind::MinusRightAngleBracket;
                 }
                 TokenKind::Minus
             }
             '*' => {
-                if self.current_char().unwrap() == '=' {
+                if self.current_char() == Some('=') {
                     self.consume();
                     return TokenKind::AstriskEqual;
                 }
+                if self.current_char() == Some('*') {
+                    self.consume();
+                    return TokenKind::AstriskAstrisk;
+                }
                 TokenKind::Astrisk
             }
             '/' => {
-                if self.current_char().unwrap() == '=' {
+                if self.current_char() == Some('=') {
                     self.consume();
                     return TokenKind::SlashEqual;
                 }
-                if self.current_char().unwrap() == '/' {
+                if self.current_char() == Some('/') {
                     self.consume();
                     return TokenKind::SlashSlash;
                 }
-                if self.current_char().unwrap() == '*' {
+                if self.current_char() == Some('*') {
                     self.consume();
                     return TokenKind::SlashAstrisk;
                 }
                 TokenKind::Slash
             }
+            '%' => {
+                if self.current_char() == Some('=') {
+                    self.consume();
+                    return TokenKind::PercentEqual;
+                }
+                TokenKind::Percent
+            }
             '=' => {
-                if self.current_char().unwrap() == '=' {
+                if self.current_char() == Some('=') {
                     self.consume();
                     return TokenKind::EqualEqual;
                 }
+                if self.current_char() == Some('>') {
+                    self.consume();
+                    return TokenKind::EqualRightAngleBracket;
+                }
                 TokenKind::Equal
             }
             '|' => {
-                if self.current_char().unwrap() == '=' {
+                if self.current_char() == Some('=') {
                     self.consume();
                     return TokenKind::PipeEqual;
                 }
-                if self.current_char().unwrap() == '|' {
+                if self.current_char() == Some('|') {
                     self.consume();
                     return TokenKind::PipePipe;
                 }
                 TokenKind::Pipe
             }
             '&' => {
-                if self.current_char().unwrap() == '=' {
+                if self.current_char() == Some('=') {
                     self.consume();
-                    return TokenKind::AstriskEqual;
+                    return TokenKind::AmpersandEqual;
                 }
-                if self.current_char().unwrap() == '&' {
+                if self.current_char() == Some('&') {
                     self.consume();
                     return TokenKind::AmpersandAmpersand;
                 }
                 TokenKind::Ampersand
             }
             '^' => {
-                if self.current_char().unwrap() == '^' {
+                if self.current_char() == Some('=') {
                     self.consume();
                     return TokenKind::CaretEqual;
                 }
@@ -495,7 +994,7 @@ impl Lexer {
             }
             '~' => TokenKind::Tilde,
             '!' => {
-                if self.current_char().unwrap() == '=' {
+                if self.current_char() == Some('=') {
                     self.consume();
                     return TokenKind::ExclemationMarkEqual;
                 }
@@ -506,14 +1005,14 @@ impl Lexer {
             '[' => TokenKind::LeftBracket,
             ']' => TokenKind::RightBracket,
             '<' => {
-                if self.current_char().unwrap() == '=' {
+                if self.current_char() == Some('=') {
                     self.consume();
                     return TokenKind::LeftAngleBracketEqual;
                 }
                 TokenKind::LeftAngleBracket
             }
             '>' => {
-                if self.current_char().unwrap() == '=' {
+                if self.current_char() == Some('=') {
                     self.consume();
                     return TokenKind::RightAngleBracketEqual;
                 }
@@ -525,19 +1024,48 @@ impl Lexer {
             '.' => TokenKind::Dot,
             ';' => TokenKind::SemiColon,
             ':' => TokenKind::Colon,
+            '?' => TokenKind::QuestionMark,
             _ => TokenKind::Bad,
         }
     }
 }
 
+// Source text of a character literal, e.g. `'a'` or `'\n'`
+pub(crate) fn char_literal(c: char) -> String {
+    let escaped = match c {
+        '\n' => "\\n".to_string(),
+        '\t' => "\\t".to_string(),
+        '\r' => "\\r".to_string(),
+        '\0' => "\\0".to_string(),
+        '\\' => "\\\\".to_string(),
+        '\'' => "\\'".to_string(),
+        c if c.is_control() => format!("\\u{{{:x}}}", c as u32),
+        c => c.to_string(),
+    };
+    format!("'{}'", escaped)
+}
+
 #[cfg(test)]
 mod test {
     use super::{Lexer, TextSpan, Token, TokenKind};
+    use crate::diagnostics::{DiagnosticsColletion, DiagnosticsColletionCell};
+    use std::{cell::RefCell, rc::Rc};
+
+    fn lex(input: &str) -> (Vec<Token>, DiagnosticsColletionCell) {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut lexer = Lexer::new(input.to_string(), Rc::clone(&diagnostics));
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            tokens.push(token);
+        }
+        (tokens, diagnostics)
+    }
 
     fn verify(input: &str, expected_tokens: Vec<Token>) {
-        let mut lexer = Lexer::new(input.to_string());
+        let (tokens, diagnostics) = lex(input);
+        assert_eq!(diagnostics.borrow().diagnostics.len(), 0);
         let mut lexed_tokens = Vec::new();
-        while let Some(token) = lexer.next_token() {
+        for token in tokens {
             if token.kind == TokenKind::Whitespace {
                 continue;
             }
@@ -561,25 +1089,18 @@ mod test {
                 ex.kind, ac.kind
             );
 
-            if ex.span.literal.len() > 0 {
+            if !ex.literal.is_empty() {
                 assert_eq!(
-                    ac.span.literal, *ex.span.literal,
+                    ac.literal, ex.literal,
                     "Tokens do not match. Expected {:?} but found {:?}",
-                    ex.span.literal, ac.span.literal
+                    ex.literal, ac.literal
                 );
             }
         }
     }
 
     fn token(kind: TokenKind, literal: &str) -> Token {
-        Token {
-            kind,
-            span: TextSpan {
-                start: 0,
-                end: 0,
-                literal: literal.to_string(),
-            },
-        }
+        Token::new(kind, TextSpan::new(0, 0), literal.to_string())
     }
 
     #[test]
@@ -627,6 +1148,303 @@ mod test {
         verify(input, expected_tokens);
     }
 
+    #[test]
+    fn lex_power_operator() {
+        let input = "2 ** 3 * 4;";
+        let expected_tokens = vec![
+            token(TokenKind::Integer(2), "2"),
+            token(TokenKind::AstriskAstrisk, ""),
+            token(TokenKind::Integer(3), "3"),
+            token(TokenKind::Astrisk, ""),
+            token(TokenKind::Integer(4), "4"),
+            token(TokenKind::SemiColon, ""),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_consecutive_signs_separately() {
+        let input = "1--1++1";
+        let expected_tokens = vec![
+            token(TokenKind::Integer(1), "1"),
+            token(TokenKind::Minus, "-"),
+            token(TokenKind::Minus, "-"),
+            token(TokenKind::Integer(1), "1"),
+            token(TokenKind::Plus, "+"),
+            token(TokenKind::Plus, "+"),
+            token(TokenKind::Integer(1), "1"),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_ternary_operator() {
+        let input = "a ? 1 : 2";
+        let expected_tokens = vec![
+            token(TokenKind::Identifier, "a"),
+            token(TokenKind::QuestionMark, "?"),
+            token(TokenKind::Integer(1), "1"),
+            token(TokenKind::Colon, ":"),
+            token(TokenKind::Integer(2), "2"),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_match_arm() {
+        let input = "match x { 1 => {} _ => {} }";
+        let expected_tokens = vec![
+            token(TokenKind::Match, ""),
+            token(TokenKind::Identifier, "x"),
+            token(TokenKind::LeftBrace, ""),
+            token(TokenKind::Integer(1), "1"),
+            token(TokenKind::EqualRightAngleBracket, "=>"),
+            token(TokenKind::LeftBrace, ""),
+            token(TokenKind::RightBrace, ""),
+            token(TokenKind::Identifier, "_"),
+            token(TokenKind::EqualRightAngleBracket, "=>"),
+            token(TokenKind::LeftBrace, ""),
+            token(TokenKind::RightBrace, ""),
+            token(TokenKind::RightBrace, ""),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_compound_assignment_operators() {
+        let input = "+= + -= - *= ** * /= / %= % |= | &= && ^= ^ *";
+        let expected_tokens = vec![
+            token(TokenKind::PlusEqual, ""),
+            token(TokenKind::Plus, ""),
+            token(TokenKind::MinusEqual, ""),
+            token(TokenKind::Minus, ""),
+            token(TokenKind::AstriskEqual, ""),
+            token(TokenKind::AstriskAstrisk, ""),
+            token(TokenKind::Astrisk, ""),
+            token(TokenKind::SlashEqual, ""),
+            token(TokenKind::Slash, ""),
+            token(TokenKind::PercentEqual, ""),
+            token(TokenKind::Percent, ""),
+            token(TokenKind::PipeEqual, ""),
+            token(TokenKind::Pipe, ""),
+            token(TokenKind::AmpersandEqual, ""),
+            token(TokenKind::AmpersandAmpersand, ""),
+            token(TokenKind::CaretEqual, ""),
+            token(TokenKind::Caret, ""),
+            token(TokenKind::Astrisk, ""),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn tokenize_keeps_whitespace_and_eof() {
+        let tokens = Lexer::tokenize("a  // b\n$");
+        let kinds: Vec<TokenKind> = tokens.iter().map(|token| token.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Identifier,
+                TokenKind::Whitespace,
+                TokenKind::Whitespace,
+                TokenKind::SingleLineComment(" b".to_string()),
+                TokenKind::Whitespace,
+                TokenKind::Bad,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn format_token_with_location_and_span() {
+        let tokens = Lexer::tokenize("let x;\n  x += 1.5;");
+        let lines: Vec<String> = tokens
+            .iter()
+            .filter(|token| token.kind != TokenKind::Whitespace)
+            .map(|token| token.to_string())
+            .collect();
+        assert_eq!(
+            lines[0],
+            "   1:1    keyword 'let'                0..3     \"let\""
+        );
+        assert_eq!(
+            lines[4],
+            "   2:5    '+='                        11..13    \"+=\""
+        );
+        assert_eq!(
+            lines[5],
+            "   2:8    float '1.5'                 14..17    \"1.5\""
+        );
+    }
+
+    #[test]
+    fn dump_token_stream_in_order() {
+        let tokens = Lexer::tokenize("1+2");
+        let kinds: Vec<TokenKind> = tokens.iter().map(|token| token.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Integer(1),
+                TokenKind::Plus,
+                TokenKind::Integer(2),
+                TokenKind::Eof,
+            ]
+        );
+        let descriptions: Vec<String> = tokens
+            .iter()
+            .map(|token| token.kind.description())
+            .collect();
+        assert_eq!(
+            descriptions,
+            vec!["int '1'", "'+'", "int '2'", "end of file"]
+        );
+    }
+
+    #[test]
+    fn describe_token_kinds() {
+        assert_eq!(TokenKind::Integer(42).description(), "int '42'");
+        assert_eq!(TokenKind::Func.description(), "keyword 'func'");
+        assert_eq!(TokenKind::F64.description(), "type 'f64'");
+        assert_eq!(
+            TokenKind::StringLiteral("a b".to_string()).description(),
+            "string 'a b'"
+        );
+        assert_eq!(TokenKind::LeftBrace.description(), "'{'");
+        assert_eq!(TokenKind::Identifier.description(), "identifier");
+    }
+
+    #[test]
+    fn lex_unknown_character() {
+        let input = "1 @ 2";
+        let (tokens, diagnostics) = lex(input);
+        let tokens: Vec<&Token> = tokens
+            .iter()
+            .filter(|token| token.kind != TokenKind::Whitespace)
+            .collect();
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens[1].kind, TokenKind::Bad);
+        assert_eq!(tokens[1].span.start, 2);
+        assert_eq!(tokens[1].span.end, 3);
+        assert_eq!(tokens[2].kind, TokenKind::Integer(2));
+        assert_eq!(tokens[3].kind, TokenKind::Eof);
+
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unknown character '@'");
+        assert_eq!(diagnostics[0].span.literal(input), "@");
+    }
+
+    #[test]
+    fn lex_every_operator_character() {
+        for (input, kind) in [
+            ("+", TokenKind::Plus),
+            ("-", TokenKind::Minus),
+            ("*", TokenKind::Astrisk),
+            ("/", TokenKind::Slash),
+            ("%", TokenKind::Percent),
+            ("=", TokenKind::Equal),
+            ("|", TokenKind::Pipe),
+            ("&", TokenKind::Ampersand),
+            ("^", TokenKind::Caret),
+            ("~", TokenKind::Tilde),
+            ("!", TokenKind::ExclemationMark),
+            ("<", TokenKind::LeftAngleBracket),
+            (">", TokenKind::RightAngleBracket),
+            ("(", TokenKind::LeftParen),
+            (")", TokenKind::RightParen),
+            ("{", TokenKind::LeftBrace),
+            ("}", TokenKind::RightBrace),
+            ("[", TokenKind::LeftBracket),
+            ("]", TokenKind::RightBracket),
+            (",", TokenKind::Comma),
+            (".", TokenKind::Dot),
+            (";", TokenKind::SemiColon),
+            (":", TokenKind::Colon),
+        ] {
+            let (tokens, diagnostics) = lex(input);
+            assert_eq!(tokens.len(), 2, "{input}");
+            assert_eq!(tokens[0].kind, kind, "{input}");
+            assert_eq!(tokens[0].literal, input);
+            assert!(diagnostics.borrow().diagnostics.is_empty());
+        }
+    }
+
+    #[test]
+    fn lex_unknown_symbols() {
+        for input in ["@", "$", "#", "`", "\\"] {
+            let (tokens, diagnostics) = lex(input);
+            assert_eq!(tokens[0].kind, TokenKind::Bad, "{input}");
+            let diagnostics = &diagnostics.borrow().diagnostics;
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(
+                diagnostics[0].message,
+                format!("Unknown character '{input}'")
+            );
+        }
+    }
+
+    #[test]
+    fn lex_trailing_slash() {
+        let (tokens, diagnostics) = lex("1 /");
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::Eof);
+        assert_eq!(tokens[tokens.len() - 2].kind, TokenKind::Slash);
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+    }
+
+    #[test]
+    fn lex_relational_operators_with_maximal_munch() {
+        for (input, operator) in [
+            ("a>=b", TokenKind::RightAngleBracketEqual),
+            ("a<=b", TokenKind::LeftAngleBracketEqual),
+            ("a==b", TokenKind::EqualEqual),
+            ("a!=b", TokenKind::ExclemationMarkEqual),
+            ("a>b", TokenKind::RightAngleBracket),
+            ("a<b", TokenKind::LeftAngleBracket),
+        ] {
+            verify(
+                input,
+                vec![
+                    token(TokenKind::Identifier, "a"),
+                    token(operator, ""),
+                    token(TokenKind::Identifier, "b"),
+                    token(TokenKind::Eof, ""),
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn lex_separated_relational_characters() {
+        let input = "a= =b > =c ===d !!=e";
+        let expected_tokens = vec![
+            token(TokenKind::Identifier, "a"),
+            token(TokenKind::Equal, ""),
+            token(TokenKind::Equal, ""),
+            token(TokenKind::Identifier, "b"),
+            token(TokenKind::RightAngleBracket, ""),
+            token(TokenKind::Equal, ""),
+            token(TokenKind::Identifier, "c"),
+            token(TokenKind::EqualEqual, ""),
+            token(TokenKind::Equal, ""),
+            token(TokenKind::Identifier, "d"),
+            token(TokenKind::ExclemationMark, ""),
+            token(TokenKind::ExclemationMarkEqual, ""),
+            token(TokenKind::Identifier, "e"),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
     #[test]
     fn lex_basic_func_decl_statements() {
         let input = "func _my_func(a: str, b: u8) -> str {
@@ -657,4 +1475,410 @@ mod test {
 
         verify(input, expected_tokens);
     }
+
+    #[test]
+    fn lex_string_literal() {
+        let input = "let s: str = \"a b\";";
+        let expected_tokens = vec![
+            token(TokenKind::Let, ""),
+            token(TokenKind::Identifier, "s"),
+            token(TokenKind::Colon, ""),
+            token(TokenKind::Str, ""),
+            token(TokenKind::Equal, ""),
+            token(TokenKind::StringLiteral("a b".to_string()), "\"a b\""),
+            token(TokenKind::SemiColon, ""),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_single_line_comment() {
+        let input = "let a: u8 = 1; // trailing comment\n// only comment\n";
+        let expected_tokens = vec![
+            token(TokenKind::Let, ""),
+            token(TokenKind::Identifier, "a"),
+            token(TokenKind::Colon, ""),
+            token(TokenKind::U8, ""),
+            token(TokenKind::Equal, ""),
+            token(TokenKind::Integer(1), ""),
+            token(TokenKind::SemiColon, ""),
+            token(
+                TokenKind::SingleLineComment(" trailing comment".to_string()),
+                "// trailing comment",
+            ),
+            token(
+                TokenKind::SingleLineComment(" only comment".to_string()),
+                "// only comment",
+            ),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_with_trivia() {
+        let input = "// leading\nlet a: i32 = 1; // trailing\n\n/* b */ a;\n// end\n";
+        let tokens = Lexer::tokenize_with_trivia(input);
+        let literals = |trivia: &[Token]| -> Vec<String> {
+            trivia.iter().map(|token| token.literal.clone()).collect()
+        };
+
+        assert_eq!(tokens[0].token.kind, TokenKind::Let);
+        assert_eq!(
+            literals(&tokens[0].leading_trivia),
+            vec!["// leading", "\n"]
+        );
+        assert_eq!(literals(&tokens[0].trailing_trivia), vec![" "]);
+
+        assert_eq!(tokens[6].token.kind, TokenKind::SemiColon);
+        assert_eq!(
+            literals(&tokens[6].trailing_trivia),
+            vec![" ", "// trailing"]
+        );
+
+        assert_eq!(tokens[7].token.kind, TokenKind::Identifier);
+        assert_eq!(
+            literals(&tokens[7].leading_trivia),
+            vec!["\n", "\n", "/* b */", " "]
+        );
+
+        let eof = tokens.last().unwrap();
+        assert_eq!(eof.token.kind, TokenKind::Eof);
+        assert_eq!(literals(&eof.leading_trivia), vec!["\n", "// end", "\n"]);
+        assert_eq!(tokens.len(), 10);
+    }
+
+    #[test]
+    fn lex_nested_multi_line_comment() {
+        let input = "/* a /* b */\n c */ 1";
+        let expected_tokens = vec![
+            token(
+                TokenKind::MultiLineComment(" a /* b */\n c ".to_string()),
+                "/* a /* b */\n c */",
+            ),
+            token(TokenKind::Integer(1), ""),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_unterminated_multi_line_comment() {
+        let (tokens, diagnostics) = lex("1 /* a /* b */");
+        assert_eq!(
+            tokens[2].kind,
+            TokenKind::MultiLineComment(" a /* b */".to_string())
+        );
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unterminated block comment");
+        assert_eq!(diagnostics[0].span.start, 2);
+    }
+
+    #[test]
+    fn lex_unterminated_string_literal() {
+        let input = "let s: str = \"abc;\n1;";
+        let (tokens, diagnostics) = lex(input);
+        // the string runs up to the end of the source
+        assert_eq!(
+            tokens[tokens.len() - 2].kind,
+            TokenKind::StringLiteral("abc;\n1;".to_string())
+        );
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unterminated string literal");
+        assert_eq!(diagnostics[0].span.start, 13);
+        assert_eq!(diagnostics[0].span.literal(input), "\"");
+    }
+
+    #[test]
+    fn lex_char_literals() {
+        let input = "'a' '\\n' '\\'' '\\\\'";
+        let expected_tokens = vec![
+            token(TokenKind::CharLiteral('a'), "'a'"),
+            token(TokenKind::CharLiteral('\n'), "'\\n'"),
+            token(TokenKind::CharLiteral('\''), "'\\''"),
+            token(TokenKind::CharLiteral('\\'), "'\\\\'"),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_unterminated_char_literal() {
+        let input = "'a\n1";
+        let (tokens, diagnostics) = lex(input);
+        assert_eq!(tokens[0].kind, TokenKind::CharLiteral('a'));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unterminated character literal");
+        assert_eq!(diagnostics[0].span.literal(input), "'a");
+    }
+
+    #[test]
+    fn lex_invalid_char_literals() {
+        for input in ["'ab'", "''", "'\\q'"] {
+            let (_, diagnostics) = lex(input);
+            assert_eq!(diagnostics.borrow().diagnostics.len(), 1, "{input}");
+        }
+    }
+
+    #[test]
+    fn lex_radix_integer_literals() {
+        let input = "0xFF 0XfF 0b1010 0B1 017 0 0.5";
+        let expected_tokens = vec![
+            token(TokenKind::Integer(255), "0xFF"),
+            token(TokenKind::Integer(255), "0XfF"),
+            token(TokenKind::Integer(10), "0b1010"),
+            token(TokenKind::Integer(1), "0B1"),
+            token(TokenKind::Integer(15), "017"),
+            token(TokenKind::Integer(0), "0"),
+            token(TokenKind::Floating(0.5), "0.5"),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_invalid_hexadecimal_digit() {
+        let (tokens, diagnostics) = lex("0x1G + 1");
+        assert_eq!(tokens[0].kind, TokenKind::Integer(1));
+        assert_eq!(tokens[0].literal, "0x1G");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Invalid digit 'G' in hexadecimal literal"
+        );
+        assert_eq!(diagnostics[0].span.start, 3);
+    }
+
+    #[test]
+    fn lex_invalid_binary_digit() {
+        let (_, diagnostics) = lex("0b102");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Invalid digit '2' in binary literal"
+        );
+        assert_eq!(diagnostics[0].span.start, 4);
+    }
+
+    #[test]
+    fn lex_missing_hexadecimal_digits() {
+        let (_, diagnostics) = lex("0x;");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Missing digits in hexadecimal literal"
+        );
+    }
+
+    #[test]
+    fn lex_radix_integer_literal_too_large() {
+        let input = "0x8000000000000000;";
+        let (tokens, diagnostics) = lex(input);
+        assert_eq!(tokens[0].kind, TokenKind::Integer(0));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Integer literal is too large");
+        assert_eq!(diagnostics[0].span.literal(input), "0x8000000000000000");
+
+        let (tokens, diagnostics) = lex("0x7FFFFFFFFFFFFFFF");
+        assert_eq!(tokens[0].kind, TokenKind::Integer(i64::MAX));
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+    }
+
+    #[test]
+    fn lex_integer_literal_too_large() {
+        for input in ["9223372036854775808;", "10_000_000_000_000_000_000;"] {
+            let (tokens, diagnostics) = lex(input);
+            assert_eq!(tokens[0].kind, TokenKind::Integer(0), "{}", input);
+            let diagnostics = &diagnostics.borrow().diagnostics;
+            assert_eq!(diagnostics.len(), 1, "{}", input);
+            assert_eq!(diagnostics[0].message, "Integer literal is too large");
+            assert_eq!(
+                diagnostics[0].span.literal(input),
+                input.trim_end_matches(';')
+            );
+        }
+
+        let (tokens, diagnostics) = lex("9223372036854775807 99999999999999999999.5");
+        assert_eq!(tokens[0].kind, TokenKind::Integer(i64::MAX));
+        assert_eq!(tokens[2].kind, TokenKind::Floating(99999999999999999999.5));
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+    }
+
+    #[test]
+    fn lex_digit_separators() {
+        let input = "1_000_000 2.718_5 0xFF_FF 0b1010_1010 _1";
+        let expected_tokens = vec![
+            token(TokenKind::Integer(1_000_000), "1_000_000"),
+            token(TokenKind::Floating(2.718_5), "2.718_5"),
+            token(TokenKind::Integer(0xFFFF), "0xFF_FF"),
+            token(TokenKind::Integer(0b1010_1010), "0b1010_1010"),
+            token(TokenKind::Identifier, "_1"),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_exponent_literals() {
+        let input = "1e10 2.5e-3 6.022E23 1.5e+2";
+        let expected_tokens = vec![
+            token(TokenKind::Floating(1e10), "1e10"),
+            token(TokenKind::Floating(0.0025), "2.5e-3"),
+            token(TokenKind::Floating(6.022e23), "6.022E23"),
+            token(TokenKind::Floating(150.0), "1.5e+2"),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_exponent_literals_exactly() {
+        let input = "1.1e-1 3e-5 1_2.5e-1_0";
+        let expected_tokens = vec![
+            token(TokenKind::Floating(0.11), "1.1e-1"),
+            token(TokenKind::Floating(0.00003), "3e-5"),
+            token(TokenKind::Floating(0.00000000125), "1_2.5e-1_0"),
+            token(TokenKind::Eof, ""),
+        ];
+
+        verify(input, expected_tokens);
+    }
+
+    #[test]
+    fn lex_missing_exponent_digits() {
+        for input in ["1e", "1e+", "2.5E-;"] {
+            let (tokens, diagnostics) = lex(input);
+            assert!(
+                matches!(tokens[0].kind, TokenKind::Floating(_)),
+                "{}",
+                input
+            );
+            let diagnostics = &diagnostics.borrow().diagnostics;
+            assert_eq!(diagnostics.len(), 1, "{}", input);
+            assert_eq!(
+                diagnostics[0].message,
+                "Missing digits in exponent of floating literal"
+            );
+            assert_eq!(diagnostics[0].span.start, 0, "{}", input);
+        }
+    }
+
+    #[test]
+    fn lex_invalid_digit_separators() {
+        for (input, value, position) in [("1_", 1, 1), ("1__2", 12, 1), ("0x_F", 15, 2)] {
+            let (tokens, diagnostics) = lex(input);
+            assert_eq!(tokens[0].kind, TokenKind::Integer(value));
+            let diagnostics = &diagnostics.borrow().diagnostics;
+            assert_eq!(diagnostics.len(), 1, "{}", input);
+            assert_eq!(diagnostics[0].span.start, position, "{}", input);
+        }
+    }
+
+    #[test]
+    fn lex_line_and_column() {
+        let (tokens, _) = lex("let a: i32 = 1;\n  return a;");
+        let locations: Vec<(&str, usize, usize)> = tokens
+            .iter()
+            .filter(|token| token.kind != TokenKind::Whitespace && token.kind != TokenKind::Eof)
+            .map(|token| (token.literal.as_str(), token.span.line, token.span.column))
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                ("let", 1, 1),
+                ("a", 1, 5),
+                (":", 1, 6),
+                ("i32", 1, 8),
+                ("=", 1, 12),
+                ("1", 1, 14),
+                (";", 1, 15),
+                ("return", 2, 3),
+                ("a", 2, 10),
+                (";", 2, 11),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_string_escape_sequences() {
+        let (tokens, diagnostics) = lex("\"a\\nb\"");
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        let TokenKind::StringLiteral(string) = &tokens[0].kind else {
+            panic!("Expected string literal but found {:?}", tokens[0].kind);
+        };
+        assert_eq!(string.chars().count(), 3);
+        assert_eq!(string, "a\nb");
+        assert_eq!(tokens[0].literal, "\"a\\nb\"");
+
+        let (tokens, diagnostics) = lex("\"\\t\\\"\\\\\\u{41}\\u{1F600}\"");
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        assert_eq!(
+            tokens[0].kind,
+            TokenKind::StringLiteral("\t\"\\A\u{1F600}".to_string())
+        );
+    }
+
+    #[test]
+    fn lex_invalid_string_escape_sequences() {
+        let (_, diagnostics) = lex("\"ab\\qc\"");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unknown escape sequence '\\q'");
+        assert_eq!(diagnostics[0].span.start, 3);
+
+        for input in [
+            "\"\\u{}\"",
+            "\"\\u{D800}\"",
+            "\"\\u41\"",
+            "\"\\u{1234567}\"",
+        ] {
+            let (_, diagnostics) = lex(input);
+            let diagnostics = &diagnostics.borrow().diagnostics;
+            assert_eq!(diagnostics.len(), 1, "{input}");
+            assert!(
+                diagnostics[0].span.literal(input).starts_with("\\u"),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn lex_multi_byte_characters() {
+        let input = "let ä = \"héllo\"; ö";
+        let (tokens, diagnostics) = lex(input);
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        let string = &tokens[6];
+        assert_eq!(string.kind, TokenKind::StringLiteral("héllo".to_string()));
+        assert_eq!(&input[string.span.start..string.span.end], "\"héllo\"");
+
+        let last = &tokens[tokens.len() - 2];
+        assert_eq!(last.literal, "ö");
+        assert_eq!((last.span.line, last.span.column), (1, 18));
+        assert_eq!(TextSpan::line_column(input, last.span.start), (1, 18));
+    }
+
+    #[test]
+    fn convert_offset_to_line_and_column() {
+        let input = "a\nbc\n\nd";
+        assert_eq!(TextSpan::line_column(input, 0), (1, 1));
+        assert_eq!(TextSpan::line_column(input, 3), (2, 2));
+        assert_eq!(TextSpan::line_column(input, 5), (3, 1));
+        assert_eq!(TextSpan::line_column(input, 6), (4, 1));
+    }
 }
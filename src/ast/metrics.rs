@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+
+use super::{
+    lexer::Token, ASTExpression, ASTExpressionKind, ASTStatement, ASTStatementKind, ASTVisitor, Ast,
+};
+
+// Complexity overview of a program, node kinds are named like in the JSON output
+#[derive(Debug, Default, PartialEq)]
+pub struct AstMetrics {
+    pub statements: BTreeMap<&'static str, usize>,
+    pub expressions: BTreeMap<&'static str, usize>,
+    // a literal on its own has depth 1
+    pub max_expression_depth: usize,
+    pub functions: usize,
+}
+
+#[allow(dead_code)]
+impl AstMetrics {
+    pub fn statement_count(&self) -> usize {
+        self.statements.values().sum()
+    }
+
+    pub fn expression_count(&self) -> usize {
+        self.expressions.values().sum()
+    }
+}
+
+pub struct MetricsVisitor {
+    metrics: AstMetrics,
+    expression_depth: usize,
+}
+
+impl MetricsVisitor {
+    pub fn new() -> Self {
+        Self {
+            metrics: AstMetrics::default(),
+            expression_depth: 0,
+        }
+    }
+
+    pub fn collect(mut self, ast: &Ast) -> AstMetrics {
+        ast.visit(&mut self);
+        self.metrics
+    }
+
+    fn statement_kind(statement: &ASTStatement) -> &'static str {
+        match statement.kind {
+            ASTStatementKind::Expr(_) => "Expression",
+            ASTStatementKind::Let(_) => "Let",
+            ASTStatementKind::Var(_) => "Var",
+            ASTStatementKind::Return(_) => "Return",
+            ASTStatementKind::Compound(_) => "Compound",
+            ASTStatementKind::FuncDecl(_) => "Function",
+            ASTStatementKind::If(_) => "If",
+            ASTStatementKind::While(_) => "While",
+            ASTStatementKind::For(_) => "For",
+            ASTStatementKind::Break(_) => "Break",
+            ASTStatementKind::Continue(_) => "Continue",
+            ASTStatementKind::Assert(_) => "Assert",
+            ASTStatementKind::Match(_) => "Match",
+        }
+    }
+
+    fn expression_kind(expr: &ASTExpression) -> &'static str {
+        match expr.kind {
+            ASTExpressionKind::IntegerLiteral(_) => "Integer",
+            ASTExpressionKind::FloatingLiteral(_) => "Floating",
+            ASTExpressionKind::BooleanLiteral(_) => "Boolean",
+            ASTExpressionKind::CharLiteral(_) => "Char",
+            ASTExpressionKind::StringLiteral(_) => "String",
+            ASTExpressionKind::Unary(_) => "Unary",
+            ASTExpressionKind::Binary(_) => "Binary",
+            ASTExpressionKind::Parenthesized(_) => "Parenthesized",
+            ASTExpressionKind::Tuple(_) => "Tuple",
+            ASTExpressionKind::Ternary(_) => "Ternary",
+            ASTExpressionKind::Variable(_) => "Variable",
+            ASTExpressionKind::Assignment(_) => "Assignment",
+            ASTExpressionKind::FunctionCall(_) => "FunctionCall",
+            ASTExpressionKind::Block(_) => "Block",
+            ASTExpressionKind::Error(_) => "Error",
+        }
+    }
+}
+
+impl ASTVisitor for MetricsVisitor {
+    fn visit_statement(&mut self, statement: &ASTStatement) {
+        *self
+            .metrics
+            .statements
+            .entry(Self::statement_kind(statement))
+            .or_insert(0) += 1;
+        self.do_visit_statement(statement);
+    }
+
+    fn visit_expression(&mut self, expr: &ASTExpression) {
+        *self
+            .metrics
+            .expressions
+            .entry(Self::expression_kind(expr))
+            .or_insert(0) += 1;
+        self.expression_depth += 1;
+        self.metrics.max_expression_depth =
+            self.metrics.max_expression_depth.max(self.expression_depth);
+        self.do_visit_expression(expr);
+        self.expression_depth -= 1;
+    }
+
+    fn visit_return_statement(&mut self, statement: &super::ASTReturnStatement) {
+        self.visit_expression(&statement.expr);
+    }
+
+    fn visit_let_statement(&mut self, statement: &super::ASTLetStatement) {
+        self.visit_expression(&statement.initializer);
+    }
+
+    fn visit_var_statement(&mut self, statement: &super::ASTVarStatement) {
+        self.visit_expression(&statement.initializer);
+    }
+
+    fn visit_if_statement(&mut self, statement: &super::ASTIfStatement) {
+        self.visit_expression(&statement.condition);
+        self.visit_statement(&statement.then_branch);
+        if let Some(else_branch) = &statement.else_branch {
+            self.visit_statement(&else_branch.else_branch);
+        }
+    }
+
+    fn visit_for_loop_statement(&mut self, statement: &super::ASTForStatement) {
+        self.visit_expression(&statement.range.0);
+        self.visit_expression(&statement.range.1);
+        self.visit_statement(&statement.body);
+    }
+
+    fn visit_while_loop_statement(&mut self, statement: &super::ASTWhileStatement) {
+        self.visit_expression(&statement.condition);
+        self.visit_statement(&statement.body);
+    }
+
+    fn visit_assert_statement(&mut self, statement: &super::ASTAssertStatement) {
+        self.visit_expression(&statement.condition);
+    }
+
+    fn visit_funtion_statement(&mut self, function: &super::ASTFunctionStatement) {
+        self.metrics.functions += 1;
+        for argument in function.arguments.iter() {
+            if let Some(default) = &argument.default {
+                self.visit_expression(default);
+            }
+        }
+        self.visit_statement(&function.body);
+    }
+
+    fn visit_assignment_expression(&mut self, expr: &super::ASTAssignmentExpression) {
+        self.visit_expression(&expr.expr);
+    }
+
+    fn visit_function_call_expression(&mut self, expr: &super::ASTFunctionCallExpression) {
+        for argument in expr.arguments.iter() {
+            self.visit_expression(argument);
+        }
+    }
+
+    fn visit_variable_expression(&mut self, _expr: &super::ASTVariableExpression) {}
+
+    fn visit_unary_expression(&mut self, expr: &super::ASTUnaryExpression) {
+        self.visit_expression(&expr.expr);
+    }
+
+    fn visit_binary_expression(&mut self, expr: &super::ASTBinaryExpression) {
+        self.visit_expression(&expr.left);
+        self.visit_expression(&expr.right);
+    }
+
+    fn visit_parenthesised_expression(&mut self, expr: &super::ASTParenthesizedExpression) {
+        self.visit_expression(&expr.expr);
+    }
+
+    fn visit_binary_operator(&mut self, _op: &super::ASTBinaryOperator) {}
+
+    fn visit_integer(&mut self, _integer: &i64) {}
+
+    fn visit_float(&mut self, _float: &f64) {}
+
+    fn visit_boolean(&mut self, _boolean: &bool) {}
+
+    fn visit_string(&mut self, _string: &Token) {}
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+    use super::AstMetrics;
+    use crate::ast::parser::Parser;
+    use crate::diagnostics::DiagnosticsColletion;
+
+    fn metrics(input: &str) -> AstMetrics {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        ast.metrics()
+    }
+
+    #[test]
+    fn should_count_nodes_of_program() {
+        let metrics = metrics(
+            "\
+            func f(x) -> i32 { if x > 0 { return x * (x + 1); } return 0; }
+            let a: i32 = f(2);
+            ",
+        );
+
+        assert_eq!(
+            metrics.statements,
+            BTreeMap::from([
+                ("Compound", 2),
+                ("Function", 1),
+                ("If", 1),
+                ("Let", 1),
+                ("Return", 2),
+            ])
+        );
+        assert_eq!(
+            metrics.expressions,
+            BTreeMap::from([
+                ("Binary", 3),
+                ("FunctionCall", 1),
+                ("Integer", 4),
+                ("Parenthesized", 1),
+                ("Variable", 3),
+            ])
+        );
+        assert_eq!(metrics.statement_count(), 7);
+        assert_eq!(metrics.expression_count(), 12);
+        assert_eq!(metrics.max_expression_depth, 4);
+        assert_eq!(metrics.functions, 1);
+    }
+
+    #[test]
+    fn should_count_default_arguments() {
+        let metrics = metrics("func f(a = 1 + (2 * 3)) { }");
+        assert_eq!(
+            metrics.expressions,
+            BTreeMap::from([("Binary", 2), ("Integer", 3), ("Parenthesized", 1)])
+        );
+        assert_eq!(metrics.max_expression_depth, 4);
+        assert_eq!(metrics.functions, 1);
+    }
+
+    #[test]
+    fn should_report_empty_program() {
+        assert_eq!(metrics(""), AstMetrics::default());
+    }
+}
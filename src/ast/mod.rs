@@ -1,12 +1,33 @@
+use constant_folder::ConstantFolder;
+use formatter::Formatter;
+use json::ASTJsonSerializer;
 use lexer::{TextSpan, Token};
+use metrics::{AstMetrics, MetricsVisitor};
 use printer::ASTTreePrinter;
-
+use resolver::ResolvedVariable;
+use symbol_checker::{SymbolChecker, SymbolInfo};
+
+use crate::diagnostics::DiagnosticsColletion;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+pub mod builtins;
+pub mod constant_folder;
+pub mod formatter;
+pub mod json;
 pub mod lexer;
+pub mod metrics;
+pub mod numeric;
 pub mod parser;
 pub mod printer;
+pub mod resolver;
 pub mod solver;
 pub mod symbol_checker;
+pub mod type_checker;
 
+#[derive(Debug, Clone)]
 pub struct Ast {
     statements: Vec<ASTStatement>,
 }
@@ -22,12 +43,24 @@ impl Ast {
         self.statements.push(statement);
     }
 
+    pub fn statements(&self) -> &[ASTStatement] {
+        &self.statements
+    }
+
     pub fn visit(&self, printer: &mut dyn ASTVisitor) {
         for statement in &self.statements {
             printer.visit_statement(statement);
         }
     }
 
+    // Passes that rewrite nodes in place, e.g. desugaring, use this instead of
+    // rebuilding the tree
+    pub fn visit_mut(&mut self, visitor: &mut dyn ASTVisitorMut) {
+        for statement in self.statements.iter_mut() {
+            visitor.visit_statement(statement);
+        }
+    }
+
     pub fn visualize(&self) {
         let mut printer = ASTTreePrinter::new();
         let decoration = "=".repeat(80);
@@ -35,8 +68,41 @@ impl Ast {
         self.visit(&mut printer);
         println!("{decoration}\n");
     }
+
+    #[allow(dead_code)]
+    pub fn to_json(&self) -> String {
+        ASTJsonSerializer::new().serialize(self)
+    }
+
+    // Returns the program as canonically formatted source text
+    #[allow(dead_code)]
+    pub fn format(&self) -> String {
+        Formatter::new().format(self)
+    }
+
+    #[allow(dead_code)]
+    pub fn metrics(&self) -> AstMetrics {
+        MetricsVisitor::new().collect(self)
+    }
+
+    // Declared functions, variables and parameters in source order, the
+    // diagnostics of the symbol check are dropped
+    #[allow(dead_code)]
+    pub fn symbols(&self) -> Vec<SymbolInfo> {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut checker = SymbolChecker::new(diagnostics);
+        checker.check(self);
+        checker.symbols()
+    }
+
+    // Returns the tree with all expressions over literals folded into a literal
+    pub fn fold_constants(self) -> Ast {
+        ConstantFolder::fold(self)
+    }
 }
 
+// Read-only walk over the tree. Like ASTVisitorMut every method visits the
+// children of its node by default, so a pass only implements what it needs.
 pub trait ASTVisitor {
     fn do_visit_statement(&mut self, statement: &ASTStatement) {
         match &statement.kind {
@@ -49,6 +115,10 @@ pub trait ASTVisitor {
             ASTStatementKind::If(statement) => self.visit_if_statement(statement),
             ASTStatementKind::For(statement) => self.visit_for_loop_statement(statement),
             ASTStatementKind::While(statement) => self.visit_while_loop_statement(statement),
+            ASTStatementKind::Break(statement) => self.visit_break_statement(statement),
+            ASTStatementKind::Continue(statement) => self.visit_continue_statement(statement),
+            ASTStatementKind::Assert(statement) => self.visit_assert_statement(statement),
+            ASTStatementKind::Match(statement) => self.visit_match_statement(statement),
         }
     }
 
@@ -56,13 +126,18 @@ pub trait ASTVisitor {
         match &expr.kind {
             ASTExpressionKind::IntegerLiteral(i) => self.visit_integer(i),
             ASTExpressionKind::FloatingLiteral(f) => self.visit_float(f),
+            ASTExpressionKind::BooleanLiteral(b) => self.visit_boolean(b),
+            ASTExpressionKind::CharLiteral(c) => self.visit_char(c),
             ASTExpressionKind::Variable(expr) => self.visit_variable_expression(expr),
-            ASTExpressionKind::StringLiteral(_) => todo!(),
+            ASTExpressionKind::StringLiteral(token) => self.visit_string(token),
             ASTExpressionKind::Unary(expr) => self.visit_unary_expression(expr),
             ASTExpressionKind::Binary(expr) => self.visit_binary_expression(expr),
             ASTExpressionKind::Parenthesized(expr) => self.visit_parenthesised_expression(expr),
+            ASTExpressionKind::Tuple(expr) => self.visit_tuple_expression(expr),
+            ASTExpressionKind::Ternary(expr) => self.visit_ternary_expression(expr),
             ASTExpressionKind::FunctionCall(expr) => self.visit_function_call_expression(expr),
             ASTExpressionKind::Assignment(expr) => self.visit_assignment_expression(expr),
+            ASTExpressionKind::Block(block) => self.visit_block_expression(block),
             ASTExpressionKind::Error(span) => self.visit_error(span),
         }
     }
@@ -75,42 +150,265 @@ pub trait ASTVisitor {
             self.visit_statement(statement);
         }
     }
+    // a block used as an expression, its value is the trailing expression
+    fn visit_block_expression(&mut self, block: &ASTCompoundStatement) {
+        self.visit_compound_statement(block);
+    }
 
-    fn visit_return_statement(&mut self, statement: &ASTReturnStatement);
+    fn visit_return_statement(&mut self, statement: &ASTReturnStatement) {
+        self.visit_expression(&statement.expr);
+    }
 
-    fn visit_let_statement(&mut self, statement: &ASTLetStatement);
-    fn visit_var_statement(&mut self, statement: &ASTVarStatement);
+    fn visit_let_statement(&mut self, statement: &ASTLetStatement) {
+        self.visit_expression(&statement.initializer);
+    }
+    fn visit_var_statement(&mut self, statement: &ASTVarStatement) {
+        self.visit_expression(&statement.initializer);
+    }
 
-    fn visit_if_statement(&mut self, statement: &ASTIfStatement);
-    fn visit_for_loop_statement(&mut self, statement: &ASTForStatement);
-    fn visit_while_loop_statement(&mut self, statement: &ASTWhileStatement);
+    fn visit_if_statement(&mut self, statement: &ASTIfStatement) {
+        self.visit_expression(&statement.condition);
+        self.visit_statement(&statement.then_branch);
+        if let Some(else_branch) = &statement.else_branch {
+            self.visit_statement(&else_branch.else_branch);
+        }
+    }
+    fn visit_for_loop_statement(&mut self, statement: &ASTForStatement) {
+        self.visit_expression(&statement.range.0);
+        self.visit_expression(&statement.range.1);
+        self.visit_statement(&statement.body);
+    }
+    fn visit_while_loop_statement(&mut self, statement: &ASTWhileStatement) {
+        self.visit_expression(&statement.condition);
+        self.visit_statement(&statement.body);
+    }
+    fn visit_match_statement(&mut self, statement: &ASTMatchStatement) {
+        self.visit_expression(&statement.scrutinee);
+        for arm in statement.arms.iter() {
+            if let Some(pattern) = &arm.pattern {
+                self.visit_expression(pattern);
+            }
+            self.visit_statement(&arm.body);
+        }
+    }
+
+    fn visit_break_statement(&mut self, _statement: &ASTBreakStatement) {}
+    fn visit_continue_statement(&mut self, _statement: &ASTContinueStatement) {}
+    fn visit_assert_statement(&mut self, statement: &ASTAssertStatement) {
+        self.visit_expression(&statement.condition);
+    }
 
     fn visit_funtion_statement(&mut self, function: &ASTFunctionStatement) {
-        if let ASTStatementKind::Compound(statement) = &function.body.kind {
-            self.visit_compound_statement(statement);
+        for argument in function.arguments.iter() {
+            if let Some(default) = &argument.default {
+                self.visit_expression(default);
+            }
         }
+        self.visit_statement(&function.body);
     }
 
     fn visit_expression(&mut self, expr: &ASTExpression) {
         self.do_visit_expression(expr);
     }
 
-    fn visit_assignment_expression(&mut self, expr: &ASTAssignmentExpression);
-    fn visit_function_call_expression(&mut self, expr: &ASTFunctionCallExpression);
-    fn visit_variable_expression(&mut self, expr: &ASTVariableExpression);
+    fn visit_assignment_expression(&mut self, expr: &ASTAssignmentExpression) {
+        self.visit_expression(&expr.expr);
+    }
+    fn visit_function_call_expression(&mut self, expr: &ASTFunctionCallExpression) {
+        for argument in expr.arguments.iter() {
+            self.visit_expression(argument);
+        }
+    }
+    fn visit_variable_expression(&mut self, _expr: &ASTVariableExpression) {}
 
-    fn visit_unary_expression(&mut self, expr: &ASTUnaryExpression);
-    fn visit_binary_expression(&mut self, expr: &ASTBinaryExpression);
-    fn visit_parenthesised_expression(&mut self, expr: &ASTParenthesizedExpression);
-    fn visit_binary_operator(&mut self, op: &ASTBinaryOperator);
+    fn visit_unary_expression(&mut self, expr: &ASTUnaryExpression) {
+        self.visit_expression(&expr.expr);
+    }
+    fn visit_binary_expression(&mut self, expr: &ASTBinaryExpression) {
+        self.visit_expression(&expr.left);
+        self.visit_binary_operator(&expr.operator);
+        self.visit_expression(&expr.right);
+    }
+    fn visit_parenthesised_expression(&mut self, expr: &ASTParenthesizedExpression) {
+        self.visit_expression(&expr.expr);
+    }
+    fn visit_tuple_expression(&mut self, expr: &ASTTupleExpression) {
+        for element in expr.elements.iter() {
+            self.visit_expression(element);
+        }
+    }
+    fn visit_ternary_expression(&mut self, expr: &ASTTernaryExpression) {
+        self.visit_expression(&expr.condition);
+        self.visit_expression(&expr.then_expr);
+        self.visit_expression(&expr.else_expr);
+    }
+    fn visit_binary_operator(&mut self, _op: &ASTBinaryOperator) {}
+
+    fn visit_error(&mut self, _span: &TextSpan) {}
+    fn visit_integer(&mut self, _integer: &i64) {}
+    fn visit_float(&mut self, _float: &f64) {}
+    fn visit_boolean(&mut self, _boolean: &bool) {}
+    fn visit_char(&mut self, _char: &char) {}
+    fn visit_string(&mut self, _string: &Token) {}
+}
 
-    fn visit_error(&mut self, span: &TextSpan) {}
-    fn visit_integer(&mut self, integer: &i64);
-    fn visit_float(&mut self, float: &f64);
+// Mutable counterpart of ASTVisitor. Every method walks the children of its
+// node by default, a pass only overrides the nodes it rewrites. A node can be
+// replaced as a whole in visit_statement and visit_expression.
+pub trait ASTVisitorMut {
+    fn do_visit_statement(&mut self, statement: &mut ASTStatement) {
+        match &mut statement.kind {
+            ASTStatementKind::Expr(expr) => self.visit_expression(expr),
+            ASTStatementKind::Return(statement) => self.visit_return_statement(statement),
+            ASTStatementKind::FuncDecl(statement) => self.visit_funtion_statement(statement),
+            ASTStatementKind::Let(statement) => self.visit_let_statement(statement),
+            ASTStatementKind::Var(statement) => self.visit_var_statement(statement),
+            ASTStatementKind::Compound(statement) => self.visit_compound_statement(statement),
+            ASTStatementKind::If(statement) => self.visit_if_statement(statement),
+            ASTStatementKind::For(statement) => self.visit_for_loop_statement(statement),
+            ASTStatementKind::While(statement) => self.visit_while_loop_statement(statement),
+            ASTStatementKind::Break(statement) => self.visit_break_statement(statement),
+            ASTStatementKind::Continue(statement) => self.visit_continue_statement(statement),
+            ASTStatementKind::Assert(statement) => self.visit_assert_statement(statement),
+            ASTStatementKind::Match(statement) => self.visit_match_statement(statement),
+        }
+    }
+
+    fn do_visit_expression(&mut self, expr: &mut ASTExpression) {
+        match &mut expr.kind {
+            ASTExpressionKind::IntegerLiteral(i) => self.visit_integer(i),
+            ASTExpressionKind::FloatingLiteral(f) => self.visit_float(f),
+            ASTExpressionKind::BooleanLiteral(b) => self.visit_boolean(b),
+            ASTExpressionKind::CharLiteral(c) => self.visit_char(c),
+            ASTExpressionKind::Variable(expr) => self.visit_variable_expression(expr),
+            ASTExpressionKind::StringLiteral(token) => self.visit_string(token),
+            ASTExpressionKind::Unary(expr) => self.visit_unary_expression(expr),
+            ASTExpressionKind::Binary(expr) => self.visit_binary_expression(expr),
+            ASTExpressionKind::Parenthesized(expr) => self.visit_parenthesised_expression(expr),
+            ASTExpressionKind::Tuple(expr) => self.visit_tuple_expression(expr),
+            ASTExpressionKind::Ternary(expr) => self.visit_ternary_expression(expr),
+            ASTExpressionKind::FunctionCall(expr) => self.visit_function_call_expression(expr),
+            ASTExpressionKind::Assignment(expr) => self.visit_assignment_expression(expr),
+            ASTExpressionKind::Block(block) => self.visit_block_expression(block),
+            ASTExpressionKind::Error(span) => self.visit_error(span),
+        }
+    }
+
+    fn visit_statement(&mut self, statement: &mut ASTStatement) {
+        self.do_visit_statement(statement);
+    }
+    fn visit_compound_statement(&mut self, statement: &mut ASTCompoundStatement) {
+        for statement in statement.statements.iter_mut() {
+            self.visit_statement(statement);
+        }
+    }
+    fn visit_block_expression(&mut self, block: &mut ASTCompoundStatement) {
+        self.visit_compound_statement(block);
+    }
+
+    fn visit_return_statement(&mut self, statement: &mut ASTReturnStatement) {
+        self.visit_expression(&mut statement.expr);
+    }
+
+    fn visit_let_statement(&mut self, statement: &mut ASTLetStatement) {
+        self.visit_expression(&mut statement.initializer);
+    }
+    fn visit_var_statement(&mut self, statement: &mut ASTVarStatement) {
+        self.visit_expression(&mut statement.initializer);
+    }
+
+    fn visit_if_statement(&mut self, statement: &mut ASTIfStatement) {
+        self.visit_expression(&mut statement.condition);
+        self.visit_statement(&mut statement.then_branch);
+        if let Some(else_branch) = &mut statement.else_branch {
+            self.visit_statement(&mut else_branch.else_branch);
+        }
+    }
+    fn visit_for_loop_statement(&mut self, statement: &mut ASTForStatement) {
+        self.visit_expression(&mut statement.range.0);
+        self.visit_expression(&mut statement.range.1);
+        self.visit_statement(&mut statement.body);
+    }
+    fn visit_while_loop_statement(&mut self, statement: &mut ASTWhileStatement) {
+        self.visit_expression(&mut statement.condition);
+        self.visit_statement(&mut statement.body);
+    }
+    fn visit_match_statement(&mut self, statement: &mut ASTMatchStatement) {
+        self.visit_expression(&mut statement.scrutinee);
+        for arm in statement.arms.iter_mut() {
+            if let Some(pattern) = &mut arm.pattern {
+                self.visit_expression(pattern);
+            }
+            self.visit_statement(&mut arm.body);
+        }
+    }
+
+    fn visit_break_statement(&mut self, _statement: &mut ASTBreakStatement) {}
+    fn visit_continue_statement(&mut self, _statement: &mut ASTContinueStatement) {}
+    fn visit_assert_statement(&mut self, statement: &mut ASTAssertStatement) {
+        self.visit_expression(&mut statement.condition);
+    }
+
+    fn visit_funtion_statement(&mut self, function: &mut ASTFunctionStatement) {
+        for argument in function.arguments.iter_mut() {
+            if let Some(default) = &mut argument.default {
+                self.visit_expression(default);
+            }
+        }
+        self.visit_statement(&mut function.body);
+    }
+
+    fn visit_expression(&mut self, expr: &mut ASTExpression) {
+        self.do_visit_expression(expr);
+    }
+
+    fn visit_assignment_expression(&mut self, expr: &mut ASTAssignmentExpression) {
+        self.visit_expression(&mut expr.expr);
+    }
+    fn visit_function_call_expression(&mut self, expr: &mut ASTFunctionCallExpression) {
+        for argument in expr.arguments.iter_mut() {
+            self.visit_expression(argument);
+        }
+    }
+    fn visit_variable_expression(&mut self, _expr: &mut ASTVariableExpression) {}
+
+    fn visit_unary_expression(&mut self, expr: &mut ASTUnaryExpression) {
+        self.visit_expression(&mut expr.expr);
+    }
+    fn visit_binary_expression(&mut self, expr: &mut ASTBinaryExpression) {
+        self.visit_expression(&mut expr.left);
+        self.visit_binary_operator(&mut expr.operator);
+        self.visit_expression(&mut expr.right);
+    }
+    fn visit_parenthesised_expression(&mut self, expr: &mut ASTParenthesizedExpression) {
+        self.visit_expression(&mut expr.expr);
+    }
+    fn visit_tuple_expression(&mut self, expr: &mut ASTTupleExpression) {
+        for element in expr.elements.iter_mut() {
+            self.visit_expression(element);
+        }
+    }
+    fn visit_ternary_expression(&mut self, expr: &mut ASTTernaryExpression) {
+        self.visit_expression(&mut expr.condition);
+        self.visit_expression(&mut expr.then_expr);
+        self.visit_expression(&mut expr.else_expr);
+    }
+    fn visit_binary_operator(&mut self, _op: &mut ASTBinaryOperator) {}
+
+    fn visit_error(&mut self, _span: &mut TextSpan) {}
+    fn visit_integer(&mut self, _integer: &mut i64) {}
+    fn visit_float(&mut self, _float: &mut f64) {}
+    fn visit_boolean(&mut self, _boolean: &mut bool) {}
+    fn visit_char(&mut self, _char: &mut char) {}
+    fn visit_string(&mut self, _string: &mut Token) {}
 }
 
-#[derive(Clone)]
-enum ASTStatementKind {
+// The node kinds and the read accessors below are the stable way to walk the
+// tree without implementing ASTVisitor. Every statement and expression has
+// the span of its source text, tokens and data types are not exposed. Nodes
+// can only be created by the parser.
+#[derive(Debug, Clone)]
+pub enum ASTStatementKind {
     Expr(ASTExpression),
     Let(ASTLetStatement),
     Var(ASTVarStatement),
@@ -120,38 +418,97 @@ enum ASTStatementKind {
     If(ASTIfStatement),
     While(ASTWhileStatement),
     For(ASTForStatement),
+    Break(ASTBreakStatement),
+    Continue(ASTContinueStatement),
+    Assert(ASTAssertStatement),
+    Match(ASTMatchStatement),
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct ASTLetStatement {
     identifier: Token,
     data_type: Token,
     initializer: ASTExpression,
 }
 
-#[derive(Clone)]
+impl ASTLetStatement {
+    pub fn identifier(&self) -> &str {
+        &self.identifier.literal
+    }
+
+    pub fn initializer(&self) -> &ASTExpression {
+        &self.initializer
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ASTVarStatement {
     identifier: Token,
     data_type: Token,
     initializer: ASTExpression,
 }
 
-#[derive(Clone)]
+impl ASTVarStatement {
+    pub fn identifier(&self) -> &str {
+        &self.identifier.literal
+    }
+
+    pub fn initializer(&self) -> &ASTExpression {
+        &self.initializer
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ASTReturnStatement {
     expr: ASTExpression,
 }
-#[derive(Clone)]
+
+impl ASTReturnStatement {
+    pub fn expression(&self) -> &ASTExpression {
+        &self.expr
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ASTCompoundStatement {
     statements: Vec<ASTStatement>,
+    // the last statement is an expression without ';'
+    has_value: bool,
+}
+
+impl ASTCompoundStatement {
+    pub fn statements(&self) -> &[ASTStatement] {
+        &self.statements
+    }
+
+    // The trailing expression, `{ let a: i32 = 2; a * a }` has the value `a * a`
+    pub fn value(&self) -> Option<&ASTExpression> {
+        match self.statements.last().map(ASTStatement::kind) {
+            Some(ASTStatementKind::Expr(expr)) if self.has_value => Some(expr),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct FunctionArgumentDeclaration {
     identifier: Token,
-    data_type: Token,
+    data_type: Option<Token>,
+    // evaluated on every call that omits the argument
+    default: Option<ASTExpression>,
+}
+
+impl FunctionArgumentDeclaration {
+    pub fn identifier(&self) -> &str {
+        &self.identifier.literal
+    }
+
+    pub fn default(&self) -> Option<&ASTExpression> {
+        self.default.as_ref()
+    }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct ASTFunctionStatement {
     identifier: Token,
     arguments: Vec<FunctionArgumentDeclaration>,
@@ -159,12 +516,34 @@ pub struct ASTFunctionStatement {
     return_type: Token,
 }
 
-#[derive(Clone)]
+impl ASTFunctionStatement {
+    pub fn identifier(&self) -> &str {
+        &self.identifier.literal
+    }
+
+    pub fn arguments(&self) -> &[FunctionArgumentDeclaration] {
+        &self.arguments
+    }
+
+    pub fn body(&self) -> &ASTStatement {
+        &self.body
+    }
+
+    // number of arguments without a default value
+    fn required_arguments(&self) -> usize {
+        self.arguments
+            .iter()
+            .take_while(|arg| arg.default.is_none())
+            .count()
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ASTElseStatement {
     else_keyword: Token,
     else_branch: Box<ASTStatement>,
 }
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct ASTIfStatement {
     keyword: Token,
     condition: ASTExpression,
@@ -172,14 +551,64 @@ pub struct ASTIfStatement {
     else_branch: Option<ASTElseStatement>,
 }
 
-#[derive(Clone)]
+impl ASTIfStatement {
+    pub fn condition(&self) -> &ASTExpression {
+        &self.condition
+    }
+
+    pub fn then_branch(&self) -> &ASTStatement {
+        &self.then_branch
+    }
+
+    pub fn else_branch(&self) -> Option<&ASTStatement> {
+        self.else_branch
+            .as_ref()
+            .map(|else_branch| else_branch.else_branch.as_ref())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ASTWhileStatement {
     keyword: Token,
     condition: ASTExpression,
     body: Box<ASTStatement>,
 }
 
-#[derive(Clone)]
+impl ASTWhileStatement {
+    pub fn condition(&self) -> &ASTExpression {
+        &self.condition
+    }
+
+    pub fn body(&self) -> &ASTStatement {
+        &self.body
+    }
+}
+
+// Leaves the innermost loop
+#[derive(Debug, Clone)]
+pub struct ASTBreakStatement {
+    keyword: Token,
+}
+
+// Skips the rest of the body, the loop continues with its condition
+#[derive(Debug, Clone)]
+pub struct ASTContinueStatement {
+    keyword: Token,
+}
+
+#[derive(Debug, Clone)]
+pub struct ASTAssertStatement {
+    keyword: Token,
+    condition: ASTExpression,
+}
+
+impl ASTAssertStatement {
+    pub fn condition(&self) -> &ASTExpression {
+        &self.condition
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ASTForStatement {
     keyword: Token,
     loop_variable: Token,
@@ -187,51 +616,113 @@ pub struct ASTForStatement {
     body: Box<ASTStatement>,
 }
 
-#[derive(Clone)]
+#[allow(dead_code)]
+impl ASTForStatement {
+    pub fn loop_variable(&self) -> &str {
+        &self.loop_variable.literal
+    }
+
+    pub fn range(&self) -> (&ASTExpression, &ASTExpression) {
+        (&self.range.0, &self.range.1)
+    }
+
+    pub fn body(&self) -> &ASTStatement {
+        &self.body
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ASTMatchArm {
+    // None for the default arm `_`
+    pattern: Option<ASTExpression>,
+    body: Box<ASTStatement>,
+}
+
+#[allow(dead_code)]
+impl ASTMatchArm {
+    pub fn pattern(&self) -> Option<&ASTExpression> {
+        self.pattern.as_ref()
+    }
+
+    pub fn body(&self) -> &ASTStatement {
+        &self.body
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ASTMatchStatement {
+    keyword: Token,
+    scrutinee: ASTExpression,
+    arms: Vec<ASTMatchArm>,
+}
+
+#[allow(dead_code)]
+impl ASTMatchStatement {
+    pub fn scrutinee(&self) -> &ASTExpression {
+        &self.scrutinee
+    }
+
+    pub fn arms(&self) -> &[ASTMatchArm] {
+        &self.arms
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ASTStatement {
     kind: ASTStatementKind,
+    // the whole statement in the source, empty if it was not parsed
+    span: TextSpan,
 }
 
 impl ASTStatement {
-    fn new(kind: ASTStatementKind) -> Self {
-        Self { kind }
+    pub fn kind(&self) -> &ASTStatementKind {
+        &self.kind
     }
 
-    fn expression(expr: ASTExpression) -> Self {
+    pub fn span(&self) -> &TextSpan {
+        &self.span
+    }
+
+    fn new(kind: ASTStatementKind) -> Self {
         Self {
-            kind: ASTStatementKind::Expr(expr),
+            kind,
+            span: TextSpan::new(0, 0),
         }
     }
 
+    fn with_span(mut self, span: TextSpan) -> Self {
+        self.span = span;
+        self
+    }
+
+    fn expression(expr: ASTExpression) -> Self {
+        Self::new(ASTStatementKind::Expr(expr))
+    }
+
     fn return_statement(expr: ASTExpression) -> Self {
-        Self {
-            kind: ASTStatementKind::Return(ASTReturnStatement { expr }),
-        }
+        Self::new(ASTStatementKind::Return(ASTReturnStatement { expr }))
     }
     fn let_statement(identifier: Token, data_type: Token, initializer: ASTExpression) -> Self {
-        Self {
-            kind: ASTStatementKind::Let(ASTLetStatement {
-                identifier,
-                data_type,
-                initializer,
-            }),
-        }
+        Self::new(ASTStatementKind::Let(ASTLetStatement {
+            identifier,
+            data_type,
+            initializer,
+        }))
     }
 
     fn var_statement(identifier: Token, data_type: Token, initializer: ASTExpression) -> Self {
-        Self {
-            kind: ASTStatementKind::Var(ASTVarStatement {
-                identifier,
-                data_type,
-                initializer,
-            }),
-        }
+        Self::new(ASTStatementKind::Var(ASTVarStatement {
+            identifier,
+            data_type,
+            initializer,
+        }))
     }
 
-    fn compound(statements: Vec<ASTStatement>) -> Self {
-        Self {
-            kind: ASTStatementKind::Compound(ASTCompoundStatement { statements }),
-        }
+    fn compound(statements: Vec<ASTStatement>, has_value: bool) -> Self {
+        Self::new(ASTStatementKind::Compound(ASTCompoundStatement {
+            statements,
+            has_value,
+        }))
     }
 
     fn conditional(
@@ -240,24 +731,43 @@ impl ASTStatement {
         then_branch: ASTStatement,
         else_branch: Option<ASTElseStatement>,
     ) -> Self {
-        Self {
-            kind: ASTStatementKind::If(ASTIfStatement {
-                keyword,
-                condition,
-                then_branch: Box::new(then_branch),
-                else_branch,
-            }),
-        }
+        Self::new(ASTStatementKind::If(ASTIfStatement {
+            keyword,
+            condition,
+            then_branch: Box::new(then_branch),
+            else_branch,
+        }))
     }
 
     fn while_loop(keyword: Token, condition: ASTExpression, body: ASTStatement) -> Self {
-        Self {
-            kind: ASTStatementKind::While(ASTWhileStatement {
-                keyword,
-                condition,
-                body: Box::new(body),
-            }),
-        }
+        Self::new(ASTStatementKind::While(ASTWhileStatement {
+            keyword,
+            condition,
+            body: Box::new(body),
+        }))
+    }
+
+    fn break_statement(keyword: Token) -> Self {
+        Self::new(ASTStatementKind::Break(ASTBreakStatement { keyword }))
+    }
+
+    fn continue_statement(keyword: Token) -> Self {
+        Self::new(ASTStatementKind::Continue(ASTContinueStatement { keyword }))
+    }
+
+    fn assert_statement(keyword: Token, condition: ASTExpression) -> Self {
+        Self::new(ASTStatementKind::Assert(ASTAssertStatement {
+            keyword,
+            condition,
+        }))
+    }
+
+    fn match_statement(keyword: Token, scrutinee: ASTExpression, arms: Vec<ASTMatchArm>) -> Self {
+        Self::new(ASTStatementKind::Match(ASTMatchStatement {
+            keyword,
+            scrutinee,
+            arms,
+        }))
     }
 
     fn for_loop(
@@ -266,14 +776,12 @@ impl ASTStatement {
         range: (ASTExpression, ASTExpression),
         body: ASTStatement,
     ) -> Self {
-        Self {
-            kind: ASTStatementKind::For(ASTForStatement {
-                keyword,
-                loop_variable,
-                range,
-                body: Box::new(body),
-            }),
-        }
+        Self::new(ASTStatementKind::For(ASTForStatement {
+            keyword,
+            loop_variable,
+            range,
+            body: Box::new(body),
+        }))
     }
 
     fn function(
@@ -282,133 +790,205 @@ impl ASTStatement {
         body: ASTStatement,
         return_type: Token,
     ) -> Self {
-        Self {
-            kind: ASTStatementKind::FuncDecl(ASTFunctionStatement {
-                identifier,
-                arguments,
-                body: Box::new(body),
-                return_type,
-            }),
-        }
+        Self::new(ASTStatementKind::FuncDecl(ASTFunctionStatement {
+            identifier,
+            arguments,
+            body: Box::new(body),
+            return_type,
+        }))
     }
 }
 
-#[derive(Clone, PartialEq)]
-enum ASTExpressionKind {
+#[derive(Debug, Clone)]
+pub enum ASTExpressionKind {
     IntegerLiteral(i64),
     FloatingLiteral(f64),
-    StringLiteral(String),
+    BooleanLiteral(bool),
+    CharLiteral(char),
+    StringLiteral(Token),
     Unary(ASTUnaryExpression),
     Binary(ASTBinaryExpression),
     Parenthesized(ASTParenthesizedExpression),
+    Tuple(ASTTupleExpression),
+    Ternary(ASTTernaryExpression),
     Variable(ASTVariableExpression),
     Assignment(ASTAssignmentExpression),
     FunctionCall(ASTFunctionCallExpression),
+    Block(ASTCompoundStatement),
     Error(TextSpan),
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct ASTExpression {
     kind: ASTExpressionKind,
+    // the whole expression in the source, empty if it was not parsed
+    span: TextSpan,
 }
 
 impl ASTExpression {
+    pub fn kind(&self) -> &ASTExpressionKind {
+        &self.kind
+    }
+
+    pub fn span(&self) -> &TextSpan {
+        &self.span
+    }
+
     fn new(kind: ASTExpressionKind) -> Self {
-        Self { kind }
+        Self {
+            kind,
+            span: TextSpan::new(0, 0),
+        }
+    }
+
+    fn with_span(mut self, span: TextSpan) -> Self {
+        self.span = span;
+        self
     }
+
     fn error(span: TextSpan) -> Self {
-        Self {
-            kind: ASTExpressionKind::Error(span),
+        Self::new(ASTExpressionKind::Error(span))
+    }
+
+    // literals and negated numbers, the patterns of match arms
+    fn is_literal(&self) -> bool {
+        match &self.kind {
+            ASTExpressionKind::IntegerLiteral(_)
+            | ASTExpressionKind::FloatingLiteral(_)
+            | ASTExpressionKind::BooleanLiteral(_)
+            | ASTExpressionKind::CharLiteral(_)
+            | ASTExpressionKind::StringLiteral(_) => true,
+            ASTExpressionKind::Unary(unary) => {
+                unary.operator.kind == ASTUnaryOperatorKind::Minus
+                    && matches!(
+                        unary.expr.kind,
+                        ASTExpressionKind::IntegerLiteral(_)
+                            | ASTExpressionKind::FloatingLiteral(_)
+                    )
+            }
+            _ => false,
         }
     }
 
     fn integer(i: i64) -> Self {
-        Self {
-            kind: ASTExpressionKind::IntegerLiteral(i),
-        }
+        Self::new(ASTExpressionKind::IntegerLiteral(i))
     }
     fn float(f: f64) -> Self {
-        Self {
-            kind: ASTExpressionKind::FloatingLiteral(f),
-        }
+        Self::new(ASTExpressionKind::FloatingLiteral(f))
+    }
+    fn boolean(b: bool) -> Self {
+        Self::new(ASTExpressionKind::BooleanLiteral(b))
+    }
+    fn char(c: char) -> Self {
+        Self::new(ASTExpressionKind::CharLiteral(c))
+    }
+    fn string(token: Token) -> Self {
+        Self::new(ASTExpressionKind::StringLiteral(token))
     }
 
     fn identifier(token: Token) -> Self {
-        Self {
-            kind: ASTExpressionKind::Variable(ASTVariableExpression { identifier: token }),
-        }
+        Self::new(ASTExpressionKind::Variable(ASTVariableExpression {
+            identifier: token,
+            resolved: Cell::new(None),
+        }))
     }
 
     fn assignment(token: Token, expr: ASTExpression) -> Self {
-        Self {
-            kind: ASTExpressionKind::Assignment(ASTAssignmentExpression {
-                identifier: token,
-                expr: Box::new(expr),
-            }),
-        }
+        Self::new(ASTExpressionKind::Assignment(ASTAssignmentExpression {
+            identifier: token,
+            expr: Box::new(expr),
+            resolved: Cell::new(None),
+        }))
     }
 
     fn unary(operator: ASTUnaryOperator, expr: ASTExpression) -> Self {
-        Self {
-            kind: ASTExpressionKind::Unary(ASTUnaryExpression {
-                operator,
-                expr: Box::new(expr),
-            }),
-        }
+        Self::new(ASTExpressionKind::Unary(ASTUnaryExpression {
+            operator,
+            expr: Box::new(expr),
+        }))
     }
 
     fn binary(operator: ASTBinaryOperator, left: ASTExpression, right: ASTExpression) -> Self {
-        Self {
-            kind: ASTExpressionKind::Binary(ASTBinaryExpression {
-                operator,
-                left: Box::new(left),
-                right: Box::new(right),
-            }),
-        }
+        Self::new(ASTExpressionKind::Binary(ASTBinaryExpression {
+            operator,
+            left: Box::new(left),
+            right: Box::new(right),
+        }))
     }
 
     fn parenthesized(expr: ASTExpression) -> Self {
-        Self {
-            kind: ASTExpressionKind::Parenthesized(ASTParenthesizedExpression {
+        Self::new(ASTExpressionKind::Parenthesized(
+            ASTParenthesizedExpression {
                 expr: Box::new(expr),
-            }),
-        }
+            },
+        ))
+    }
+
+    fn tuple(elements: Vec<ASTExpression>) -> Self {
+        Self::new(ASTExpressionKind::Tuple(ASTTupleExpression { elements }))
+    }
+
+    fn ternary(
+        condition: ASTExpression,
+        then_expr: ASTExpression,
+        else_expr: ASTExpression,
+    ) -> Self {
+        Self::new(ASTExpressionKind::Ternary(ASTTernaryExpression {
+            condition: Box::new(condition),
+            then_expr: Box::new(then_expr),
+            else_expr: Box::new(else_expr),
+        }))
+    }
+
+    fn block(block: ASTCompoundStatement) -> Self {
+        Self::new(ASTExpressionKind::Block(block))
     }
 
     fn function_call(identifier: Token, arguments: Vec<ASTExpression>) -> Self {
-        Self {
-            kind: ASTExpressionKind::FunctionCall(ASTFunctionCallExpression {
-                identifier,
-                arguments,
-            }),
-        }
+        Self::new(ASTExpressionKind::FunctionCall(ASTFunctionCallExpression {
+            identifier,
+            arguments,
+        }))
     }
 }
 
-#[derive(Clone, PartialEq)]
-enum ASTUnaryOperatorKind {
+#[derive(Debug, Clone, PartialEq)]
+pub enum ASTUnaryOperatorKind {
+    Plus,
     Minus,
     BitwiseNOT,
     LogicNot,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 struct ASTUnaryOperator {
     kind: ASTUnaryOperatorKind,
     token: lexer::Token,
 }
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct ASTUnaryExpression {
     operator: ASTUnaryOperator,
     expr: Box<ASTExpression>,
 }
 
+impl ASTUnaryExpression {
+    pub fn operator(&self) -> &ASTUnaryOperatorKind {
+        &self.operator.kind
+    }
+
+    pub fn operand(&self) -> &ASTExpression {
+        &self.expr
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
-enum ASTBinaryOperatorKind {
+pub enum ASTBinaryOperatorKind {
     Plus,
     Minus,
     Multiply,
     Divide,
+    Modulo,
+    Power,
     EqualTo,
     NotEqualTo,
     LogicAND,
@@ -428,58 +1008,180 @@ pub struct ASTBinaryOperator {
     token: lexer::Token,
 }
 
-impl ASTBinaryOperator {
-    fn precedence(&self) -> u8 {
-        match self.kind {
-            ASTBinaryOperatorKind::Plus => 5,
-            ASTBinaryOperatorKind::Minus => 5,
-            ASTBinaryOperatorKind::Multiply => 6,
-            ASTBinaryOperatorKind::Divide => 6,
-            ASTBinaryOperatorKind::EqualTo => 1,
-            ASTBinaryOperatorKind::NotEqualTo => 1,
-            ASTBinaryOperatorKind::LogicAND => 1,
+impl ASTBinaryOperatorKind {
+    #[allow(dead_code)]
+    pub const ALL: [ASTBinaryOperatorKind; 17] = [
+        ASTBinaryOperatorKind::Plus,
+        ASTBinaryOperatorKind::Minus,
+        ASTBinaryOperatorKind::Multiply,
+        ASTBinaryOperatorKind::Divide,
+        ASTBinaryOperatorKind::Modulo,
+        ASTBinaryOperatorKind::Power,
+        ASTBinaryOperatorKind::EqualTo,
+        ASTBinaryOperatorKind::NotEqualTo,
+        ASTBinaryOperatorKind::LogicAND,
+        ASTBinaryOperatorKind::LogicOR,
+        ASTBinaryOperatorKind::GreaterThan,
+        ASTBinaryOperatorKind::GreaterThanOrEqual,
+        ASTBinaryOperatorKind::LessThan,
+        ASTBinaryOperatorKind::LessThanOrEqual,
+        ASTBinaryOperatorKind::BitwiseOR,
+        ASTBinaryOperatorKind::BitwiseAND,
+        ASTBinaryOperatorKind::BitwiseXOR,
+    ];
+
+    // follows the C ordering, a higher value binds tighter
+    pub fn precedence(&self) -> u8 {
+        match self {
+            ASTBinaryOperatorKind::Power => 10,
+            ASTBinaryOperatorKind::Multiply => 9,
+            ASTBinaryOperatorKind::Divide => 9,
+            ASTBinaryOperatorKind::Modulo => 9,
+            ASTBinaryOperatorKind::Plus => 8,
+            ASTBinaryOperatorKind::Minus => 8,
+            ASTBinaryOperatorKind::GreaterThan => 7,
+            ASTBinaryOperatorKind::GreaterThanOrEqual => 7,
+            ASTBinaryOperatorKind::LessThan => 7,
+            ASTBinaryOperatorKind::LessThanOrEqual => 7,
+            ASTBinaryOperatorKind::EqualTo => 6,
+            ASTBinaryOperatorKind::NotEqualTo => 6,
+            ASTBinaryOperatorKind::BitwiseAND => 5,
+            ASTBinaryOperatorKind::BitwiseXOR => 4,
+            ASTBinaryOperatorKind::BitwiseOR => 3,
+            ASTBinaryOperatorKind::LogicAND => 2,
             ASTBinaryOperatorKind::LogicOR => 1,
-            ASTBinaryOperatorKind::GreaterThan => 1,
-            ASTBinaryOperatorKind::GreaterThanOrEqual => 1,
-            ASTBinaryOperatorKind::LessThan => 1,
-            ASTBinaryOperatorKind::LessThanOrEqual => 1,
-            ASTBinaryOperatorKind::BitwiseOR => 1,
-            ASTBinaryOperatorKind::BitwiseAND => 1,
-            ASTBinaryOperatorKind::BitwiseXOR => 1,
         }
     }
+
+    pub fn associativity(&self) -> ASTBinaryOperatorAssociativity {
+        match self {
+            ASTBinaryOperatorKind::Power => ASTBinaryOperatorAssociativity::Right,
+            _ => ASTBinaryOperatorAssociativity::Left,
+        }
+    }
+}
+
+impl ASTBinaryOperator {
+    fn precedence(&self) -> u8 {
+        self.kind.precedence()
+    }
+
+    fn associativity(&self) -> ASTBinaryOperatorAssociativity {
+        self.kind.associativity()
+    }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ASTBinaryOperatorAssociativity {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone)]
 pub struct ASTBinaryExpression {
     operator: ASTBinaryOperator,
     left: Box<ASTExpression>,
     right: Box<ASTExpression>,
 }
 
-#[derive(Clone, PartialEq)]
+impl ASTBinaryExpression {
+    pub fn operator(&self) -> &ASTBinaryOperatorKind {
+        &self.operator.kind
+    }
+
+    pub fn left(&self) -> &ASTExpression {
+        &self.left
+    }
+
+    pub fn right(&self) -> &ASTExpression {
+        &self.right
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ASTParenthesizedExpression {
     expr: Box<ASTExpression>,
 }
 
-#[derive(Clone, PartialEq)]
+impl ASTParenthesizedExpression {
+    pub fn expression(&self) -> &ASTExpression {
+        &self.expr
+    }
+}
+
+// `(a, b)`, the comma tells it apart from a parenthesized expression
+#[derive(Debug, Clone)]
+pub struct ASTTupleExpression {
+    elements: Vec<ASTExpression>,
+}
+
+impl ASTTupleExpression {
+    pub fn elements(&self) -> &[ASTExpression] {
+        &self.elements
+    }
+}
+
+// `condition ? then : else`, only the chosen branch is evaluated
+#[derive(Debug, Clone)]
+pub struct ASTTernaryExpression {
+    condition: Box<ASTExpression>,
+    then_expr: Box<ASTExpression>,
+    else_expr: Box<ASTExpression>,
+}
+
+impl ASTTernaryExpression {
+    pub fn condition(&self) -> &ASTExpression {
+        &self.condition
+    }
+
+    pub fn then_expression(&self) -> &ASTExpression {
+        &self.then_expr
+    }
+
+    pub fn else_expression(&self) -> &ASTExpression {
+        &self.else_expr
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ASTVariableExpression {
     identifier: Token,
+    // set by the resolver, without it the variable is looked up by name
+    resolved: Cell<Option<ResolvedVariable>>,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct ASTAssignmentExpression {
     identifier: Token,
     expr: Box<ASTExpression>,
+    resolved: Cell<Option<ResolvedVariable>>,
 }
 
 impl ASTVariableExpression {
     pub fn identifier(&self) -> &str {
-        &self.identifier.span.literal
+        &self.identifier.literal
+    }
+
+    pub fn resolved(&self) -> Option<ResolvedVariable> {
+        self.resolved.get()
+    }
+}
+
+impl ASTAssignmentExpression {
+    pub fn identifier(&self) -> &str {
+        &self.identifier.literal
+    }
+
+    pub fn resolved(&self) -> Option<ResolvedVariable> {
+        self.resolved.get()
+    }
+
+    pub fn expression(&self) -> &ASTExpression {
+        &self.expr
     }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct ASTFunctionCallExpression {
     identifier: Token,
     arguments: Vec<ASTExpression>,
@@ -487,28 +1189,44 @@ pub struct ASTFunctionCallExpression {
 
 impl ASTFunctionCallExpression {
     pub fn identifier(&self) -> &str {
-        &self.identifier.span.literal
+        &self.identifier.literal
+    }
+
+    pub fn arguments(&self) -> &[ASTExpression] {
+        &self.arguments
     }
 }
 
 #[cfg(test)]
 mod test {
+    use std::{cell::RefCell, rc::Rc};
+
     use crate::compilation_unit::CompilationUnit;
+    use crate::diagnostics::DiagnosticsColletion;
 
     use super::lexer::TokenKind;
-    use super::ASTVisitor;
+    use super::parser::Parser;
     use super::Ast;
+    use super::{ASTBinaryOperatorAssociativity, ASTBinaryOperatorKind};
+    use super::{ASTExpression, ASTExpressionKind, ASTStatement, ASTStatementKind};
+    use super::{ASTVisitor, ASTVisitorMut};
 
     #[derive(Debug, PartialEq)]
     enum TestASTNode {
         Floating(f64),
         Integer(i64),
+        Boolean(bool),
+        String(String),
         Variable(String),
         Let(String, TokenKind),
         Var(String, TokenKind),
         Assign(String),
         If,
+        Else,
         While,
+        Break,
+        Continue,
+        Assert,
         For(String),
         Return,
         FuncDecl(Vec<(String, TokenKind)>),
@@ -524,16 +1242,17 @@ mod test {
 
     impl ASTVerifier {
         pub fn new(input: &str, expected_ast: Vec<TestASTNode>) -> Self {
-            let compilation_unit = CompilationUnit::compile(input);
+            // the parsed tree is verified, compile would fold its constants
+            let compilation_unit = CompilationUnit::parse(input);
             assert!(compilation_unit.is_ok());
             let mut verifier = ASTVerifier {
                 actual: Vec::new(),
                 expected: expected_ast,
             };
 
-            match compilation_unit {
-                Ok(c) => verifier.flatten_ast(&c.ast),
-                Err(_) => (),
+            if let Ok(mut c) = compilation_unit {
+                assert!(c.check().is_ok());
+                verifier.flatten_ast(&c.ast)
             };
             verifier
         }
@@ -569,7 +1288,7 @@ mod test {
 
         fn visit_let_statement(&mut self, statement: &super::ASTLetStatement) {
             self.actual.push(TestASTNode::Let(
-                statement.identifier.span.literal.clone(),
+                statement.identifier.literal.clone(),
                 statement.data_type.kind.clone(),
             ));
             self.visit_expression(&statement.initializer);
@@ -577,7 +1296,7 @@ mod test {
 
         fn visit_var_statement(&mut self, statement: &super::ASTVarStatement) {
             self.actual.push(TestASTNode::Var(
-                statement.identifier.span.literal.clone(),
+                statement.identifier.literal.clone(),
                 statement.data_type.kind.clone(),
             ));
             self.visit_expression(&statement.initializer);
@@ -589,15 +1308,15 @@ mod test {
             if let super::ASTStatementKind::Compound(body) = &statement.then_branch.kind {
                 self.visit_compound_statement(body);
             }
-            if let Some(else_branch) = statement.else_branch {
+            if let Some(else_branch) = &statement.else_branch {
+                self.actual.push(TestASTNode::Else);
                 self.visit_statement(&else_branch.else_branch);
             }
         }
 
         fn visit_for_loop_statement(&mut self, statement: &super::ASTForStatement) {
-            self.actual.push(TestASTNode::For(
-                statement.loop_variable.span.literal.clone(),
-            ));
+            self.actual
+                .push(TestASTNode::For(statement.loop_variable.literal.clone()));
             self.visit_expression(&statement.range.0);
             self.visit_expression(&statement.range.1);
             if let super::ASTStatementKind::Compound(body) = &statement.body.kind {
@@ -613,16 +1332,31 @@ mod test {
             }
         }
 
+        fn visit_break_statement(&mut self, _statement: &super::ASTBreakStatement) {
+            self.actual.push(TestASTNode::Break);
+        }
+
+        fn visit_continue_statement(&mut self, _statement: &super::ASTContinueStatement) {
+            self.actual.push(TestASTNode::Continue);
+        }
+
+        fn visit_assert_statement(&mut self, statement: &super::ASTAssertStatement) {
+            self.actual.push(TestASTNode::Assert);
+            self.visit_expression(&statement.condition);
+        }
+
         fn visit_funtion_statement(&mut self, function: &super::ASTFunctionStatement) {
             let mut args: Vec<(String, TokenKind)> = Vec::new();
             args.push((
-                function.identifier.span.literal.clone(),
+                function.identifier.literal.clone(),
                 function.return_type.kind.clone(),
             ));
             for arg in function.arguments.iter() {
                 args.push((
-                    arg.identifier.span.literal.clone(),
-                    arg.data_type.kind.clone(),
+                    arg.identifier.literal.clone(),
+                    arg.data_type
+                        .as_ref()
+                        .map_or(TokenKind::Void, |data_type| data_type.kind.clone()),
                 ));
             }
 
@@ -635,14 +1369,13 @@ mod test {
 
         fn visit_assignment_expression(&mut self, expr: &super::ASTAssignmentExpression) {
             self.actual
-                .push(TestASTNode::Assign(expr.identifier.span.literal.clone()));
+                .push(TestASTNode::Assign(expr.identifier.literal.clone()));
             self.visit_expression(&expr.expr);
         }
 
         fn visit_function_call_expression(&mut self, expr: &super::ASTFunctionCallExpression) {
-            self.actual.push(TestASTNode::FunctionCall(
-                expr.identifier.span.literal.clone(),
-            ));
+            self.actual
+                .push(TestASTNode::FunctionCall(expr.identifier.literal.clone()));
             for arg in expr.arguments.iter() {
                 self.visit_expression(arg);
             }
@@ -650,12 +1383,13 @@ mod test {
 
         fn visit_variable_expression(&mut self, expr: &super::ASTVariableExpression) {
             self.actual
-                .push(TestASTNode::Variable(expr.identifier.span.literal.clone()));
+                .push(TestASTNode::Variable(expr.identifier.literal.clone()));
         }
 
         fn visit_unary_expression(&mut self, expr: &super::ASTUnaryExpression) {
             self.actual
                 .push(TestASTNode::UnaryExpr(expr.operator.token.kind.clone()));
+            self.visit_expression(&expr.expr);
         }
 
         fn visit_binary_expression(&mut self, expr: &super::ASTBinaryExpression) {
@@ -670,14 +1404,24 @@ mod test {
             self.visit_expression(&expr.expr);
         }
 
-        fn visit_binary_operator(&mut self, op: &super::ASTBinaryOperator) {}
+        fn visit_binary_operator(&mut self, _op: &super::ASTBinaryOperator) {}
 
         fn visit_integer(&mut self, integer: &i64) {
-            self.actual.push(TestASTNode::Integer(integer.clone()));
+            self.actual.push(TestASTNode::Integer(*integer));
         }
 
         fn visit_float(&mut self, float: &f64) {
-            self.actual.push(TestASTNode::Floating(float.clone()));
+            self.actual.push(TestASTNode::Floating(*float));
+        }
+
+        fn visit_boolean(&mut self, boolean: &bool) {
+            self.actual.push(TestASTNode::Boolean(*boolean));
+        }
+
+        fn visit_string(&mut self, string: &super::Token) {
+            if let TokenKind::StringLiteral(s) = &string.kind {
+                self.actual.push(TestASTNode::String(s.clone()));
+            }
         }
     }
 
@@ -712,6 +1456,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn should_parse_simple_binary_addition_statement() {
         let input = "10 + 3.1415;";
         let expected_ast = vec![
@@ -725,6 +1470,128 @@ mod test {
     }
 
     #[test]
+    fn should_parse_chained_unary_operators() {
+        let input = "!-+3 * 2;";
+        let expected_ast = vec![
+            TestASTNode::BinaryExpr(TokenKind::Astrisk),
+            TestASTNode::UnaryExpr(TokenKind::ExclemationMark),
+            TestASTNode::UnaryExpr(TokenKind::Minus),
+            TestASTNode::UnaryExpr(TokenKind::Plus),
+            TestASTNode::Integer(3),
+            TestASTNode::Integer(2),
+        ];
+
+        let verifier = ASTVerifier::new(input, expected_ast);
+        verifier.verify();
+    }
+
+    #[test]
+    fn should_parse_negative_number_as_unary_minus() {
+        let input = "-5 * -5;";
+        let expected_ast = vec![
+            TestASTNode::BinaryExpr(TokenKind::Astrisk),
+            TestASTNode::UnaryExpr(TokenKind::Minus),
+            TestASTNode::Integer(5),
+            TestASTNode::UnaryExpr(TokenKind::Minus),
+            TestASTNode::Integer(5),
+        ];
+
+        let verifier = ASTVerifier::new(input, expected_ast);
+        verifier.verify();
+    }
+
+    #[test]
+    fn should_parse_power_as_right_associative() {
+        let input = "2 ** 3 ** 2 * 4;";
+        let expected_ast = vec![
+            TestASTNode::BinaryExpr(TokenKind::Astrisk),
+            TestASTNode::BinaryExpr(TokenKind::AstriskAstrisk),
+            TestASTNode::Integer(2),
+            TestASTNode::BinaryExpr(TokenKind::AstriskAstrisk),
+            TestASTNode::Integer(3),
+            TestASTNode::Integer(2),
+            TestASTNode::Integer(4),
+        ];
+
+        let verifier = ASTVerifier::new(input, expected_ast);
+        verifier.verify();
+    }
+
+    #[test]
+    fn should_parse_equality_below_additive_and_above_logic() {
+        let input = "1 + 2 == 3 && 1;";
+        let expected_ast = vec![
+            TestASTNode::BinaryExpr(TokenKind::AmpersandAmpersand),
+            TestASTNode::BinaryExpr(TokenKind::EqualEqual),
+            TestASTNode::BinaryExpr(TokenKind::Plus),
+            TestASTNode::Integer(1),
+            TestASTNode::Integer(2),
+            TestASTNode::Integer(3),
+            TestASTNode::Integer(1),
+        ];
+
+        let verifier = ASTVerifier::new(input, expected_ast);
+        verifier.verify();
+    }
+
+    #[test]
+    fn should_parse_logic_and_bitwise_precedence() {
+        let input = "1 || 2 && 3 | 4 ^ 5 & 6 != 7 < 8;";
+        let expected_ast = vec![
+            TestASTNode::BinaryExpr(TokenKind::PipePipe),
+            TestASTNode::Integer(1),
+            TestASTNode::BinaryExpr(TokenKind::AmpersandAmpersand),
+            TestASTNode::Integer(2),
+            TestASTNode::BinaryExpr(TokenKind::Pipe),
+            TestASTNode::Integer(3),
+            TestASTNode::BinaryExpr(TokenKind::Caret),
+            TestASTNode::Integer(4),
+            TestASTNode::BinaryExpr(TokenKind::Ampersand),
+            TestASTNode::Integer(5),
+            TestASTNode::BinaryExpr(TokenKind::ExclemationMarkEqual),
+            TestASTNode::Integer(6),
+            TestASTNode::BinaryExpr(TokenKind::LeftAngleBracket),
+            TestASTNode::Integer(7),
+            TestASTNode::Integer(8),
+        ];
+
+        let verifier = ASTVerifier::new(input, expected_ast);
+        verifier.verify();
+    }
+
+    #[test]
+    fn should_parse_else_if_chain() {
+        let input = "\
+        var a: i32 = 0;
+        if a < 0 { a = 1; } else if a == 0 { a = 2; } else { a = 3; }
+        ";
+        let expected_ast = vec![
+            TestASTNode::Var("a".to_string(), TokenKind::I32),
+            TestASTNode::Integer(0),
+            TestASTNode::If,
+            TestASTNode::BinaryExpr(TokenKind::LeftAngleBracket),
+            TestASTNode::Variable("a".to_string()),
+            TestASTNode::Integer(0),
+            TestASTNode::Assign("a".to_string()),
+            TestASTNode::Integer(1),
+            TestASTNode::Else,
+            TestASTNode::If,
+            TestASTNode::BinaryExpr(TokenKind::EqualEqual),
+            TestASTNode::Variable("a".to_string()),
+            TestASTNode::Integer(0),
+            TestASTNode::Assign("a".to_string()),
+            TestASTNode::Integer(2),
+            TestASTNode::Else,
+            TestASTNode::Assign("a".to_string()),
+            TestASTNode::Integer(3),
+        ];
+
+        let verifier = ASTVerifier::new(input, expected_ast);
+        verifier.verify();
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
     fn should_parse_complex_binary_statement() {
         let input = "let a: f64 = (7.2 - 10) / 2 + 3.1415 * 8;";
         let expected_ast = vec![
@@ -820,6 +1687,21 @@ mod test {
         verifier.verify();
     }
 
+    #[test]
+    fn should_parse_assert_statement() {
+        let input = "assert(1 == 1);";
+        let expected_ast = vec![
+            TestASTNode::Assert,
+            TestASTNode::ParenExpr,
+            TestASTNode::BinaryExpr(TokenKind::EqualEqual),
+            TestASTNode::Integer(1),
+            TestASTNode::Integer(1),
+        ];
+
+        let verifier = ASTVerifier::new(input, expected_ast);
+        verifier.verify();
+    }
+
     #[test]
     fn should_parse_while_loop() {
         let input = "\
@@ -844,4 +1726,264 @@ mod test {
         let verifier = ASTVerifier::new(input, expected_ast);
         verifier.verify();
     }
+
+    #[test]
+    fn should_parse_break_and_continue() {
+        let input = "\
+        while true {
+            if false {
+                break;
+            }
+            continue;
+        }
+        ";
+        let expected_ast = vec![
+            TestASTNode::While,
+            TestASTNode::Boolean(true),
+            TestASTNode::If,
+            TestASTNode::Boolean(false),
+            TestASTNode::Break,
+            TestASTNode::Continue,
+        ];
+
+        let verifier = ASTVerifier::new(input, expected_ast);
+        verifier.verify();
+    }
+
+    #[test]
+    fn should_parse_boolean_literals() {
+        let input = "let a: bool = true; let b: bool = false;";
+        let expected_ast = vec![
+            TestASTNode::Let("a".to_string(), TokenKind::Bool),
+            TestASTNode::Boolean(true),
+            TestASTNode::Let("b".to_string(), TokenKind::Bool),
+            TestASTNode::Boolean(false),
+        ];
+
+        let verifier = ASTVerifier::new(input, expected_ast);
+        verifier.verify();
+    }
+
+    #[test]
+    fn should_parse_string_literal() {
+        let input = "let a: str = \"Hello, LIFT!\";";
+        let expected_ast = vec![
+            TestASTNode::Let("a".to_string(), TokenKind::Str),
+            TestASTNode::String("Hello, LIFT!".to_string()),
+        ];
+
+        let verifier = ASTVerifier::new(input, expected_ast);
+        verifier.verify();
+    }
+
+    #[test]
+    fn should_skip_comment_inside_expression() {
+        let input = "\
+        // leading comment
+        let a: i32 = 1 + // inline comment
+            2;
+        ";
+        let expected_ast = vec![
+            TestASTNode::Let("a".to_string(), TokenKind::I32),
+            TestASTNode::BinaryExpr(TokenKind::Plus),
+            TestASTNode::Integer(1),
+            TestASTNode::Integer(2),
+        ];
+
+        let verifier = ASTVerifier::new(input, expected_ast);
+        verifier.verify();
+    }
+
+    fn count_calls(statement: &ASTStatement) -> usize {
+        fn count_in_expression(expr: &ASTExpression) -> usize {
+            match expr.kind() {
+                ASTExpressionKind::FunctionCall(call) => {
+                    1 + call
+                        .arguments()
+                        .iter()
+                        .map(count_in_expression)
+                        .sum::<usize>()
+                }
+                ASTExpressionKind::Binary(binary) => {
+                    count_in_expression(binary.left()) + count_in_expression(binary.right())
+                }
+                ASTExpressionKind::Unary(unary) => count_in_expression(unary.operand()),
+                ASTExpressionKind::Parenthesized(expr) => count_in_expression(expr.expression()),
+                ASTExpressionKind::Assignment(expr) => count_in_expression(expr.expression()),
+                _ => 0,
+            }
+        }
+
+        match statement.kind() {
+            ASTStatementKind::Expr(expr) => count_in_expression(expr),
+            ASTStatementKind::Let(statement) => count_in_expression(statement.initializer()),
+            ASTStatementKind::Var(statement) => count_in_expression(statement.initializer()),
+            ASTStatementKind::Return(statement) => count_in_expression(statement.expression()),
+            ASTStatementKind::Compound(statement) => {
+                statement.statements().iter().map(count_calls).sum()
+            }
+            ASTStatementKind::FuncDecl(function) => count_calls(function.body()),
+            ASTStatementKind::If(statement) => {
+                count_in_expression(statement.condition())
+                    + count_calls(statement.then_branch())
+                    + statement.else_branch().map_or(0, count_calls)
+            }
+            ASTStatementKind::While(statement) => {
+                count_in_expression(statement.condition()) + count_calls(statement.body())
+            }
+            ASTStatementKind::Assert(statement) => count_in_expression(statement.condition()),
+            ASTStatementKind::Match(statement) => {
+                count_in_expression(statement.scrutinee())
+                    + statement
+                        .arms()
+                        .iter()
+                        .map(|arm| count_calls(arm.body()))
+                        .sum::<usize>()
+            }
+            ASTStatementKind::For(statement) => {
+                let (start, end) = statement.range();
+                count_in_expression(start)
+                    + count_in_expression(end)
+                    + count_calls(statement.body())
+            }
+            ASTStatementKind::Break(_) | ASTStatementKind::Continue(_) => 0,
+        }
+    }
+
+    #[test]
+    fn should_walk_ast_through_accessors() {
+        let input = "\
+        func f(x) -> i32 { return x; }
+        let a: i32 = f(f(1)) + -f(2);
+        if a > 0 { f(a); } else { a = (f(0)); }
+        ";
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        let statements = ast.statements();
+        assert_eq!(statements.len(), 3);
+        let ASTStatementKind::FuncDecl(function) = statements[0].kind() else {
+            panic!("expected a function declaration");
+        };
+        assert_eq!(function.identifier(), "f");
+        assert_eq!(function.arguments()[0].identifier(), "x");
+        assert_eq!(statements.iter().map(count_calls).sum::<usize>(), 5);
+    }
+
+    struct NegateIntegers;
+
+    impl ASTVisitorMut for NegateIntegers {
+        fn visit_integer(&mut self, integer: &mut i64) {
+            *integer = -*integer;
+        }
+    }
+
+    #[test]
+    fn should_rewrite_ast_in_place() {
+        let input = "\
+        func f(x, y = 4) -> i32 { return x * y; }
+        let a: i32 = f(2) + 1;
+        while a > 0 { a = a - 1.5; }
+        ";
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        ast.visit_mut(&mut NegateIntegers);
+        assert_eq!(
+            ast.format(),
+            "\
+func f(x, y = -4) -> i32 {
+    return x * y;
+}
+let a: i32 = f(-2) + -1;
+while a > 0 {
+    a = a - 1.5;
+}
+"
+        );
+    }
+
+    #[test]
+    fn should_expose_operator_precedence() {
+        let precedences: Vec<(ASTBinaryOperatorKind, u8)> = ASTBinaryOperatorKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let precedence = kind.precedence();
+                (kind, precedence)
+            })
+            .collect();
+        assert_eq!(
+            precedences,
+            vec![
+                (ASTBinaryOperatorKind::Plus, 8),
+                (ASTBinaryOperatorKind::Minus, 8),
+                (ASTBinaryOperatorKind::Multiply, 9),
+                (ASTBinaryOperatorKind::Divide, 9),
+                (ASTBinaryOperatorKind::Modulo, 9),
+                (ASTBinaryOperatorKind::Power, 10),
+                (ASTBinaryOperatorKind::EqualTo, 6),
+                (ASTBinaryOperatorKind::NotEqualTo, 6),
+                (ASTBinaryOperatorKind::LogicAND, 2),
+                (ASTBinaryOperatorKind::LogicOR, 1),
+                (ASTBinaryOperatorKind::GreaterThan, 7),
+                (ASTBinaryOperatorKind::GreaterThanOrEqual, 7),
+                (ASTBinaryOperatorKind::LessThan, 7),
+                (ASTBinaryOperatorKind::LessThanOrEqual, 7),
+                (ASTBinaryOperatorKind::BitwiseOR, 3),
+                (ASTBinaryOperatorKind::BitwiseAND, 5),
+                (ASTBinaryOperatorKind::BitwiseXOR, 4),
+            ]
+        );
+
+        let right_associative: Vec<ASTBinaryOperatorKind> = ASTBinaryOperatorKind::ALL
+            .into_iter()
+            .filter(|kind| kind.associativity() == ASTBinaryOperatorAssociativity::Right)
+            .collect();
+        assert_eq!(right_associative, vec![ASTBinaryOperatorKind::Power]);
+    }
+
+    #[test]
+    fn should_walk_children_by_default() {
+        struct IntegerCounter {
+            count: usize,
+        }
+
+        impl ASTVisitor for IntegerCounter {
+            fn visit_integer(&mut self, _integer: &i64) {
+                self.count += 1;
+            }
+        }
+
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let input = "func f(a) -> i32 { return -a * (2 + 3); } var x: i32 = f(4); while x > 0 { x = x - 1; } let y: i32 = { 5 }; match x { 6 => {} }";
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        let mut counter = IntegerCounter { count: 0 };
+        ast.visit(&mut counter);
+        assert_eq!(counter.count, 7);
+    }
+
+    #[test]
+    fn should_clone_and_debug_print_subtrees() {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let input = "func square(x) -> i32 { return x * x; }";
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        let ASTStatementKind::FuncDecl(function) = &ast.statements()[0].kind else {
+            panic!("expected a function declaration");
+        };
+
+        let cloned = function.clone();
+        assert_eq!(cloned.identifier(), "square");
+        assert_eq!(format!("{:?}", cloned), format!("{:?}", function));
+        assert!(format!("{:?}", cloned).contains("Return"));
+        assert_eq!(ast.clone().statements().len(), 1);
+    }
+
+    #[test]
+    fn should_reject_call_of_undefined_function() {
+        assert!(CompilationUnit::compile("g(1);").is_err());
+    }
 }
@@ -0,0 +1,341 @@
+use core::fmt;
+use std::{
+    cmp::Ordering,
+    ops::{Add, Div, Mul, Neg, Rem, Sub},
+};
+
+// The numbers the solver computes with besides integers, floats by default.
+// Integers and float literals are converted into it, builtins that have no
+// exact counterpart (e.g. sqrt) compute with f64.
+pub trait Numeric:
+    Copy
+    + PartialEq
+    + PartialOrd
+    + fmt::Debug
+    + fmt::Display
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + Neg<Output = Self>
+    + 'static
+{
+    // result of undefined operations, it is not equal to any number
+    const NAN: Self;
+
+    fn from_i64(int: i64) -> Self;
+    fn from_f64(float: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn is_nan(self) -> bool;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+
+    fn powf(self, exponent: Self) -> Self {
+        Self::from_f64(self.to_f64().powf(exponent.to_f64()))
+    }
+}
+
+impl Numeric for f64 {
+    const NAN: Self = f64::NAN;
+
+    fn from_i64(int: i64) -> Self {
+        int as f64
+    }
+
+    fn from_f64(float: f64) -> Self {
+        float
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn is_nan(self) -> bool {
+        f64::is_nan(self)
+    }
+
+    fn floor(self) -> Self {
+        f64::floor(self)
+    }
+
+    fn ceil(self) -> Self {
+        f64::ceil(self)
+    }
+
+    fn powf(self, exponent: Self) -> Self {
+        f64::powf(self, exponent)
+    }
+}
+
+// An exact fraction, `1.0 / 3` stays a third. It is always reduced with a
+// positive denominator, NaN is 0/0. A result that doesn't fit into i128 is
+// NaN as well.
+#[derive(Debug, Clone, Copy)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    pub fn new(numerator: i128, denominator: i128) -> Self {
+        if denominator == 0 {
+            return Self::NAN;
+        }
+        let divisor = gcd(numerator, denominator);
+        let (numerator, denominator) = (numerator / divisor, denominator / divisor);
+        if denominator > 0 {
+            return Self {
+                numerator,
+                denominator,
+            };
+        }
+        match (numerator.checked_neg(), denominator.checked_neg()) {
+            (Some(numerator), Some(denominator)) => Self {
+                numerator,
+                denominator,
+            },
+            _ => Self::NAN,
+        }
+    }
+
+    fn checked(numerator: Option<i128>, denominator: Option<i128>) -> Self {
+        match (numerator, denominator) {
+            (Some(numerator), Some(denominator)) => Self::new(numerator, denominator),
+            _ => Self::NAN,
+        }
+    }
+
+    // the quotient rounded towards zero, None if it doesn't fit
+    fn truncated_quotient(self, other: Self) -> Option<i128> {
+        let numerator = self.numerator.checked_mul(other.denominator)?;
+        let denominator = self.denominator.checked_mul(other.numerator)?;
+        numerator.checked_div(denominator)
+    }
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    // only 0/0 has no divisor, and i128::MIN has no positive one
+    match a.checked_abs() {
+        Some(0) | None => 1,
+        Some(divisor) => divisor,
+    }
+}
+
+impl Add for Rational {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let left = self.numerator.checked_mul(other.denominator);
+        let right = other.numerator.checked_mul(self.denominator);
+        Self::checked(
+            left.zip(right)
+                .and_then(|(left, right)| left.checked_add(right)),
+            self.denominator.checked_mul(other.denominator),
+        )
+    }
+}
+
+impl Sub for Rational {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for Rational {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::checked(
+            self.numerator.checked_mul(other.numerator),
+            self.denominator.checked_mul(other.denominator),
+        )
+    }
+}
+
+impl Div for Rational {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        Self::checked(
+            self.numerator.checked_mul(other.denominator),
+            self.denominator.checked_mul(other.numerator),
+        )
+    }
+}
+
+// like for floats the remainder has the sign of the dividend
+impl Rem for Rational {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        if self.is_nan() || other.is_nan() {
+            return Self::NAN;
+        }
+        match self.truncated_quotient(other) {
+            Some(quotient) => self - Self::new(quotient, 1) * other,
+            None => Self::NAN,
+        }
+    }
+}
+
+impl Neg for Rational {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::checked(self.numerator.checked_neg(), Some(self.denominator))
+    }
+}
+
+impl PartialEq for Rational {
+    fn eq(&self, other: &Self) -> bool {
+        !self.is_nan() && self.numerator == other.numerator && self.denominator == other.denominator
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.is_nan() || other.is_nan() {
+            return None;
+        }
+        let left = self.numerator.checked_mul(other.denominator);
+        let right = other.numerator.checked_mul(self.denominator);
+        match (left, right) {
+            (Some(left), Some(right)) => Some(left.cmp(&right)),
+            _ => self.to_f64().partial_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.denominator {
+            0 => write!(f, "NaN"),
+            1 => write!(f, "{}", self.numerator),
+            _ => write!(f, "{}/{}", self.numerator, self.denominator),
+        }
+    }
+}
+
+impl Numeric for Rational {
+    const NAN: Self = Self {
+        numerator: 0,
+        denominator: 0,
+    };
+
+    fn from_i64(int: i64) -> Self {
+        Self::new(int as i128, 1)
+    }
+
+    // The literal as it was written, `0.1` is a tenth and not the float
+    // closest to it. Floats print the shortest decimal that reads back the
+    // same.
+    fn from_f64(float: f64) -> Self {
+        if !float.is_finite() {
+            return Self::NAN;
+        }
+        let decimal = float.abs().to_string();
+        let (integral, fraction) = decimal.split_once('.').unwrap_or((&decimal, ""));
+        let denominator = u32::try_from(fraction.len())
+            .ok()
+            .and_then(|digits| 10i128.checked_pow(digits));
+        let numerator = format!("{}{}", integral, fraction).parse::<i128>().ok();
+        let rational = Self::checked(numerator, denominator);
+        if float < 0.0 {
+            -rational
+        } else {
+            rational
+        }
+    }
+
+    fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    fn is_nan(self) -> bool {
+        self.denominator == 0
+    }
+
+    fn floor(self) -> Self {
+        if self.is_nan() {
+            return self;
+        }
+        Self::new(self.numerator.div_euclid(self.denominator), 1)
+    }
+
+    fn ceil(self) -> Self {
+        -(-self).floor()
+    }
+
+    // integral exponents stay exact
+    fn powf(self, exponent: Self) -> Self {
+        let Some(power) = (exponent.denominator == 1)
+            .then_some(exponent.numerator)
+            .and_then(|power| u32::try_from(power.unsigned_abs()).ok())
+        else {
+            return Self::from_f64(self.to_f64().powf(exponent.to_f64()));
+        };
+        let result = Self::checked(
+            self.numerator.checked_pow(power),
+            self.denominator.checked_pow(power),
+        );
+        if exponent.numerator < 0 {
+            Self::new(1, 1) / result
+        } else {
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Numeric, Rational};
+
+    #[test]
+    fn should_compute_exact_fractions() {
+        let third = Rational::new(1, 3);
+        assert_eq!(third + third + third, Rational::from_i64(1));
+        assert_eq!(Rational::new(2, -4), Rational::new(-1, 2));
+        assert_eq!(
+            (Rational::new(7, 2) % Rational::new(-3, 2)).to_string(),
+            "1/2"
+        );
+        assert_eq!(
+            (Rational::new(-7, 2) % Rational::new(3, 2)).to_string(),
+            "-1/2"
+        );
+        assert_eq!(Rational::new(-1, 2).floor(), Rational::from_i64(-1));
+        assert_eq!(Rational::new(-1, 2).ceil(), Rational::from_i64(0));
+        assert_eq!(
+            Rational::new(2, 3).powf(Rational::from_i64(-2)),
+            Rational::new(9, 4)
+        );
+        assert!(Rational::new(1, 3) < Rational::new(1, 2));
+    }
+
+    #[test]
+    fn should_read_float_as_written() {
+        assert_eq!(Rational::from_f64(0.1), Rational::new(1, 10));
+        assert_eq!(Rational::from_f64(-2.5), Rational::new(-5, 2));
+        assert_eq!(
+            Rational::from_f64(0.1) + Rational::from_f64(0.2),
+            Rational::from_f64(0.3)
+        );
+        assert!(Rational::from_f64(f64::INFINITY).is_nan());
+    }
+
+    #[test]
+    fn should_be_nan_when_out_of_range() {
+        let huge = Rational::new(i128::MAX, 1);
+        assert!((huge + huge).is_nan());
+        assert!((huge * Rational::new(1, 2) / Rational::from_i64(0)).is_nan());
+        assert!(Rational::NAN != Rational::NAN);
+        assert_eq!(Rational::NAN.partial_cmp(&Rational::from_i64(0)), None);
+        assert_eq!(Rational::NAN.to_string(), "NaN");
+    }
+}
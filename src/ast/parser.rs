@@ -1,5 +1,5 @@
 use crate::ast::lexer::{Lexer, Token, TokenKind};
-use crate::ast::{ASTExpression, ASTStatement};
+use crate::ast::{ASTExpression, ASTStatement, ASTStatementKind, Ast};
 use crate::diagnostics::DiagnosticsColletion;
 use crate::diagnostics::DiagnosticsColletionCell;
 use std::{
@@ -9,8 +9,8 @@ use std::{
 
 use super::lexer::TextSpan;
 use super::{
-    ASTBinaryOperator, ASTBinaryOperatorKind, ASTElseStatement, ASTUnaryOperator,
-    ASTUnaryOperatorKind, FunctionArgumentDeclaration,
+    ASTBinaryOperator, ASTBinaryOperatorAssociativity, ASTBinaryOperatorKind, ASTElseStatement,
+    ASTMatchArm, ASTUnaryOperator, ASTUnaryOperatorKind, FunctionArgumentDeclaration,
 };
 
 struct Cursor {
@@ -35,51 +35,82 @@ impl Cursor {
 }
 
 pub struct Parser {
+    // needed for the literals of the node spans
+    source: String,
     tokens: Vec<Token>,
+    eof: Token,
     cursor: Cursor,
+    // nesting of the expression being parsed, deeper input would overflow the stack
+    expression_depth: usize,
+    max_expression_depth: usize,
+    // a line break ends a statement like a ';'
+    newline_ends_statement: bool,
     diagnostics_colletion: DiagnosticsColletionCell,
 }
 
 impl Parser {
+    const DEFAULT_MAX_EXPRESSION_DEPTH: usize = 256;
+
     pub fn new(
         tokens: Vec<Token>,
         diagnostics_colletion: Rc<RefCell<DiagnosticsColletion>>,
     ) -> Self {
+        // the tokens cover the whole source, including whitespace and comments
+        let source: String = tokens
+            .iter()
+            .filter(|token| token.kind != TokenKind::Eof)
+            .map(|token| token.literal.as_str())
+            .collect();
+        let tokens: Vec<Token> = tokens
+            .iter()
+            .filter(|token| !token.kind.is_trivia())
+            .cloned()
+            .collect();
         Self {
-            tokens: tokens
-                .iter()
-                .filter(|token| match token.kind {
-                    TokenKind::Whitespace => false,
-                    TokenKind::SingleLineComment(_) => false,
-                    TokenKind::MultiLineComment(_) => false,
-                    _ => true,
-                })
-                .map(|token| token.clone())
-                .collect(),
+            source,
+            eof: Self::eof_token(&tokens),
+            tokens,
             cursor: Cursor::new(),
+            expression_depth: 0,
+            max_expression_depth: Self::DEFAULT_MAX_EXPRESSION_DEPTH,
+            newline_ends_statement: false,
             diagnostics_colletion,
         }
     }
 
+    #[allow(dead_code)]
+    pub fn set_max_expression_depth(&mut self, max_expression_depth: usize) {
+        self.max_expression_depth = max_expression_depth;
+    }
+
+    // Statements are terminated by ';' unless a line break is allowed to end
+    // them as well. Expressions still continue on the next line, `1\n- 2`
+    // is a single statement.
+    #[allow(dead_code)]
+    pub fn set_newline_ends_statement(&mut self, newline_ends_statement: bool) {
+        self.newline_ends_statement = newline_ends_statement;
+    }
+
     pub fn from_input(input: String, diagnostics_colletion: DiagnosticsColletionCell) -> Self {
-        let mut lexer = Lexer::new(input);
+        let mut lexer = Lexer::new(input, Rc::clone(&diagnostics_colletion));
         let mut tokens = Vec::new();
         while let Some(token) = lexer.next_token() {
-            match token.kind {
-                TokenKind::Whitespace => (),
-                TokenKind::SingleLineComment(_) => (),
-                TokenKind::MultiLineComment(_) => (),
-                _ => tokens.push(token),
-            };
+            tokens.push(token);
         }
-        Self {
-            tokens,
-            cursor: Cursor::new(),
-            diagnostics_colletion,
+        Self::new(tokens, diagnostics_colletion)
+    }
+
+    // Parses all remaining statements
+    pub fn parse(&mut self) -> Ast {
+        let mut ast = Ast::new();
+        while let Some(statement) = self.next_statement() {
+            ast.add_statement(statement);
         }
+        ast
     }
 
     pub fn next_statement(&mut self) -> Option<ASTStatement> {
+        self.skip_trailing_tokens(true);
         if self.current_token().kind == TokenKind::Eof {
             return None;
         }
@@ -94,10 +125,12 @@ impl Parser {
             TokenKind::Func => self.parse_function_statement(),
             TokenKind::If => self.parse_if_statement(),
             TokenKind::While => self.parse_while_loop_statement(),
+            TokenKind::Break => self.parse_break_statement(),
+            TokenKind::Continue => self.parse_continue_statement(),
+            TokenKind::Match => self.parse_match_statement(),
             TokenKind::For => self.parse_for_loop_statement(),
+            TokenKind::Assert => self.parse_assert_statement(),
             TokenKind::LeftBrace => self.parse_compound_statement(),
-            TokenKind::SingleLineComment(_) => todo!("Decide if comments need to be in AST"),
-            TokenKind::MultiLineComment(_) => todo!("Decide if comments need to be in AST"),
             _ => self.parse_expression_statement(),
         }
     }
@@ -106,12 +139,47 @@ impl Parser {
         self.peek(0)
     }
 
+    // Returns the token at `offset` from the cursor, or the EOF token when
+    // the offset points outside of the token stream.
     fn peek(&self, offset: isize) -> &Token {
-        let index = std::cmp::min(
-            (self.cursor.get_value() as isize + offset) as usize,
-            self.tokens.len() - 1,
-        );
-        self.tokens.get(index).unwrap()
+        self.cursor
+            .get_value()
+            .checked_add_signed(offset)
+            .and_then(|index| self.tokens.get(index))
+            .unwrap_or(&self.eof)
+    }
+
+    fn eof_token(tokens: &[Token]) -> Token {
+        match tokens.last() {
+            Some(token) if token.kind == TokenKind::Eof => token.clone(),
+            Some(token) => Token::new(
+                TokenKind::Eof,
+                TextSpan::new(token.span.end, token.span.end),
+                '\0'.to_string(),
+            ),
+            None => Token::new(TokenKind::Eof, TextSpan::new(0, 0), '\0'.to_string()),
+        }
+    }
+
+    // Span of the source from the token at `start` up to the last consumed
+    // token, empty if no token was consumed since `start`
+    fn span_from(&self, start: usize) -> TextSpan {
+        let (start_offset, line, column) = match self.tokens.get(start) {
+            Some(token) if token.kind != TokenKind::Eof => {
+                (token.span.start, token.span.line, token.span.column)
+            }
+            // the end of file token has no location
+            _ => {
+                let (line, column) = TextSpan::line_column(&self.source, self.source.len());
+                (self.source.len(), line, column)
+            }
+        };
+        let end = if self.cursor.get_value() > start {
+            self.peek(-1).span.end.max(start_offset)
+        } else {
+            start_offset
+        };
+        TextSpan::with_location(start_offset, end, line, column)
     }
 
     fn consume(&self) -> &Token {
@@ -129,49 +197,207 @@ impl Parser {
         token
     }
 
+    fn consume_statement_end(&self) {
+        let at_line_end = matches!(
+            self.current_token().kind,
+            TokenKind::RightBrace | TokenKind::Eof
+        ) || self.current_token().span.line > self.peek(-1).span.line;
+        if self.current_token().kind != TokenKind::SemiColon
+            && self.newline_ends_statement
+            && at_line_end
+        {
+            return;
+        }
+        self.skip_trailing_tokens(false);
+        self.consume_expected(TokenKind::SemiColon);
+    }
+
+    // Closing delimiters without an opening one, like the ')' in `1 + 2 )`,
+    // are reported together and skipped. Only at the top level a '}' can't
+    // close a block.
+    fn skip_trailing_tokens(&self, top_level: bool) {
+        let start = self.cursor.get_value();
+        while match self.current_token().kind {
+            TokenKind::RightParen => true,
+            TokenKind::RightBrace => top_level,
+            _ => false,
+        } {
+            self.consume();
+        }
+        if self.cursor.get_value() > start {
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_unexpected_trailing_tokens(self.span_from(start));
+        }
+    }
+
+    // In nested expressions the unexpected token alone doesn't tell which
+    // '(' is missing its ')', a note points at the opening one.
+    fn consume_closing_paren(&self, open_paren: &Token) {
+        if self.consume_expected(TokenKind::RightParen).kind != TokenKind::RightParen {
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_unclosed_parenthesis(open_paren.span.clone());
+        }
+    }
+
+    // Error recovery for loops, if no token was consumed since `start` the
+    // current token can't be handled by any parser and is skipped.
+    fn skip_if_no_progress(&self, start: usize) {
+        if self.cursor.get_value() == start && self.current_token().kind != TokenKind::Eof {
+            self.consume();
+        }
+    }
+
     fn parse_return_statement(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
         self.consume_expected(TokenKind::Return);
         let expr = self.parse_expression();
-        self.consume_expected(TokenKind::SemiColon);
-        ASTStatement::return_statement(expr)
+        self.consume_statement_end();
+        ASTStatement::return_statement(expr).with_span(self.span_from(start))
     }
 
     fn parse_let_statement(&mut self) -> ASTStatement {
-        self.consume_expected(TokenKind::Let);
-        let identifier = self.consume_expected(TokenKind::Identifier).clone();
-        self.consume_expected(TokenKind::Colon);
-        let data_type = self.consume().clone();
-        self.consume_expected(TokenKind::Equal);
-        let expr = self.parse_expression();
-        self.consume_expected(TokenKind::SemiColon);
-        ASTStatement::let_statement(identifier, data_type, expr)
+        let start = self.cursor.get_value();
+        match self.parse_declaration(TokenKind::Let) {
+            Some((identifier, data_type, expr)) => {
+                ASTStatement::let_statement(identifier, data_type, expr)
+                    .with_span(self.span_from(start))
+            }
+            None => self.skip_statement(start),
+        }
     }
 
     fn parse_var_statement(&mut self) -> ASTStatement {
-        self.consume_expected(TokenKind::Var);
+        let start = self.cursor.get_value();
+        match self.parse_declaration(TokenKind::Var) {
+            Some((identifier, data_type, expr)) => {
+                ASTStatement::var_statement(identifier, data_type, expr)
+                    .with_span(self.span_from(start))
+            }
+            None => self.skip_statement(start),
+        }
+    }
+
+    // `keyword identifier: type = expr;`, None if the declaration is broken
+    // before the initializer. Guessing what was meant would report every
+    // following token as unexpected as well.
+    fn parse_declaration(&mut self, keyword: TokenKind) -> Option<(Token, Token, ASTExpression)> {
+        self.consume_expected(keyword);
         let identifier = self.consume_expected(TokenKind::Identifier).clone();
-        self.consume_expected(TokenKind::Colon);
+        if identifier.kind != TokenKind::Identifier
+            || self.consume_expected(TokenKind::Colon).kind != TokenKind::Colon
+        {
+            return None;
+        }
         let data_type = self.consume().clone();
-        self.consume_expected(TokenKind::Equal);
+        if self.consume_expected(TokenKind::Equal).kind != TokenKind::Equal {
+            return None;
+        }
         let expr = self.parse_expression();
-        self.consume_expected(TokenKind::SemiColon);
-        ASTStatement::var_statement(identifier, data_type, expr)
+        self.consume_statement_end();
+        Some((identifier, data_type, expr))
     }
 
+    // Skips to the end of a broken statement, which is replaced by an error
+    fn skip_statement(&mut self, start: usize) -> ASTStatement {
+        while !matches!(
+            self.current_token().kind,
+            TokenKind::RightBrace | TokenKind::Eof
+        ) {
+            if self.consume().kind == TokenKind::SemiColon {
+                break;
+            }
+        }
+        let span = self.span_from(start);
+        ASTStatement::expression(ASTExpression::error(span.clone()).with_span(span.clone()))
+            .with_span(span)
+    }
+
+    // A block can be an expression, so it counts towards the expression depth
     fn parse_compound_statement(&mut self) -> ASTStatement {
-        self.consume_expected(TokenKind::LeftBrace);
+        if self.expression_depth >= self.max_expression_depth {
+            return self.skip_too_deeply_nested_block();
+        }
+        self.expression_depth += 1;
+        let block = self.parse_block();
+        self.expression_depth -= 1;
+        block
+    }
+
+    fn parse_block(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
+        let left_brace = self.consume_expected(TokenKind::LeftBrace).clone();
         let mut statements: Vec<ASTStatement> = Vec::new();
         while self.current_token().kind != TokenKind::RightBrace
             && self.current_token().kind != TokenKind::Eof
         {
-            println!("Help {:?}", self.current_token());
+            let statement_start = self.cursor.get_value();
             statements.push(self.parse_statement());
+            self.skip_if_no_progress(statement_start);
         }
-        self.consume_expected(TokenKind::RightBrace);
-        ASTStatement::compound(statements)
+
+        // a trailing block with a value is the value of the enclosing block
+        if matches!(
+            statements.last().map(ASTStatement::kind),
+            Some(ASTStatementKind::Compound(block)) if block.value().is_some()
+        ) {
+            let statement = statements.pop().unwrap();
+            if let ASTStatementKind::Compound(block) = statement.kind {
+                let expr = ASTExpression::block(block).with_span(statement.span.clone());
+                statements.push(ASTStatement::expression(expr).with_span(statement.span));
+            }
+        }
+
+        // an expression statement without ';' before the '}' is the value of the block
+        let has_value = matches!(
+            statements.last().map(ASTStatement::kind),
+            Some(ASTStatementKind::Expr(_))
+        ) && self.peek(-1).kind != TokenKind::SemiColon;
+
+        if self.current_token().kind == TokenKind::Eof {
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_unterminated_block(left_brace.span);
+        } else {
+            self.consume_expected(TokenKind::RightBrace);
+        }
+        ASTStatement::compound(statements, has_value).with_span(self.span_from(start))
+    }
+
+    // Skips the block up to its closing '}', it is replaced by an empty one.
+    // A '}' without a matching '{' closes an enclosing block and is kept.
+    fn skip_too_deeply_nested_block(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
+        self.diagnostics_colletion
+            .borrow_mut()
+            .report_block_too_deeply_nested(
+                self.current_token().span.clone(),
+                self.max_expression_depth,
+            );
+        let mut open_braces: usize = 0;
+        loop {
+            match self.current_token().kind {
+                TokenKind::Eof => break,
+                TokenKind::RightBrace if open_braces == 0 => break,
+                _ => {}
+            }
+            match self.consume().kind {
+                TokenKind::LeftBrace => open_braces += 1,
+                TokenKind::RightBrace => {
+                    open_braces -= 1;
+                    if open_braces == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        ASTStatement::compound(Vec::new(), false).with_span(self.span_from(start))
     }
 
     fn parse_function_statement(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
         self.consume_expected(TokenKind::Func);
         let identifier = self.consume_expected(TokenKind::Identifier).clone();
         self.consume_expected(TokenKind::LeftParen);
@@ -196,15 +422,35 @@ impl Parser {
 
             if self.current_token().kind == TokenKind::Identifier {
                 let identifier = self.consume().clone();
-                self.consume_expected(TokenKind::Colon);
+                // the type annotation of a parameter is optional: `func f(x, y: f64)`
+                let data_type = if self.current_token().kind == TokenKind::Colon {
+                    self.consume_expected(TokenKind::Colon);
+                    Some(self.consume().clone())
+                } else {
+                    None
+                };
+                // `func f(x, y = 2)`, parameters with a default have to come last
+                let default = if self.current_token().kind == TokenKind::Equal {
+                    self.consume_expected(TokenKind::Equal);
+                    Some(self.parse_expression())
+                } else {
+                    None
+                };
+                if default.is_none() && arguments.iter().any(|arg| arg.default.is_some()) {
+                    self.diagnostics_colletion
+                        .borrow_mut()
+                        .report_parameter_without_default(&identifier);
+                }
                 arguments.push(FunctionArgumentDeclaration {
                     identifier,
-                    data_type: self.consume().clone(),
+                    data_type,
+                    default,
                 });
             } else {
                 self.diagnostics_colletion
                     .borrow_mut()
                     .report_unexpected_token(&TokenKind::Identifier, self.current_token());
+                self.consume();
             }
 
             if self.current_token().kind == TokenKind::Comma
@@ -224,19 +470,22 @@ impl Parser {
             self.consume_expected(TokenKind::MinusRightAngleBracket);
             self.consume().clone()
         } else {
-            Token {
-                kind: TokenKind::Void,
-                span: TextSpan::new(
+            Token::new(
+                TokenKind::Void,
+                TextSpan::with_location(
                     right_paren.span.start,
                     right_paren.span.end,
-                    "void".to_string(),
+                    right_paren.span.line,
+                    right_paren.span.column,
                 ),
-            }
+                "void".to_string(),
+            )
         };
 
         let body = self.parse_compound_statement();
 
         ASTStatement::function(identifier, arguments, body, return_type)
+            .with_span(self.span_from(start))
     }
 
     fn consume_optional_else_statement(&mut self) -> Option<ASTElseStatement> {
@@ -244,9 +493,14 @@ impl Parser {
             return None;
         }
         let else_keyword = self.consume_expected(TokenKind::Else).clone();
-        let else_branch = self.parse_compound_statement();
+        // `else if` chains nest the next conditional as the else branch
+        let else_branch = if self.current_token().kind == TokenKind::If {
+            self.parse_if_statement()
+        } else {
+            self.parse_compound_statement()
+        };
         Some(ASTElseStatement {
-            else_keyword: else_keyword,
+            else_keyword,
             else_branch: Box::new(else_branch),
         })
     }
@@ -258,32 +512,118 @@ impl Parser {
             TokenKind::MinusEqual => ASTBinaryOperatorKind::Minus,
             TokenKind::AstriskEqual => ASTBinaryOperatorKind::Multiply,
             TokenKind::SlashEqual => ASTBinaryOperatorKind::Divide,
+            TokenKind::PercentEqual => ASTBinaryOperatorKind::Modulo,
             TokenKind::PipeEqual => ASTBinaryOperatorKind::BitwiseOR,
             TokenKind::AmpersandEqual => ASTBinaryOperatorKind::BitwiseAND,
             TokenKind::CaretEqual => ASTBinaryOperatorKind::BitwiseXOR,
-            _ => todo!(),
+            _ => unreachable!("not an assignment operator: {}", op.kind),
         };
         ASTBinaryOperator { kind, token: op }
     }
 
     fn parse_if_statement(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
         let keyword = self.consume_expected(TokenKind::If).clone();
         let condition = self.parse_expression();
         let then_branch = self.parse_compound_statement();
         let else_branch = self.consume_optional_else_statement();
 
         ASTStatement::conditional(keyword, condition, then_branch, else_branch)
+            .with_span(self.span_from(start))
     }
 
     fn parse_while_loop_statement(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
         let keyword = self.consume_expected(TokenKind::While).clone();
         let condition = self.parse_expression();
         let body = self.parse_compound_statement();
 
-        ASTStatement::while_loop(keyword, condition, body)
+        ASTStatement::while_loop(keyword, condition, body).with_span(self.span_from(start))
+    }
+
+    fn parse_match_statement(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
+        let keyword = self.consume_expected(TokenKind::Match).clone();
+        let scrutinee = self.parse_expression();
+        let left_brace = self.consume_expected(TokenKind::LeftBrace).clone();
+        let mut arms: Vec<ASTMatchArm> = Vec::new();
+        while self.current_token().kind != TokenKind::RightBrace
+            && self.current_token().kind != TokenKind::Eof
+        {
+            let arm_start = self.cursor.get_value();
+            arms.push(self.parse_match_arm());
+            self.skip_if_no_progress(arm_start);
+        }
+
+        if self.current_token().kind == TokenKind::Eof {
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_unterminated_block(left_brace.span);
+        } else {
+            self.consume_expected(TokenKind::RightBrace);
+        }
+        ASTStatement::match_statement(keyword, scrutinee, arms).with_span(self.span_from(start))
+    }
+
+    // `1 => { ... }` or the default arm `_ => { ... }`, arms may be separated by ','
+    fn parse_match_arm(&mut self) -> ASTMatchArm {
+        let pattern = if self.current_token().kind == TokenKind::Identifier
+            && self.current_token().literal == "_"
+        {
+            self.consume();
+            None
+        } else {
+            Some(self.parse_match_pattern())
+        };
+        self.consume_expected(TokenKind::EqualRightAngleBracket);
+        let body = self.parse_compound_statement();
+        if self.current_token().kind == TokenKind::Comma {
+            self.consume();
+        }
+        ASTMatchArm {
+            pattern,
+            body: Box::new(body),
+        }
+    }
+
+    // patterns are literals, numbers may be negated
+    fn parse_match_pattern(&mut self) -> ASTExpression {
+        let pattern = self.parse_expression();
+        if !pattern.is_literal() {
+            self.diagnostics_colletion
+                .borrow_mut()
+                .report_expected_literal_pattern(
+                    pattern.span.literal(&self.source),
+                    pattern.span.clone(),
+                );
+        }
+        pattern
+    }
+
+    fn parse_break_statement(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
+        let keyword = self.consume_expected(TokenKind::Break).clone();
+        self.consume_statement_end();
+        ASTStatement::break_statement(keyword).with_span(self.span_from(start))
+    }
+
+    fn parse_continue_statement(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
+        let keyword = self.consume_expected(TokenKind::Continue).clone();
+        self.consume_statement_end();
+        ASTStatement::continue_statement(keyword).with_span(self.span_from(start))
+    }
+
+    fn parse_assert_statement(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
+        let keyword = self.consume_expected(TokenKind::Assert).clone();
+        let condition = self.parse_expression();
+        self.consume_statement_end();
+        ASTStatement::assert_statement(keyword, condition).with_span(self.span_from(start))
     }
 
     fn parse_for_loop_statement(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
         let keyword = self.consume_expected(TokenKind::For).clone();
         let loop_variable = self.consume_expected(TokenKind::Identifier).clone();
         self.consume_expected(TokenKind::In);
@@ -295,43 +635,144 @@ impl Parser {
         let body = self.parse_compound_statement();
 
         ASTStatement::for_loop(keyword, loop_variable, (range_start, range_end), body)
+            .with_span(self.span_from(start))
     }
 
     fn parse_expression_statement(&mut self) -> ASTStatement {
+        let start = self.cursor.get_value();
         let expr = self.parse_expression();
-        self.consume_expected(TokenKind::SemiColon);
-        ASTStatement::expression(expr)
+        self.skip_trailing_tokens(false);
+        // the last expression of a block may leave out the ';'
+        if self.current_token().kind != TokenKind::RightBrace {
+            self.consume_statement_end();
+        }
+        ASTStatement::expression(expr).with_span(self.span_from(start))
     }
 
     fn parse_assignment_expression(&mut self) -> ASTExpression {
+        let start = self.cursor.get_value();
         if self.current_token().kind == TokenKind::Identifier {
             if self.peek(1).kind == TokenKind::Equal {
                 let var = self.consume().clone();
                 self.consume_expected(TokenKind::Equal);
-                let assignment = self.parse_binary_expression(0);
-                return ASTExpression::assignment(var, assignment);
+                let assignment = self.parse_ternary_expression();
+                return ASTExpression::assignment(var, assignment).with_span(self.span_from(start));
             }
-            if self.peek(1).kind == TokenKind::PlusEqual
-                || self.peek(1).kind == TokenKind::MinusEqual
-                || self.peek(1).kind == TokenKind::AstriskEqual
-                || self.peek(1).kind == TokenKind::Slash
-            {
+            if matches!(
+                self.peek(1).kind,
+                TokenKind::PlusEqual
+                    | TokenKind::MinusEqual
+                    | TokenKind::AstriskEqual
+                    | TokenKind::SlashEqual
+                    | TokenKind::PercentEqual
+                    | TokenKind::PipeEqual
+                    | TokenKind::AmpersandEqual
+                    | TokenKind::CaretEqual
+            ) {
                 let var = self.consume().clone();
                 let op = self.consume_assignment_operator();
-                let assignment = self.parse_binary_expression(0);
+                let assignment = self.parse_ternary_expression();
+                // the desugared `x op e` has the span of the whole assignment
+                let span = self.span_from(start);
+                let variable = ASTExpression::identifier(var.clone()).with_span(var.span.clone());
                 return ASTExpression::assignment(
-                    var.clone(),
-                    ASTExpression::binary(op, ASTExpression::identifier(var.clone()), assignment),
-                );
+                    var,
+                    ASTExpression::binary(op, variable, assignment).with_span(span.clone()),
+                )
+                .with_span(span);
             }
         }
-        self.parse_binary_expression(0)
+        self.parse_ternary_expression()
+    }
+
+    // `c ? a : b` binds weaker than every binary operator and is right
+    // associative, `a ? b : c ? d : e` is `a ? b : (c ? d : e)`
+    fn parse_ternary_expression(&mut self) -> ASTExpression {
+        let start = self.cursor.get_value();
+        let condition = self.parse_binary_expression(0);
+        if self.current_token().kind != TokenKind::QuestionMark {
+            return condition;
+        }
+        self.parse_ternary_branches(start, condition)
+    }
+
+    // kept apart so the frame of every nested expression stays small
+    fn parse_ternary_branches(&mut self, start: usize, condition: ASTExpression) -> ASTExpression {
+        self.consume();
+        let then_expr = self.parse_nested_expression(Self::parse_expression);
+        self.consume_expected(TokenKind::Colon);
+        let else_expr = self.parse_nested_expression(Self::parse_ternary_expression);
+        ASTExpression::ternary(condition, then_expr, else_expr).with_span(self.span_from(start))
     }
 
     fn parse_expression(&mut self) -> ASTExpression {
         self.parse_assignment_expression()
     }
 
+    // Parses a subexpression one level deeper. Past the maximum depth the
+    // rest of the subexpression is skipped and replaced by an error.
+    fn parse_nested_expression(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> ASTExpression,
+    ) -> ASTExpression {
+        if self.expression_depth >= self.max_expression_depth {
+            return self.skip_too_deeply_nested_expression();
+        }
+        self.expression_depth += 1;
+        let expr = parse(self);
+        self.expression_depth -= 1;
+        expr
+    }
+
+    // Skips up to the ')' closing the enclosing parenthesis or the end of
+    // the statement, so the outer expressions still parse.
+    fn skip_too_deeply_nested_expression(&mut self) -> ASTExpression {
+        let start = self.cursor.get_value();
+        self.diagnostics_colletion
+            .borrow_mut()
+            .report_expression_too_deeply_nested(
+                self.current_token().span.clone(),
+                self.max_expression_depth,
+            );
+        let mut open_parens: usize = 0;
+        loop {
+            match self.current_token().kind {
+                TokenKind::Eof => break,
+                TokenKind::SemiColon
+                | TokenKind::LeftBrace
+                | TokenKind::RightBrace
+                | TokenKind::RightParen
+                    if open_parens == 0 =>
+                {
+                    break
+                }
+                TokenKind::LeftParen => open_parens += 1,
+                TokenKind::RightParen => open_parens -= 1,
+                _ => {}
+            }
+            self.consume();
+        }
+        let span = self.span_from(start);
+        ASTExpression::error(span.clone()).with_span(span)
+    }
+
+    // The elements after the first one, a trailing comma is allowed so `(a,)`
+    // is a tuple with a single element
+    fn parse_tuple_expression(&mut self, first: ASTExpression, open: &Token) -> ASTExpression {
+        let mut elements = vec![first];
+        while self.current_token().kind == TokenKind::Comma {
+            self.consume();
+            if self.current_token().kind == TokenKind::RightParen {
+                break;
+            }
+            let start = self.cursor.get_value();
+            elements.push(self.parse_nested_expression(Self::parse_expression));
+            self.skip_if_no_progress(start);
+        }
+        self.consume_closing_paren(open);
+        ASTExpression::tuple(elements)
+    }
+
     fn parse_arguments_list(&mut self) -> Vec<ASTExpression> {
         if self.current_token().kind == TokenKind::Comma {
             self.diagnostics_colletion
@@ -350,7 +791,9 @@ impl Parser {
                     .report_unexpected_token(&TokenKind::Identifier, self.current_token());
                 self.consume();
             }
-            arguments.push(self.parse_expression());
+            let start = self.cursor.get_value();
+            arguments.push(self.parse_nested_expression(Self::parse_expression));
+            self.skip_if_no_progress(start);
             if self.current_token().kind == TokenKind::Comma
                 && self.peek(1).kind == TokenKind::RightParen
             {
@@ -365,18 +808,37 @@ impl Parser {
 
     fn parse_function_call_expression(&mut self) -> ASTExpression {
         let identifier = self.peek(-1).clone();
-        self.consume();
+        let open_paren = self.consume().clone();
         let arguments = self.parse_arguments_list();
-        self.consume_expected(TokenKind::RightParen);
+        self.consume_closing_paren(&open_paren);
         ASTExpression::function_call(identifier.clone(), arguments)
     }
 
+    // `{ let a: i32 = 2; a * a }` in place of an expression
+    fn parse_block_expression(&mut self) -> ASTExpression {
+        let block = self.parse_compound_statement();
+        match block.kind {
+            ASTStatementKind::Compound(statement) => {
+                ASTExpression::block(statement).with_span(block.span)
+            }
+            _ => unreachable!("a block is parsed as compound statement"),
+        }
+    }
+
     fn parse_primary_expression(&mut self) -> ASTExpression {
+        if self.current_token().kind == TokenKind::LeftBrace {
+            return self.parse_block_expression();
+        }
+        let start = self.cursor.get_value();
         let token = self.consume().clone();
 
-        return match token.kind {
+        let expr = match token.kind {
             TokenKind::Integer(i) => ASTExpression::integer(i),
             TokenKind::Floating(i) => ASTExpression::float(i),
+            TokenKind::StringLiteral(_) => ASTExpression::string(token.clone()),
+            TokenKind::CharLiteral(c) => ASTExpression::char(c),
+            TokenKind::True => ASTExpression::boolean(true),
+            TokenKind::False => ASTExpression::boolean(false),
             TokenKind::Identifier => {
                 if self.current_token().kind == TokenKind::LeftParen {
                     self.parse_function_call_expression()
@@ -385,13 +847,17 @@ impl Parser {
                 }
             }
 
+            // the lexer already reported the bad token
+            TokenKind::Bad => ASTExpression::error(token.span),
+            // assignments are expressions, `(a = 1) + a` is allowed
             TokenKind::LeftParen => {
-                let expr = self.parse_binary_expression(0);
-                let _found_token = self.consume_expected(TokenKind::RightParen);
-                ASTExpression::parenthesized(expr)
-            }
-            TokenKind::Tilde | TokenKind::Minus | TokenKind::ExclemationMark => {
-                self.parse_unary_expression()
+                let expr = self.parse_nested_expression(Self::parse_expression);
+                if self.current_token().kind == TokenKind::Comma {
+                    self.parse_tuple_expression(expr, &token)
+                } else {
+                    self.consume_closing_paren(&token);
+                    ASTExpression::parenthesized(expr)
+                }
             }
             _ => {
                 self.diagnostics_colletion
@@ -400,22 +866,45 @@ impl Parser {
                 ASTExpression::error(token.span)
             }
         };
+        expr.with_span(self.span_from(start))
     }
 
+    // prefix operators bind tighter than any binary operator and stack, so
+    // `!-x` parses as `!(-x)`. There are no negative literals, `-5` is a
+    // unary minus that constant folding turns into a literal.
+    // A sign right after a binary operator starts the operand, `1 - -1` is a
+    // subtraction of `-1`. The lexer never merges two signs into one token.
     fn parse_unary_expression(&mut self) -> ASTExpression {
-        let operator = self.parse_unary_operator().unwrap();
-        let expr = self.parse_primary_expression();
-        ASTExpression::unary(operator, expr)
+        let start = self.cursor.get_value();
+        match self.parse_unary_operator() {
+            Some(operator) => {
+                self.consume();
+                let expr = self.parse_nested_expression(Self::parse_unary_expression);
+                ASTExpression::unary(operator, expr).with_span(self.span_from(start))
+            }
+            None => self.parse_primary_expression(),
+        }
     }
+
     fn parse_binary_expression(&mut self, precedence: u8) -> ASTExpression {
-        let mut left = self.parse_primary_expression();
+        let start = self.cursor.get_value();
+        let mut left = self.parse_unary_expression();
 
         while let Some(operator) = self.parse_binary_operator() {
             let operator_precedence = operator.precedence();
             if operator_precedence > precedence {
                 self.consume();
-                let right = self.parse_binary_expression(operator_precedence);
-                left = ASTExpression::binary(operator, left, right);
+                // a right associative operator lets the right operand take
+                // another operator of the same precedence
+                let right_precedence = match operator.associativity() {
+                    ASTBinaryOperatorAssociativity::Left => operator_precedence,
+                    ASTBinaryOperatorAssociativity::Right => operator_precedence - 1,
+                };
+                let right = self.parse_nested_expression(|parser| {
+                    parser.parse_binary_expression(right_precedence)
+                });
+                left =
+                    ASTExpression::binary(operator, left, right).with_span(self.span_from(start));
             } else {
                 break;
             }
@@ -430,6 +919,8 @@ impl Parser {
             TokenKind::Minus => Some(ASTBinaryOperatorKind::Minus),
             TokenKind::Astrisk => Some(ASTBinaryOperatorKind::Multiply),
             TokenKind::Slash => Some(ASTBinaryOperatorKind::Divide),
+            TokenKind::Percent => Some(ASTBinaryOperatorKind::Modulo),
+            TokenKind::AstriskAstrisk => Some(ASTBinaryOperatorKind::Power),
 
             TokenKind::Pipe => Some(ASTBinaryOperatorKind::BitwiseOR),
             TokenKind::Ampersand => Some(ASTBinaryOperatorKind::BitwiseAND),
@@ -444,11 +935,9 @@ impl Parser {
             TokenKind::LeftAngleBracketEqual => Some(ASTBinaryOperatorKind::LessThanOrEqual),
             _ => None,
         };
-        kind.map(|kind| {
-            return ASTBinaryOperator {
-                kind,
-                token: token.clone(),
-            };
+        kind.map(|kind| ASTBinaryOperator {
+            kind,
+            token: token.clone(),
         })
     }
 
@@ -458,13 +947,671 @@ impl Parser {
             TokenKind::Tilde => Some(ASTUnaryOperatorKind::BitwiseNOT),
             TokenKind::ExclemationMark => Some(ASTUnaryOperatorKind::LogicNot),
             TokenKind::Minus => Some(ASTUnaryOperatorKind::Minus),
+            TokenKind::Plus => Some(ASTUnaryOperatorKind::Plus),
             _ => None,
         };
-        kind.map(|kind| {
-            return ASTUnaryOperator {
-                kind,
-                token: token.clone(),
-            };
+        kind.map(|kind| ASTUnaryOperator {
+            kind,
+            token: token.clone(),
         })
     }
 }
+
+// Lexes and parses any input without panicking, errors are reported in the
+// returned diagnostics and replaced by error nodes in the ast
+#[allow(dead_code)]
+pub fn try_parse(source: &str) -> (Ast, DiagnosticsColletion) {
+    let diagnostics_colletion = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+    let ast = Parser::from_input(source.to_string(), Rc::clone(&diagnostics_colletion)).parse();
+    let diagnostics = diagnostics_colletion.replace(DiagnosticsColletion::new());
+    (ast, diagnostics)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{try_parse, Parser};
+    use crate::ast::lexer::{Lexer, TokenKind};
+    use crate::ast::{
+        ASTBinaryOperatorKind, ASTExpressionKind, ASTStatementKind, ASTUnaryOperatorKind,
+    };
+    use crate::diagnostics::{DiagnosticsColletion, DiagnosticsColletionCell, Severity};
+    use std::{cell::RefCell, rc::Rc};
+
+    fn parse(input: &str) -> DiagnosticsColletionCell {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut parser = Parser::from_input(input.to_string(), Rc::clone(&diagnostics));
+        while parser.next_statement().is_some() {}
+        diagnostics
+    }
+
+    #[test]
+    fn should_report_trailing_tokens() {
+        for (input, trailing) in [
+            ("1 + 2 );", ")"),
+            ("(1 + 2)));", "))"),
+            ("1 + 2; }", "}"),
+            ("} ) 1;", "} )"),
+            ("{ 1 + 2 ) }", ")"),
+        ] {
+            let diagnostics = parse(input);
+            let diagnostics = &diagnostics.borrow().diagnostics;
+            assert_eq!(diagnostics.len(), 1, "{input}");
+            assert_eq!(diagnostics[0].message, "Unexpected trailing tokens");
+            assert_eq!(diagnostics[0].span.literal(input), trailing, "{input}");
+        }
+
+        let diagnostics = parse("1 + 2 )");
+        assert!(diagnostics
+            .borrow()
+            .diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message == "Unexpected trailing tokens"));
+    }
+
+    #[test]
+    fn should_parse_all_statements_into_ast() {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let input = "let a: i32 = 1; func f() -> i32 { return a; } if a > 0 { f(); } a;";
+        let mut parser = Parser::from_input(input.to_string(), Rc::clone(&diagnostics));
+        let ast = parser.parse();
+
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        assert_eq!(ast.statements.len(), 4);
+        assert!(parser.next_statement().is_none());
+    }
+
+    fn nested(depth: usize) -> String {
+        format!("{}1{};", "(".repeat(depth), ")".repeat(depth))
+    }
+
+    #[test]
+    fn should_report_too_deeply_nested_expression() {
+        let diagnostics = parse(&nested(100_000));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Expression too deeply nested, maximum depth of 256 exceeded"
+        );
+        assert_eq!(diagnostics[0].span.start, 257);
+
+        assert!(parse(&nested(200)).borrow().diagnostics.is_empty());
+    }
+
+    #[test]
+    fn should_limit_nesting_of_every_expression_kind() {
+        for input in [
+            "((1)) + (((2)));",
+            "- - - -1;",
+            "2 ** 2 ** 2 ** 2;",
+            "f(f(f(1)));",
+        ] {
+            let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+            let mut parser = Parser::from_input(input.to_string(), Rc::clone(&diagnostics));
+            parser.set_max_expression_depth(2);
+            let ast = parser.parse();
+            assert_eq!(ast.statements.len(), 1, "{input}");
+            let diagnostics = &diagnostics.borrow().diagnostics;
+            assert_eq!(diagnostics.len(), 1, "{input}");
+            assert!(diagnostics[0]
+                .message
+                .starts_with("Expression too deeply nested"));
+        }
+    }
+
+    #[test]
+    fn should_require_semicolon_between_statements() {
+        for input in ["1 2", "1\n2", "let a: i32 = 1\na"] {
+            let diagnostics = parse(input);
+            let diagnostics = diagnostics.borrow();
+            assert_eq!(diagnostics.len(), 1, "{input}");
+            assert!(diagnostics
+                .iter()
+                .next()
+                .unwrap()
+                .message()
+                .starts_with("Expected <;>"));
+        }
+    }
+
+    #[test]
+    fn should_end_statement_at_newline_if_enabled() {
+        let parse_lines = |input: &str| {
+            let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+            let mut parser = Parser::from_input(input.to_string(), Rc::clone(&diagnostics));
+            parser.set_newline_ends_statement(true);
+            let ast = parser.parse();
+            let count = diagnostics.borrow().len();
+            (ast, count)
+        };
+
+        let (ast, diagnostics) = parse_lines(
+            "let a: i32 = 1\nvar b: i32 = a\n  - 2\nb = b + a; a\nif a > 0 { return b }",
+        );
+        assert_eq!(diagnostics, 0);
+        assert_eq!(ast.statements().len(), 5);
+        assert_eq!(
+            ast.format(),
+            "let a: i32 = 1;\nvar b: i32 = a - 2;\nb = b + a;\na;\nif a > 0 {\n    return b;\n}\n"
+        );
+
+        // statements on the same line still need a ';'
+        let (_, diagnostics) = parse_lines("1 2");
+        assert_eq!(diagnostics, 1);
+    }
+
+    #[test]
+    fn should_skip_rest_of_broken_declaration() {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let input = "let x = 5; var = 1; { let y 2 } let z: i32 = 3;";
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        let diagnostics = diagnostics.borrow();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Expected <:>, but found <=>",
+                "Expected <Identifier>, but found <=>",
+                "Expected <:>, but found <Integer>",
+            ]
+        );
+        assert_eq!(ast.statements().len(), 4);
+        assert!(matches!(
+            ast.statements()[0].kind(),
+            ASTStatementKind::Expr(expr) if matches!(expr.kind(), ASTExpressionKind::Error(_))
+        ));
+        assert!(matches!(
+            ast.statements()[3].kind(),
+            ASTStatementKind::Let(_)
+        ));
+    }
+
+    #[test]
+    fn should_point_at_unclosed_parenthesis() {
+        let input = "let x: i32 = ((1 + 2);";
+        let diagnostics = parse(input);
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].message, "Expected <)>, but found <;>");
+        assert_eq!(diagnostics[1].message, "Unclosed '(' opened here");
+        assert_eq!(diagnostics[1].severity, Severity::Note);
+        assert_eq!(diagnostics[1].span.start, 13);
+        assert_eq!(diagnostics[1].span.literal(input), "(");
+
+        let diagnostics = parse("f(1, 2;");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics[2].message, "Unclosed '(' opened here");
+        assert_eq!(diagnostics[2].span.start, 1);
+    }
+
+    #[test]
+    fn should_report_unterminated_block() {
+        let diagnostics = parse("{ let x: i32 = 1;");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unterminated block, expected <}>");
+        assert_eq!(diagnostics[0].span.start, 0);
+    }
+
+    #[test]
+    fn should_terminate_on_unexpected_token_in_block() {
+        let diagnostics = parse("{ @ }");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert!(!diagnostics.is_empty());
+        assert_eq!(diagnostics[0].message, "Unknown character '@'");
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.message != "Expected expression, found bad"));
+    }
+
+    #[test]
+    fn should_report_found_token_when_expecting_expression() {
+        let input = "let x: i32 = ;";
+        let diagnostics = parse(input);
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics[0].message, "Expected expression, found ';'");
+        assert_eq!(diagnostics[0].span.literal(input), ";");
+        assert_eq!(
+            (diagnostics[0].span.start, diagnostics[0].span.end),
+            (13, 14)
+        );
+        assert_eq!(
+            (diagnostics[0].span.line, diagnostics[0].span.column),
+            (1, 14)
+        );
+
+        // without a type the declaration is broken before the initializer
+        let diagnostics = parse("let x = ;");
+        let messages: Vec<String> = diagnostics
+            .borrow()
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.clone())
+            .collect();
+        assert_eq!(messages, vec!["Expected <:>, but found <=>"]);
+
+        let diagnostics = parse("{ 1 + }");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics[0].message, "Expected expression, found '}'");
+        assert_eq!(diagnostics[0].span.start, 6);
+    }
+
+    #[test]
+    fn should_parse_bad_token_as_expression_error() {
+        let diagnostics = parse("let a: i32 = $; a + 1;");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unknown character '$'");
+    }
+
+    #[test]
+    fn should_terminate_on_unexpected_token_in_parameter_list() {
+        let input = "func f(1) { return 1; }";
+        let diagnostics = parse(input);
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].span.literal(input), "1");
+    }
+
+    #[test]
+    fn should_span_whole_binary_expression() {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let input = "let a: i32 = 1;\nlet b: i32 = (a +  2) * f(a, 3);";
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        let statement = &ast.statements()[1];
+        assert_eq!(
+            statement.span().literal(input),
+            "let b: i32 = (a +  2) * f(a, 3);"
+        );
+        let ASTStatementKind::Let(let_statement) = statement.kind() else {
+            panic!("expected a let statement");
+        };
+        let binary = let_statement.initializer();
+        let span = binary.span();
+        assert_eq!(span.literal(input), "(a +  2) * f(a, 3)");
+        assert_eq!((span.start, span.end), (29, 47));
+        assert_eq!((span.line, span.column), (2, 14));
+
+        let ASTExpressionKind::Binary(binary) = binary.kind() else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(binary.left().span().literal(input), "(a +  2)");
+        assert_eq!(binary.right().span().literal(input), "f(a, 3)");
+        let ASTExpressionKind::Parenthesized(parenthesized) = binary.left().kind() else {
+            panic!("expected a parenthesized expression");
+        };
+        assert_eq!(parenthesized.expression().span().literal(input), "a +  2");
+    }
+
+    #[test]
+    fn should_span_unary_and_compound_assignment() {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let input = "var a: i32 = 1; { a -= -a; }";
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        let ASTStatementKind::Compound(block) = ast.statements()[1].kind() else {
+            panic!("expected a block");
+        };
+        assert_eq!(ast.statements()[1].span().literal(input), "{ a -= -a; }");
+        let ASTStatementKind::Expr(assignment) = block.statements()[0].kind() else {
+            panic!("expected an expression statement");
+        };
+        assert_eq!(assignment.span().literal(input), "a -= -a");
+        let ASTExpressionKind::Assignment(assignment) = assignment.kind() else {
+            panic!("expected an assignment");
+        };
+        let ASTExpressionKind::Binary(binary) = assignment.expression().kind() else {
+            panic!("expected the desugared binary expression");
+        };
+        assert_eq!(binary.left().span().literal(input), "a");
+        assert_eq!(binary.right().span().literal(input), "-a");
+    }
+
+    #[test]
+    fn should_parse_default_parameter_values() {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let input = "func f(x, y: i32 = 2, z = y * 2) { return x + y + z; }";
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        let ASTStatementKind::FuncDecl(function) = ast.statements()[0].kind() else {
+            panic!("expected a function declaration");
+        };
+        let defaults: Vec<bool> = function
+            .arguments()
+            .iter()
+            .map(|argument| argument.default().is_some())
+            .collect();
+        assert_eq!(defaults, vec![false, true, true]);
+        assert_eq!(function.required_arguments(), 1);
+    }
+
+    #[test]
+    fn should_report_parameter_without_default_after_default() {
+        let diagnostics = parse("func f(x = 1, y) { return x + y; }");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Parameter 'y' needs a default value, it follows a parameter with one"
+        );
+        assert_eq!(diagnostics[0].span.start, 14);
+    }
+
+    #[test]
+    fn should_not_treat_trailing_identifier_as_assignment() {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut lexer = Lexer::new("a".to_string(), Rc::clone(&diagnostics));
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            if token.kind != TokenKind::Eof {
+                tokens.push(token);
+            }
+        }
+
+        let mut parser = Parser::new(tokens, Rc::clone(&diagnostics));
+        let mut statements = 0;
+        while parser.next_statement().is_some() {
+            statements += 1;
+        }
+        assert_eq!(statements, 1);
+
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Expected <;>, but found <Eof>");
+    }
+
+    #[test]
+    fn should_distinguish_block_value_from_statement() {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let input = "let a: i32 = { 1 }; let b: i32 = { 1; };";
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        let values: Vec<bool> = ast
+            .statements()
+            .iter()
+            .map(|statement| {
+                let ASTStatementKind::Let(let_statement) = statement.kind() else {
+                    panic!("expected a let statement");
+                };
+                let ASTExpressionKind::Block(block) = let_statement.initializer().kind() else {
+                    panic!("expected a block expression");
+                };
+                block.value().is_some()
+            })
+            .collect();
+        assert_eq!(values, vec![true, false]);
+    }
+
+    #[test]
+    fn should_parse_match_arms() {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let input = "match x { 1 => { a; }, -2 => {} _ => { b; } }";
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        let ASTStatementKind::Match(statement) = ast.statements()[0].kind() else {
+            panic!("expected a match statement");
+        };
+        assert_eq!(statement.scrutinee().span().literal(input), "x");
+        let patterns: Vec<Option<&str>> = statement
+            .arms()
+            .iter()
+            .map(|arm| arm.pattern().map(|pattern| pattern.span().literal(input)))
+            .collect();
+        assert_eq!(patterns, vec![Some("1"), Some("-2"), None]);
+    }
+
+    #[test]
+    fn should_report_non_literal_match_pattern() {
+        let diagnostics = parse("match x { y + 1 => {} }");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Expected literal pattern, but found 'y + 1'"
+        );
+    }
+
+    #[test]
+    fn should_parse_sign_after_binary_operator_as_unary() {
+        for (input, operator, sign) in [
+            (
+                "1 - -1;",
+                ASTBinaryOperatorKind::Minus,
+                ASTUnaryOperatorKind::Minus,
+            ),
+            (
+                "1--1;",
+                ASTBinaryOperatorKind::Minus,
+                ASTUnaryOperatorKind::Minus,
+            ),
+            (
+                "1 + +1;",
+                ASTBinaryOperatorKind::Plus,
+                ASTUnaryOperatorKind::Plus,
+            ),
+            (
+                "1++1;",
+                ASTBinaryOperatorKind::Plus,
+                ASTUnaryOperatorKind::Plus,
+            ),
+            (
+                "1 * -1;",
+                ASTBinaryOperatorKind::Multiply,
+                ASTUnaryOperatorKind::Minus,
+            ),
+        ] {
+            let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+            let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+            assert!(diagnostics.borrow().diagnostics.is_empty(), "{input}");
+
+            let ASTStatementKind::Expr(expr) = ast.statements()[0].kind() else {
+                panic!("expected an expression statement");
+            };
+            let ASTExpressionKind::Binary(binary) = expr.kind() else {
+                panic!("expected a binary expression for {input}");
+            };
+            assert_eq!(*binary.operator(), operator, "{input}");
+            assert_eq!(binary.left().span().literal(input), "1", "{input}");
+            let ASTExpressionKind::Unary(unary) = binary.right().kind() else {
+                panic!("expected a unary operand for {input}");
+            };
+            assert_eq!(*unary.operator(), sign, "{input}");
+            assert_eq!(unary.operand().span().literal(input), "1", "{input}");
+        }
+    }
+
+    #[test]
+    fn should_tell_tuples_from_parenthesized_expressions() {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let input = "(1, a + 2); (1); (1,);";
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        let expressions: Vec<&ASTExpressionKind> = ast
+            .statements()
+            .iter()
+            .map(|statement| match statement.kind() {
+                ASTStatementKind::Expr(expr) => expr.kind(),
+                _ => panic!("expected an expression statement"),
+            })
+            .collect();
+        let ASTExpressionKind::Tuple(tuple) = expressions[0] else {
+            panic!("expected a tuple");
+        };
+        let elements: Vec<&str> = tuple
+            .elements()
+            .iter()
+            .map(|element| element.span().literal(input))
+            .collect();
+        assert_eq!(elements, vec!["1", "a + 2"]);
+        assert!(matches!(
+            expressions[1],
+            ASTExpressionKind::Parenthesized(_)
+        ));
+        assert!(matches!(
+            expressions[2],
+            ASTExpressionKind::Tuple(tuple) if tuple.elements().len() == 1
+        ));
+    }
+
+    #[test]
+    fn should_parse_ternary_below_binary_operators() {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let input = "x = a + 1 ? b : c ? d : e || f;";
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        let ASTStatementKind::Expr(expr) = ast.statements()[0].kind() else {
+            panic!("expected an expression statement");
+        };
+        let ASTExpressionKind::Assignment(assignment) = expr.kind() else {
+            panic!("expected an assignment");
+        };
+        let ASTExpressionKind::Ternary(ternary) = assignment.expression().kind() else {
+            panic!("expected a ternary expression");
+        };
+        assert_eq!(ternary.condition().span().literal(input), "a + 1");
+        assert_eq!(ternary.then_expression().span().literal(input), "b");
+        let ASTExpressionKind::Ternary(nested) = ternary.else_expression().kind() else {
+            panic!("expected a nested ternary expression");
+        };
+        assert_eq!(nested.condition().span().literal(input), "c");
+        assert_eq!(nested.else_expression().span().literal(input), "e || f");
+    }
+
+    #[test]
+    fn should_report_ternary_without_else() {
+        let diagnostics = parse("1 ? 2;");
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics[0].message, "Expected <:>, but found <;>");
+    }
+
+    // xorshift, the inputs have to be the same on every run
+    fn garbage(seed: u64, count: usize) -> Vec<String> {
+        const PIECES: [&str; 50] = [
+            "let",
+            "var",
+            "func",
+            "if",
+            "else",
+            "while",
+            "for",
+            "in",
+            "match",
+            "return",
+            "break",
+            "continue",
+            "assert",
+            "true",
+            "x",
+            "_",
+            "i32",
+            "f64",
+            "str",
+            "char",
+            "0x",
+            "0b",
+            "1",
+            "2.5",
+            "1e",
+            "9",
+            "99999999999999999999",
+            "1_",
+            "\"",
+            "'",
+            "\\",
+            "\\u{",
+            "(",
+            ")",
+            "{",
+            "}",
+            ",",
+            ";",
+            ":",
+            "?",
+            "=>",
+            "->",
+            "=",
+            "+=",
+            "-",
+            "**",
+            "!",
+            "/*",
+            "//",
+            "\n",
+        ];
+        let mut state = seed;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        (0..count)
+            .map(|_| {
+                let length = next() % 40;
+                let mut input = String::new();
+                for _ in 0..length {
+                    let random = next();
+                    match random % 5 {
+                        // any character, also multi-byte ones
+                        0 => {
+                            input.push(char::from_u32((random >> 8) as u32 % 0x2FF).unwrap_or('?'))
+                        }
+                        1 => input.push(' '),
+                        _ => input.push_str(PIECES[(random >> 8) as usize % PIECES.len()]),
+                    }
+                }
+                input
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_parse_garbage_without_panic() {
+        for input in garbage(0x2545F4914F6CDD1D, 5000) {
+            let result = std::panic::catch_unwind(|| try_parse(&input));
+            assert!(result.is_ok(), "panicked on {input:?}");
+        }
+    }
+
+    // the nesting limits keep the parser within the stack of the main thread,
+    // test threads have a smaller one
+    #[test]
+    fn should_parse_deeply_nested_input_without_panic() {
+        let inputs = [
+            "{".repeat(100_000),
+            "if 1 ".repeat(100_000),
+            "-".repeat(100_000),
+            "(".repeat(100_000),
+            "f(".repeat(100_000),
+            "match x { 1 => ".repeat(100_000),
+            // the depth limit is hit at a '}' instead of a '{'
+            format!("{}if 1 }}", "{".repeat(256)),
+        ];
+        let parse_all = move || {
+            for input in inputs {
+                let (_, diagnostics) = try_parse(&input);
+                assert!(!diagnostics.diagnostics.is_empty());
+            }
+        };
+        std::thread::Builder::new()
+            .stack_size(8 * 1024 * 1024)
+            .spawn(parse_all)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn should_parse_division_of_identifier() {
+        let diagnostics = parse("let a: i32 = 4; a / 2;");
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+    }
+}
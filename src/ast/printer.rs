@@ -1,13 +1,36 @@
+use super::lexer::char_literal;
 use super::ASTVisitor;
 
-use termion::color::Fg;
+use std::io::{self, Stdout, Write};
 use termion::color::{self, White};
 
-pub struct ASTTreePrinter {
+// https://no-color.org: a non-empty NO_COLOR disables colored output
+fn no_color_from_env() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+fn foreground(color: &dyn color::Color, no_color: bool) -> String {
+    if no_color {
+        String::new()
+    } else {
+        color::Fg(color).to_string()
+    }
+}
+
+pub struct ASTTreePrinter<W: Write = Stdout> {
     indentation: usize,
+    writer: W,
+    // suppresses all color escape sequences, e.g. when writing to a file
+    no_color: bool,
 }
 
-impl ASTTreePrinter {
+impl ASTTreePrinter<Stdout> {
+    pub fn new() -> Self {
+        Self::with_writer(io::stdout())
+    }
+}
+
+impl<W: Write> ASTTreePrinter<W> {
     const INDENATION: usize = 2;
 
     const TEXT_COLOR: color::White = color::White;
@@ -21,12 +44,26 @@ impl ASTTreePrinter {
     const LET_STATEMENT_ICON: &str = nerd_font_symbols::md::MD_EQUAL;
     const FUNC_STATEMENT_ICON: &str = nerd_font_symbols::md::MD_FUNCTION_VARIANT;
     const FUNC_CALL_STATEMENT_ICON: &str = nerd_font_symbols::md::MD_FUNCTION;
-    const EXPR_ICON: &str = nerd_font_symbols::md::MD_FUNCTION_VARIANT;
     const BIN_EXPR_ICON: &str = nerd_font_symbols::cod::COD_SYMBOL_OPERATOR;
     const VARIABLE_ICON: &str = nerd_font_symbols::md::MD_VARIABLE;
+    const STRING_ICON: &str = nerd_font_symbols::cod::COD_SYMBOL_STRING;
 
-    pub fn new() -> Self {
-        Self { indentation: 0 }
+    pub fn with_writer(writer: W) -> Self {
+        Self {
+            indentation: 0,
+            writer,
+            no_color: no_color_from_env(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn set_no_color(&mut self, no_color: bool) {
+        self.no_color = no_color;
+    }
+
+    #[allow(dead_code)]
+    pub fn into_writer(self) -> W {
+        self.writer
     }
 
     fn increase_indentation(&mut self) {
@@ -36,19 +73,24 @@ impl ASTTreePrinter {
         self.indentation -= Self::INDENATION;
     }
 
-    fn print(&self, text: &str, text_color: &dyn color::Color) {
-        // println!("{}├─ {}", "│ ".repeat(self.indentation), text);
-        println!(
+    fn fg(&self, color: &dyn color::Color) -> String {
+        foreground(color, self.no_color)
+    }
+
+    fn print(&mut self, text: &str, text_color: &dyn color::Color) {
+        writeln!(
+            self.writer,
             "│{}└─ {}{}{}",
             " ".repeat(self.indentation),
-            color::Fg(text_color),
+            self.fg(text_color),
             text,
-            color::Fg(color::Reset)
-        );
+            self.fg(&color::Reset)
+        )
+        .unwrap();
     }
 }
 
-impl ASTVisitor for ASTTreePrinter {
+impl<W: Write> ASTVisitor for ASTTreePrinter<W> {
     fn visit_statement(&mut self, statement: &super::ASTStatement) {
         self.print(
             &format!("{}  Statement:", Self::STATEMENT_ICON),
@@ -74,8 +116,8 @@ impl ASTVisitor for ASTTreePrinter {
             &format!(
                 "{}  Declaration(Let): {}{}",
                 Self::LET_STATEMENT_ICON,
-                color::Fg(Self::TEXT_COLOR),
-                &statement.identifier.span.literal
+                self.fg(&Self::TEXT_COLOR),
+                &statement.identifier.literal
             ),
             &Self::LET_STATEMENT_COLOR,
         );
@@ -83,8 +125,8 @@ impl ASTVisitor for ASTTreePrinter {
         self.print(
             &format!(
                 "DataType: {}{}",
-                Fg(Self::TEXT_COLOR),
-                statement.data_type.span.literal
+                self.fg(&Self::TEXT_COLOR),
+                statement.data_type.literal
             ),
             &Self::TEXT_COLOR,
         );
@@ -97,8 +139,8 @@ impl ASTVisitor for ASTTreePrinter {
             &format!(
                 "{}  Declaration(Var): {}{}",
                 Self::LET_STATEMENT_ICON,
-                color::Fg(Self::TEXT_COLOR),
-                &statement.identifier.span.literal
+                self.fg(&Self::TEXT_COLOR),
+                &statement.identifier.literal
             ),
             &Self::LET_STATEMENT_COLOR,
         );
@@ -106,8 +148,8 @@ impl ASTVisitor for ASTTreePrinter {
         self.print(
             &format!(
                 "DataType: {}{}",
-                Fg(Self::TEXT_COLOR),
-                statement.data_type.span.literal
+                self.fg(&Self::TEXT_COLOR),
+                statement.data_type.literal
             ),
             &Self::TEXT_COLOR,
         );
@@ -133,7 +175,7 @@ impl ASTVisitor for ASTTreePrinter {
 
     fn visit_for_loop_statement(&mut self, statement: &super::ASTForStatement) {
         self.print(
-            &format!("For: {}", statement.loop_variable.span.literal),
+            &format!("For: {}", statement.loop_variable.literal),
             &color::Blue,
         );
         self.increase_indentation();
@@ -163,35 +205,80 @@ impl ASTVisitor for ASTTreePrinter {
         self.decrease_indentation();
     }
 
+    fn visit_match_statement(&mut self, statement: &super::ASTMatchStatement) {
+        self.print("Match:", &color::Blue);
+        self.increase_indentation();
+        self.visit_expression(&statement.scrutinee);
+        for arm in statement.arms.iter() {
+            match &arm.pattern {
+                Some(pattern) => {
+                    self.print("Case:", &Self::TEXT_COLOR);
+                    self.increase_indentation();
+                    self.visit_expression(pattern);
+                }
+                None => {
+                    self.print("Default:", &Self::TEXT_COLOR);
+                    self.increase_indentation();
+                }
+            }
+            self.visit_statement(&arm.body);
+            self.decrease_indentation();
+        }
+        self.decrease_indentation();
+    }
+
+    fn visit_break_statement(&mut self, _statement: &super::ASTBreakStatement) {
+        self.print("Break", &color::Blue);
+    }
+
+    fn visit_continue_statement(&mut self, _statement: &super::ASTContinueStatement) {
+        self.print("Continue", &color::Blue);
+    }
+
+    fn visit_assert_statement(&mut self, statement: &super::ASTAssertStatement) {
+        self.print("Assert:", &color::Blue);
+        self.increase_indentation();
+        self.visit_expression(&statement.condition);
+        self.decrease_indentation();
+    }
+
     fn visit_funtion_statement(&mut self, function: &super::ASTFunctionStatement) {
         self.print(
             &format!(
                 "{}  Function: {}{}",
                 Self::FUNC_STATEMENT_ICON,
-                color::Fg(Self::TEXT_COLOR),
-                &function.identifier.span.literal
+                self.fg(&Self::TEXT_COLOR),
+                &function.identifier.literal
             ),
             &Self::TEXT_COLOR,
         );
 
         self.increase_indentation();
-        self.print(&format!("Arguments:"), &Self::TEXT_COLOR);
+        self.print("Arguments:", &Self::TEXT_COLOR);
         self.increase_indentation();
         for arg in function.arguments.iter() {
+            let data_type = arg.data_type.as_ref().map_or(String::new(), |data_type| {
+                format!(" ({})", data_type.literal)
+            });
             self.print(
                 &format!(
-                    "{}  Argument: {}{} ({})",
+                    "{}  Argument: {}{}{}",
                     Self::FUNC_STATEMENT_ICON,
-                    color::Fg(Self::TEXT_COLOR),
-                    &arg.identifier.span.literal,
-                    &arg.data_type.span.literal
+                    self.fg(&Self::TEXT_COLOR),
+                    &arg.identifier.literal,
+                    data_type
                 ),
                 &Self::TEXT_COLOR,
             );
+            if let Some(default) = &arg.default {
+                self.increase_indentation();
+                self.visit_expression(default);
+                self.decrease_indentation();
+            }
         }
         self.decrease_indentation();
 
-        self.print(&format!("Body:"), &Self::TEXT_COLOR);
+        self.print("Body:", &Self::TEXT_COLOR);
         self.increase_indentation();
 
         if let super::ASTStatementKind::Compound(statement) = &function.body.kind {
@@ -206,7 +293,7 @@ impl ASTVisitor for ASTTreePrinter {
         //     &Self::EXPR_COLOR,
         // );
         // self.increase_indentation();
-        ASTVisitor::do_visit_expression(self, &expr);
+        ASTVisitor::do_visit_expression(self, expr);
         // self.decrease_indentation();
     }
 
@@ -216,8 +303,8 @@ impl ASTVisitor for ASTTreePrinter {
             &format!(
                 "{}  Assignment: {}{}",
                 nerd_font_symbols::md::MD_EQUAL,
-                color::Fg(Self::OPERATOR_COLOR),
-                expr.identifier.span.literal
+                self.fg(&Self::OPERATOR_COLOR),
+                expr.identifier.literal
             ),
             &Self::TEXT_COLOR,
         );
@@ -230,14 +317,14 @@ impl ASTVisitor for ASTTreePrinter {
             &format!(
                 "{}  FunctionCall: {}{}",
                 Self::FUNC_CALL_STATEMENT_ICON,
-                color::Fg(Self::TEXT_COLOR),
-                &expr.identifier.span.literal
+                self.fg(&Self::TEXT_COLOR),
+                &expr.identifier.literal
             ),
             &Self::TEXT_COLOR,
         );
         self.increase_indentation();
         for expr in expr.arguments.iter() {
-            ASTVisitor::do_visit_expression(self, &expr);
+            ASTVisitor::do_visit_expression(self, expr);
         }
         self.decrease_indentation();
     }
@@ -254,8 +341,8 @@ impl ASTVisitor for ASTTreePrinter {
             &format!(
                 "{}  Unary: {}{}",
                 Self::BIN_EXPR_ICON,
-                color::Fg(Self::OPERATOR_COLOR),
-                expr.operator.token.span.literal
+                self.fg(&Self::OPERATOR_COLOR),
+                expr.operator.token.literal
             ),
             &Self::BIN_EXPR_COLOR,
         );
@@ -269,8 +356,8 @@ impl ASTVisitor for ASTTreePrinter {
             &format!(
                 "{}  Binary: {}{}",
                 Self::BIN_EXPR_ICON,
-                color::Fg(Self::OPERATOR_COLOR),
-                expr.operator.token.span.literal
+                self.fg(&Self::OPERATOR_COLOR),
+                expr.operator.token.literal
             ),
             &Self::BIN_EXPR_COLOR,
         );
@@ -292,6 +379,47 @@ impl ASTVisitor for ASTTreePrinter {
         );
         self.increase_indentation();
         self.visit_expression(&expr.expr);
+        self.decrease_indentation();
+        // closes the group at the level it was opened, nested groups stay
+        // distinguishable
+        self.print(")", &color::Magenta);
+    }
+
+    fn visit_ternary_expression(&mut self, expr: &super::ASTTernaryExpression) {
+        self.print("Ternary:", &color::Magenta);
+        self.increase_indentation();
+        self.visit_expression(&expr.condition);
+        self.print("Then:", &Self::TEXT_COLOR);
+        self.increase_indentation();
+        self.visit_expression(&expr.then_expr);
+        self.decrease_indentation();
+        self.print("Else:", &Self::TEXT_COLOR);
+        self.increase_indentation();
+        self.visit_expression(&expr.else_expr);
+        self.decrease_indentation();
+        self.decrease_indentation();
+    }
+
+    fn visit_tuple_expression(&mut self, expr: &super::ASTTupleExpression) {
+        self.print(
+            &format!("{}  Tuple:", nerd_font_symbols::md::MD_CODE_PARENTHESES),
+            &color::Magenta,
+        );
+        self.increase_indentation();
+        for element in expr.elements.iter() {
+            self.visit_expression(element);
+        }
+        self.decrease_indentation();
+    }
+
+    fn visit_block_expression(&mut self, block: &super::ASTCompoundStatement) {
+        self.print(
+            &format!("{}  Block:", nerd_font_symbols::md::MD_CODE_BRACES),
+            &Self::EXPR_COLOR,
+        );
+        self.increase_indentation();
+        self.visit_compound_statement(block);
+        self.decrease_indentation();
     }
 
     fn visit_binary_operator(&mut self, op: &super::ASTBinaryOperator) {
@@ -302,6 +430,8 @@ impl ASTVisitor for ASTTreePrinter {
                 super::ASTBinaryOperatorKind::Minus => "-",
                 super::ASTBinaryOperatorKind::Multiply => "*",
                 super::ASTBinaryOperatorKind::Divide => "/",
+                super::ASTBinaryOperatorKind::Modulo => "%",
+                super::ASTBinaryOperatorKind::Power => "**",
                 super::ASTBinaryOperatorKind::EqualTo => "==",
                 super::ASTBinaryOperatorKind::NotEqualTo => "!=",
                 super::ASTBinaryOperatorKind::LogicAND => "&&",
@@ -329,11 +459,28 @@ impl ASTVisitor for ASTTreePrinter {
     fn visit_float(&mut self, float: &f64) {
         self.print(&format!("Float: {}", float), &Self::TEXT_COLOR);
     }
+
+    fn visit_boolean(&mut self, boolean: &bool) {
+        self.print(&format!("Boolean: {}", boolean), &Self::TEXT_COLOR);
+    }
+
+    fn visit_char(&mut self, c: &char) {
+        self.print(&format!("Char: {}", char_literal(*c)), &Self::TEXT_COLOR);
+    }
+
+    fn visit_string(&mut self, string: &super::Token) {
+        self.print(
+            &format!("{}  String: {}", Self::STRING_ICON, string.literal),
+            &Self::TEXT_COLOR,
+        );
+    }
 }
 
 pub struct ASTHiglightPrinter {
     indent: usize,
     result: String,
+    // suppresses all color escape sequences like in the tree printer
+    no_color: bool,
 }
 
 impl ASTHiglightPrinter {
@@ -341,11 +488,12 @@ impl ASTHiglightPrinter {
 
     const KEYWORD_COLOR: color::Green = color::Green;
     const TYPE_COLOR: color::Cyan = color::Cyan;
-    const OPERATOR_COLOR: color::Green = color::Green;
     const TEXT_COLOR: color::White = color::White;
 
     const INTEGER_COLOR: color::Cyan = color::Cyan;
     const FLOAT_COLOR: color::Cyan = color::Cyan;
+    const BOOLEAN_COLOR: color::Green = color::Green;
+    const STRING_COLOR: color::LightGreen = color::LightGreen;
     const LET_COLOR: color::Green = color::Green;
     const FUNC_COLOR: color::Green = color::Green;
     const FUNC_CALL_COLOR: color::Yellow = color::Yellow;
@@ -356,9 +504,19 @@ impl ASTHiglightPrinter {
         Self {
             indent: 0,
             result: "".to_string(),
+            no_color: no_color_from_env(),
         }
     }
 
+    #[allow(dead_code)]
+    pub fn set_no_color(&mut self, no_color: bool) {
+        self.no_color = no_color;
+    }
+
+    fn fg(&self, color: &dyn color::Color) -> String {
+        foreground(color, self.no_color)
+    }
+
     pub fn print_result(&self) {
         let decoration = "=".repeat(80) + "\n";
         println!(
@@ -369,7 +527,7 @@ impl ASTHiglightPrinter {
                 .map(|(i, line)| format!("{:3} │ {}", i, line))
                 .collect::<Vec<String>>()
                 .join("\n"),
-            Fg(White)
+            self.fg(&White)
         );
     }
 
@@ -396,7 +554,7 @@ impl ASTHiglightPrinter {
         self.result.push_str(&format!(
             "{}{}",
             " ".repeat(self.indent),
-            color::Fg(color::Reset)
+            self.fg(&color::Reset)
         ));
     }
     fn print_with_indent(&mut self, text: &str) {
@@ -404,16 +562,16 @@ impl ASTHiglightPrinter {
             "{}{}{}",
             " ".repeat(self.indent),
             text,
-            color::Fg(color::Reset)
+            self.fg(&color::Reset)
         ));
     }
     fn print(&mut self, text: &str) {
         self.result
-            .push_str(&format!("{}{}", text, color::Fg(color::Reset)));
+            .push_str(&format!("{}{}", text, self.fg(&color::Reset)));
     }
 
     fn visit_idenifier(&mut self, identifier: &String) {
-        self.print(&format!("{}{}", Fg(Self::TEXT_COLOR), identifier));
+        self.print(&format!("{}{}", self.fg(&Self::TEXT_COLOR), identifier));
     }
 }
 
@@ -423,7 +581,7 @@ impl ASTVisitor for ASTHiglightPrinter {
     }
 
     fn visit_return_statement(&mut self, statement: &super::ASTReturnStatement) {
-        self.print_with_indent(&format!("{}return", Fg(Self::LET_COLOR)));
+        self.print_with_indent(&format!("{}return", self.fg(&Self::LET_COLOR)));
         self.add_whitespace();
         self.visit_expression(&statement.expr);
         self.add_semicolon();
@@ -431,16 +589,16 @@ impl ASTVisitor for ASTHiglightPrinter {
     }
 
     fn visit_let_statement(&mut self, statement: &super::ASTLetStatement) {
-        self.print_with_indent(&format!("{}let", Fg(Self::LET_COLOR)));
+        self.print_with_indent(&format!("{}let", self.fg(&Self::LET_COLOR)));
         self.add_whitespace();
-        self.visit_idenifier(&statement.identifier.span.literal);
+        self.visit_idenifier(&statement.identifier.literal);
         self.print(&format!(
             ": {}{}",
-            Fg(Self::TYPE_COLOR),
-            statement.data_type.span.literal
+            self.fg(&Self::TYPE_COLOR),
+            statement.data_type.literal
         ));
         self.add_whitespace();
-        self.print(&format!("{}=", Fg(Self::TEXT_COLOR)));
+        self.print(&format!("{}=", self.fg(&Self::TEXT_COLOR)));
         self.add_whitespace();
         self.visit_expression(&statement.initializer);
         self.add_semicolon();
@@ -448,16 +606,16 @@ impl ASTVisitor for ASTHiglightPrinter {
     }
 
     fn visit_var_statement(&mut self, statement: &super::ASTVarStatement) {
-        self.print_with_indent(&format!("{}var", Fg(Self::LET_COLOR)));
+        self.print_with_indent(&format!("{}var", self.fg(&Self::LET_COLOR)));
         self.add_whitespace();
-        self.visit_idenifier(&statement.identifier.span.literal);
+        self.visit_idenifier(&statement.identifier.literal);
         self.print(&format!(
             ": {}{}",
-            Fg(Self::TYPE_COLOR),
-            statement.data_type.span.literal
+            self.fg(&Self::TYPE_COLOR),
+            statement.data_type.literal
         ));
         self.add_whitespace();
-        self.print(&format!("{}=", Fg(Self::TEXT_COLOR)));
+        self.print(&format!("{}=", self.fg(&Self::TEXT_COLOR)));
         self.add_whitespace();
         self.visit_expression(&statement.initializer);
         self.add_semicolon();
@@ -465,21 +623,21 @@ impl ASTVisitor for ASTHiglightPrinter {
     }
 
     fn visit_compound_statement(&mut self, statement: &super::ASTCompoundStatement) {
-        self.print(&format!("{}{}", Fg(Self::TEXT_COLOR), '{'));
+        self.print(&format!("{}{}", self.fg(&Self::TEXT_COLOR), '{'));
         self.add_newline();
         self.increase_indentation();
         for statement in statement.statements.iter() {
             self.visit_statement(statement);
         }
         self.decrease_indentation();
-        self.print_with_indent(&format!("{}{}", Fg(Self::TEXT_COLOR), '}'));
+        self.print_with_indent(&format!("{}{}", self.fg(&Self::TEXT_COLOR), '}'));
     }
 
     fn visit_if_statement(&mut self, statement: &super::ASTIfStatement) {
         self.print_with_indent(&format!(
             "{}if{} ",
-            Fg(Self::KEYWORD_COLOR),
-            Fg(Self::TEXT_COLOR),
+            self.fg(&Self::KEYWORD_COLOR),
+            self.fg(&Self::TEXT_COLOR),
         ));
         self.visit_expression(&statement.condition);
         self.add_whitespace();
@@ -489,8 +647,8 @@ impl ASTVisitor for ASTHiglightPrinter {
         if let Some(else_branch) = &statement.else_branch {
             self.print(&format!(
                 "{} else{} ",
-                Fg(Self::KEYWORD_COLOR),
-                Fg(Self::TEXT_COLOR),
+                self.fg(&Self::KEYWORD_COLOR),
+                self.fg(&Self::TEXT_COLOR),
             ));
             self.visit_statement(&else_branch.else_branch);
         }
@@ -500,9 +658,9 @@ impl ASTVisitor for ASTHiglightPrinter {
     fn visit_for_loop_statement(&mut self, statement: &super::ASTForStatement) {
         self.print_with_indent(&format!(
             "{}for{} {} in",
-            Fg(Self::KEYWORD_COLOR),
-            Fg(Self::TEXT_COLOR),
-            statement.loop_variable.span.literal
+            self.fg(&Self::KEYWORD_COLOR),
+            self.fg(&Self::TEXT_COLOR),
+            statement.loop_variable.literal
         ));
         self.add_whitespace();
         self.visit_expression(&statement.range.0);
@@ -516,8 +674,8 @@ impl ASTVisitor for ASTHiglightPrinter {
     fn visit_while_loop_statement(&mut self, statement: &super::ASTWhileStatement) {
         self.print_with_indent(&format!(
             "{}while{}",
-            Fg(Self::KEYWORD_COLOR),
-            Fg(Self::TEXT_COLOR),
+            self.fg(&Self::KEYWORD_COLOR),
+            self.fg(&Self::TEXT_COLOR),
         ));
         self.add_whitespace();
         self.visit_expression(&statement.condition);
@@ -526,29 +684,88 @@ impl ASTVisitor for ASTHiglightPrinter {
         self.add_newline();
     }
 
+    fn visit_match_statement(&mut self, statement: &super::ASTMatchStatement) {
+        self.print_with_indent(&format!(
+            "{}match{}",
+            self.fg(&Self::KEYWORD_COLOR),
+            self.fg(&Self::TEXT_COLOR),
+        ));
+        self.add_whitespace();
+        self.visit_expression(&statement.scrutinee);
+        self.print(&format!("{} {{", self.fg(&Self::TEXT_COLOR)));
+        self.add_newline();
+        self.increase_indentation();
+        for arm in statement.arms.iter() {
+            self.print_indent();
+            match &arm.pattern {
+                Some(pattern) => self.visit_expression(pattern),
+                None => self.print(&format!("{}_", self.fg(&Self::TEXT_COLOR))),
+            }
+            self.print(&format!("{} => ", self.fg(&Self::TEXT_COLOR)));
+            self.visit_statement(&arm.body);
+            self.add_newline();
+        }
+        self.decrease_indentation();
+        self.print_with_indent(&format!("{}}}", self.fg(&Self::TEXT_COLOR)));
+        self.add_newline();
+    }
+
+    fn visit_break_statement(&mut self, _statement: &super::ASTBreakStatement) {
+        self.print_with_indent(&format!("{}break", self.fg(&Self::KEYWORD_COLOR)));
+        self.add_semicolon();
+        self.add_newline();
+    }
+
+    fn visit_continue_statement(&mut self, _statement: &super::ASTContinueStatement) {
+        self.print_with_indent(&format!("{}continue", self.fg(&Self::KEYWORD_COLOR)));
+        self.add_semicolon();
+        self.add_newline();
+    }
+
+    fn visit_assert_statement(&mut self, statement: &super::ASTAssertStatement) {
+        self.print_with_indent(&format!(
+            "{}assert{}",
+            self.fg(&Self::KEYWORD_COLOR),
+            self.fg(&Self::TEXT_COLOR),
+        ));
+        self.add_whitespace();
+        self.visit_expression(&statement.condition);
+        self.add_semicolon();
+        self.add_newline();
+    }
+
     fn visit_funtion_statement(&mut self, function: &super::ASTFunctionStatement) {
         self.print_with_indent(&format!(
             "{}func {}{}{}(",
-            Fg(Self::FUNC_COLOR),
-            Fg(Self::FUNC_NAME_COLOR),
-            function.identifier.span.literal,
-            Fg(Self::TEXT_COLOR),
+            self.fg(&Self::FUNC_COLOR),
+            self.fg(&Self::FUNC_NAME_COLOR),
+            function.identifier.literal,
+            self.fg(&Self::TEXT_COLOR),
         ));
         for (i, arg) in function.arguments.iter().enumerate() {
             if i != 0 {
-                self.print(&format!("{},", Fg(Self::TEXT_COLOR)));
+                self.print(&format!("{},", self.fg(&Self::TEXT_COLOR)));
                 self.add_whitespace();
             }
             self.print(&format!(
-                "{}{}: {}{}",
-                Fg(Self::TEXT_COLOR),
-                arg.identifier.span.literal,
-                Fg(Self::TYPE_COLOR),
-                arg.data_type.span.literal,
+                "{}{}",
+                self.fg(&Self::TEXT_COLOR),
+                arg.identifier.literal,
             ));
+            if let Some(data_type) = &arg.data_type {
+                self.print(&format!(
+                    ": {}{}",
+                    self.fg(&Self::TYPE_COLOR),
+                    data_type.literal
+                ));
+            }
+            if let Some(default) = &arg.default {
+                self.print(&format!("{} = ", self.fg(&Self::TEXT_COLOR)));
+                self.visit_expression(default);
+            }
         }
 
-        self.print(&format!("{}) ", Fg(Self::TEXT_COLOR)));
+        self.print(&format!("{}) ", self.fg(&Self::TEXT_COLOR)));
         if let super::ASTStatementKind::Compound(statement) = &function.body.kind {
             self.visit_compound_statement(statement);
         }
@@ -558,9 +775,9 @@ impl ASTVisitor for ASTHiglightPrinter {
     fn visit_assignment_expression(&mut self, expr: &super::ASTAssignmentExpression) {
         self.print_with_indent(&format!(
             "{}{}{} = ",
-            Fg(Self::VARIABLE_COLOR),
-            expr.identifier.span.literal,
-            Fg(Self::TEXT_COLOR)
+            self.fg(&Self::VARIABLE_COLOR),
+            expr.identifier.literal,
+            self.fg(&Self::TEXT_COLOR)
         ));
         self.visit_expression(&expr.expr);
         self.add_semicolon();
@@ -570,25 +787,25 @@ impl ASTVisitor for ASTHiglightPrinter {
     fn visit_function_call_expression(&mut self, expr: &super::ASTFunctionCallExpression) {
         self.print(&format!(
             "{}{}{}(",
-            Fg(Self::FUNC_CALL_COLOR),
+            self.fg(&Self::FUNC_CALL_COLOR),
             expr.identifier(),
-            Fg(Self::TEXT_COLOR)
+            self.fg(&Self::TEXT_COLOR)
         ));
 
         for (i, arg) in expr.arguments.iter().enumerate() {
             if i != 0 {
-                self.print(&format!("{},", Fg(Self::TEXT_COLOR)));
+                self.print(&format!("{},", self.fg(&Self::TEXT_COLOR)));
                 self.add_whitespace();
             }
             self.visit_expression(arg);
         }
-        self.print(&format!("{})", Fg(Self::TEXT_COLOR)));
+        self.print(&format!("{})", self.fg(&Self::TEXT_COLOR)));
     }
 
     fn visit_variable_expression(&mut self, expr: &super::ASTVariableExpression) {
         self.print(&format!(
             "{}{}",
-            Fg(Self::VARIABLE_COLOR),
+            self.fg(&Self::VARIABLE_COLOR),
             expr.identifier()
         ));
     }
@@ -596,8 +813,8 @@ impl ASTVisitor for ASTHiglightPrinter {
     fn visit_unary_expression(&mut self, expr: &super::ASTUnaryExpression) {
         self.print(&format!(
             "{}{}",
-            Fg(Self::TEXT_COLOR),
-            expr.operator.token.span.literal
+            self.fg(&Self::TEXT_COLOR),
+            expr.operator.token.literal
         ));
         self.visit_expression(&expr.expr);
     }
@@ -607,28 +824,57 @@ impl ASTVisitor for ASTHiglightPrinter {
         self.add_whitespace();
         self.print(&format!(
             "{}{}",
-            Fg(Self::TEXT_COLOR),
-            expr.operator.token.span.literal
+            self.fg(&Self::TEXT_COLOR),
+            expr.operator.token.literal
         ));
         self.add_whitespace();
         self.visit_expression(&expr.right);
     }
 
     fn visit_parenthesised_expression(&mut self, expr: &super::ASTParenthesizedExpression) {
-        self.print(&format!("{}(", Fg(Self::TEXT_COLOR)));
+        self.print(&format!("{}(", self.fg(&Self::TEXT_COLOR)));
         self.visit_expression(&expr.expr);
-        self.print(&format!("{})", Fg(Self::TEXT_COLOR)));
+        self.print(&format!("{})", self.fg(&Self::TEXT_COLOR)));
+    }
+
+    fn visit_ternary_expression(&mut self, expr: &super::ASTTernaryExpression) {
+        self.visit_expression(&expr.condition);
+        self.add_whitespace();
+        self.print(&format!("{}?", self.fg(&Self::TEXT_COLOR)));
+        self.add_whitespace();
+        self.visit_expression(&expr.then_expr);
+        self.add_whitespace();
+        self.print(&format!("{}:", self.fg(&Self::TEXT_COLOR)));
+        self.add_whitespace();
+        self.visit_expression(&expr.else_expr);
+    }
+
+    fn visit_tuple_expression(&mut self, expr: &super::ASTTupleExpression) {
+        self.print(&format!("{}(", self.fg(&Self::TEXT_COLOR)));
+        for (i, element) in expr.elements.iter().enumerate() {
+            if i != 0 {
+                self.print(&format!("{},", self.fg(&Self::TEXT_COLOR)));
+                self.add_whitespace();
+            }
+            self.visit_expression(element);
+        }
+        if expr.elements.len() == 1 {
+            self.print(&format!("{},", self.fg(&Self::TEXT_COLOR)));
+        }
+        self.print(&format!("{})", self.fg(&Self::TEXT_COLOR)));
     }
 
     fn visit_binary_operator(&mut self, op: &super::ASTBinaryOperator) {
         self.print(&format!(
             "{}{}",
-            Fg(Self::TEXT_COLOR),
+            self.fg(&Self::TEXT_COLOR),
             match op.kind {
                 super::ASTBinaryOperatorKind::Plus => "+",
                 super::ASTBinaryOperatorKind::Minus => "-",
                 super::ASTBinaryOperatorKind::Multiply => "*",
                 super::ASTBinaryOperatorKind::Divide => "/",
+                super::ASTBinaryOperatorKind::Modulo => "%",
+                super::ASTBinaryOperatorKind::Power => "**",
                 super::ASTBinaryOperatorKind::EqualTo => "==",
                 super::ASTBinaryOperatorKind::NotEqualTo => "!=",
                 super::ASTBinaryOperatorKind::LogicAND => "&&",
@@ -645,9 +891,163 @@ impl ASTVisitor for ASTHiglightPrinter {
     }
 
     fn visit_integer(&mut self, integer: &i64) {
-        self.print(&format!("{}{}", Fg(Self::INTEGER_COLOR), integer));
+        self.print(&format!("{}{}", self.fg(&Self::INTEGER_COLOR), integer));
     }
     fn visit_float(&mut self, float: &f64) {
-        self.print(&format!("{}{}", Fg(Self::FLOAT_COLOR), float));
+        self.print(&format!("{}{}", self.fg(&Self::FLOAT_COLOR), float));
+    }
+    fn visit_boolean(&mut self, boolean: &bool) {
+        self.print(&format!("{}{}", self.fg(&Self::BOOLEAN_COLOR), boolean));
+    }
+    fn visit_char(&mut self, c: &char) {
+        self.print(&format!(
+            "{}{}",
+            self.fg(&Self::STRING_COLOR),
+            char_literal(*c)
+        ));
+    }
+    fn visit_string(&mut self, string: &super::Token) {
+        self.print(&format!(
+            "{}{}",
+            self.fg(&Self::STRING_COLOR),
+            string.literal
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::{ASTHiglightPrinter, ASTTreePrinter};
+    use crate::ast::{parser::Parser, Ast};
+    use crate::diagnostics::DiagnosticsColletion;
+
+    fn parse(input: &str) -> Ast {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut parser = Parser::from_input(input.to_string(), Rc::clone(&diagnostics));
+        let ast = parser.parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        ast
+    }
+
+    #[test]
+    fn should_highlight_without_color() {
+        let mut printer = ASTHiglightPrinter::new();
+        printer.set_no_color(true);
+        parse("let a: i32 = 1 + 2; var b: bool = a > 2;").visit(&mut printer);
+        assert_eq!(
+            printer.result,
+            "let a: i32 = 1 + 2;\nvar b: bool = a > 2;\n"
+        );
+    }
+
+    fn print_tree(input: &str, no_color: bool) -> String {
+        let mut printer = ASTTreePrinter::with_writer(Vec::new());
+        printer.set_no_color(no_color);
+        parse(input).visit(&mut printer);
+        String::from_utf8(printer.into_writer()).unwrap()
+    }
+
+    #[test]
+    fn should_write_tree_to_writer() {
+        let output = print_tree("let a: i32 = 1 + 2;", false);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[0].contains("Statement:"));
+        assert!(lines[1].contains("Declaration(Let): "));
+        assert!(lines[3].contains("Binary: "));
+        assert!(lines[4].starts_with("│      └─ "));
+        assert!(lines[4].contains("Integer: 1"));
+        assert!(lines[5].contains("Integer: 2"));
+    }
+
+    #[test]
+    fn should_print_plain_tree_without_escape_sequences() {
+        let input = "func f(a: i32) -> i32 { return a * (2 + 3); }";
+        let colored = print_tree(input, false);
+        let plain = print_tree(input, true);
+
+        assert!(colored.contains('\u{1b}'));
+        assert!(!plain.contains('\u{1b}'));
+        assert_eq!(colored.lines().count(), plain.lines().count());
+        for (colored, plain) in colored.lines().zip(plain.lines()) {
+            assert_eq!(strip_escape_sequences(colored), plain);
+        }
+    }
+
+    fn strip_escape_sequences(text: &str) -> String {
+        let mut result = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\u{1b}' {
+                chars.by_ref().find(|c| *c == 'm');
+            } else {
+                result.push(c);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn should_restore_indentation_after_parenthesized_expression() {
+        let output = print_tree("(1 + 2) + 3;", true);
+        let lines: Vec<&str> = output.lines().collect();
+        let indentation = |line: &str| line.find("└─").unwrap();
+
+        assert_eq!(lines.len(), 8);
+        assert!(lines[2].contains("Parenthesized:"));
+        assert!(lines[6].ends_with(')'));
+        assert_eq!(indentation(lines[6]), indentation(lines[2]));
+        assert!(lines[7].contains("Integer: 3"));
+        assert_eq!(indentation(lines[7]), indentation(lines[2]));
+    }
+
+    #[test]
+    fn should_close_nested_parenthesized_expressions() {
+        assert_eq!(
+            print_tree("((1));", true),
+            concat!(
+                "│└─ \u{f04a0}  Statement:\n",
+                "│  └─ \u{f0172}  Parenthesized:\n",
+                "│    └─ \u{f0172}  Parenthesized:\n",
+                "│      └─ Integer: 1\n",
+                "│    └─ )\n",
+                "│  └─ )\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_print_match_arms() {
+        assert_eq!(
+            print_tree("match 1 { 1 => {} _ => {} }", true),
+            concat!(
+                "│└─ \u{f04a0}  Statement:\n",
+                "│  └─ Match:\n",
+                "│    └─ Integer: 1\n",
+                "│    └─ Case:\n",
+                "│      └─ Integer: 1\n",
+                "│      └─ \u{f04a0}  Statement:\n",
+                "│    └─ Default:\n",
+                "│      └─ \u{f04a0}  Statement:\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_print_nested_unary_expressions() {
+        assert_eq!(
+            print_tree("-~!1 + 2;", true),
+            concat!(
+                "│└─ \u{f04a0}  Statement:\n",
+                "│  └─ \u{eb64}  Binary: +\n",
+                "│    └─ \u{eb64}  Unary: -\n",
+                "│      └─ \u{eb64}  Unary: ~\n",
+                "│        └─ \u{eb64}  Unary: !\n",
+                "│          └─ Integer: 1\n",
+                "│    └─ Integer: 2\n",
+            )
+        );
     }
 }
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use super::{
+    ASTAssignmentExpression, ASTCompoundStatement, ASTForStatement, ASTFunctionStatement,
+    ASTLetStatement, ASTStatement, ASTStatementKind, ASTVarStatement, ASTVariableExpression,
+    ASTVisitor, Ast,
+};
+
+// Where the solver finds a variable: `depth` scopes outwards from the
+// innermost one, at `slot` in the declaration order of that scope
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolvedVariable {
+    pub depth: usize,
+    pub slot: usize,
+}
+
+type Scope = HashMap<String, usize>;
+
+// Resolves every variable once so the solver doesn't look it up by name.
+// The scopes mirror the ones of the solver: a block opens a scope, a function
+// body sees its arguments and the scopes enclosing its declaration. Variables
+// that are declared after the function using them, or not at all, stay
+// unresolved and are looked up by name.
+pub struct Resolver {
+    scopes: Vec<Scope>,
+}
+
+impl Resolver {
+    // `scopes` are the slots of the variables the solver already declared,
+    // e.g. the globals of an earlier evaluation
+    pub fn new(scopes: Vec<Scope>) -> Self {
+        Self { scopes }
+    }
+
+    pub fn resolve(mut self, ast: &Ast) {
+        self.resolve_statements(&ast.statements);
+    }
+
+    // Function bodies are resolved last, the variables declared after a
+    // function in the same block can be used by it as well
+    fn resolve_statements(&mut self, statements: &[ASTStatement]) {
+        let functions = statements
+            .iter()
+            .filter_map(|statement| match &statement.kind {
+                ASTStatementKind::FuncDecl(function) => Some(function),
+                _ => None,
+            });
+        // like the solver, functions are declared before the other statements
+        for function in functions.clone() {
+            self.declare(function.identifier());
+        }
+        for statement in statements {
+            self.visit_statement(statement);
+        }
+        for function in functions {
+            self.resolve_function(function);
+        }
+    }
+
+    fn declare(&mut self, identifier: &str) {
+        let scope = self.scopes.last_mut().unwrap();
+        let slot = scope.len();
+        scope.entry(identifier.to_string()).or_insert(slot);
+    }
+
+    fn lookup(&self, identifier: &str) -> Option<ResolvedVariable> {
+        self.scopes
+            .iter()
+            .rev()
+            .enumerate()
+            .find_map(|(depth, scope)| {
+                scope
+                    .get(identifier)
+                    .map(|slot| ResolvedVariable { depth, slot: *slot })
+            })
+    }
+
+    // A default is resolved before its argument is declared, it can only
+    // refer to the preceding arguments
+    fn resolve_function(&mut self, function: &ASTFunctionStatement) {
+        self.scopes.push(Scope::new());
+        for argument in function.arguments.iter() {
+            if let Some(default) = &argument.default {
+                self.visit_expression(default);
+            }
+            self.declare(&argument.identifier.literal);
+        }
+        if let ASTStatementKind::Compound(body) = &function.body.kind {
+            self.resolve_statements(&body.statements);
+        }
+        self.scopes.pop();
+    }
+}
+
+impl ASTVisitor for Resolver {
+    fn visit_compound_statement(&mut self, statement: &ASTCompoundStatement) {
+        self.scopes.push(Scope::new());
+        self.resolve_statements(&statement.statements);
+        self.scopes.pop();
+    }
+
+    fn visit_let_statement(&mut self, statement: &ASTLetStatement) {
+        self.visit_expression(&statement.initializer);
+        self.declare(&statement.identifier.literal);
+    }
+
+    fn visit_var_statement(&mut self, statement: &ASTVarStatement) {
+        self.visit_expression(&statement.initializer);
+        self.declare(&statement.identifier.literal);
+    }
+
+    // the solver doesn't evaluate for loops
+    fn visit_for_loop_statement(&mut self, _statement: &ASTForStatement) {}
+
+    // declared in advance, the body is resolved after the other statements
+    fn visit_funtion_statement(&mut self, _function: &ASTFunctionStatement) {}
+
+    fn visit_assignment_expression(&mut self, expr: &ASTAssignmentExpression) {
+        self.visit_expression(&expr.expr);
+        expr.resolved.set(self.lookup(expr.identifier()));
+    }
+
+    fn visit_variable_expression(&mut self, expr: &ASTVariableExpression) {
+        expr.resolved.set(self.lookup(expr.identifier()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ResolvedVariable, Resolver};
+    use crate::ast::{parser::Parser, ASTVariableExpression, ASTVisitor, Ast};
+    use crate::diagnostics::DiagnosticsColletion;
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    struct Collector {
+        variables: Vec<(String, Option<(usize, usize)>)>,
+    }
+
+    impl ASTVisitor for Collector {
+        fn visit_variable_expression(&mut self, expr: &ASTVariableExpression) {
+            let resolved = expr
+                .resolved()
+                .map(|ResolvedVariable { depth, slot }| (depth, slot));
+            self.variables
+                .push((expr.identifier().to_string(), resolved));
+        }
+    }
+
+    fn parse(input: &str) -> Ast {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        ast
+    }
+
+    fn resolve(input: &str, globals: &[&str]) -> Vec<(String, Option<(usize, usize)>)> {
+        let ast = parse(input);
+        let globals: HashMap<String, usize> = globals
+            .iter()
+            .enumerate()
+            .map(|(slot, identifier)| (identifier.to_string(), slot))
+            .collect();
+        Resolver::new(vec![globals]).resolve(&ast);
+        let mut collector = Collector {
+            variables: Vec::new(),
+        };
+        ast.visit(&mut collector);
+        collector.variables
+    }
+
+    fn variable(
+        identifier: &str,
+        resolved: Option<(usize, usize)>,
+    ) -> (String, Option<(usize, usize)>) {
+        (identifier.to_string(), resolved)
+    }
+
+    #[test]
+    fn should_resolve_variables_of_enclosing_blocks() {
+        assert_eq!(
+            resolve(
+                "let a: i32 = 1; let b: i32 = 2; { let c: i32 = b; { a + c; } } a = b;",
+                &[]
+            ),
+            vec![
+                variable("b", Some((1, 1))),
+                variable("a", Some((2, 0))),
+                variable("c", Some((1, 0))),
+                variable("b", Some((0, 1))),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_resolve_shadowed_variable_before_its_declaration() {
+        assert_eq!(
+            resolve("let a: i32 = 1; { a; let a: i32 = 2; a; }", &[]),
+            vec![variable("a", Some((1, 0))), variable("a", Some((0, 0)))]
+        );
+    }
+
+    #[test]
+    fn should_resolve_function_bodies_with_arguments_and_enclosing_scopes() {
+        assert_eq!(
+            resolve(
+                "let x: i32 = 1; { let hidden: i32 = 2; func f(a, b = a) { return a + b + x + hidden + later; } } let later: i32 = 3;",
+                &[]
+            ),
+            vec![
+                variable("a", Some((0, 0))),
+                variable("a", Some((0, 0))),
+                variable("b", Some((0, 1))),
+                variable("x", Some((2, 0))),
+                variable("hidden", Some((1, 1))),
+                variable("later", None),
+            ]
+        );
+        // functions are declared first, the globals after them
+        assert_eq!(
+            resolve("func f() { return g + f(); } let g: i32 = 1;", &[]),
+            vec![variable("g", Some((1, 1)))]
+        );
+    }
+
+    #[test]
+    fn should_resolve_globals_of_earlier_evaluation() {
+        assert_eq!(
+            resolve("let b: i32 = 1; a + b + c;", &["a"]),
+            vec![
+                variable("a", Some((0, 0))),
+                variable("b", Some((0, 1))),
+                variable("c", None),
+            ]
+        );
+    }
+}
@@ -1,30 +1,736 @@
-use std::{collections::HashMap, ops::Not};
+use core::fmt;
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    io::Write,
+    ops::Not,
+    rc::Rc,
+};
+
+use crate::diagnostics::DiagnosticsColletionCell;
 
 use super::{
-    ASTBinaryOperator, ASTBinaryOperatorKind, ASTFunctionStatement, ASTReturnStatement, ASTVisitor,
+    builtins::Builtin,
+    lexer::{TextSpan, Token, TokenKind},
+    numeric::Numeric,
+    resolver::{ResolvedVariable, Resolver},
+    ASTBinaryOperator, ASTBinaryOperatorKind, ASTFunctionCallExpression, ASTFunctionStatement,
+    ASTReturnStatement, ASTStatement, ASTStatementKind, ASTUnaryOperatorKind, ASTVisitor, Ast,
 };
 
-type Scope = HashMap<String, f64>;
-pub struct ASTSolver {
-    result: Option<f64>,
-    scopes: Vec<Scope>,
-    functions: HashMap<String, ASTFunctionStatement>,
+#[derive(Debug, PartialEq, Clone)]
+pub enum SolverError {
+    UndefinedVariable(TextSpan),
+    ArgumentCountMismatch {
+        function: TextSpan,
+        expected: usize,
+        found: usize,
+    },
+    UndefinedFunction(TextSpan),
+    RecursionLimitExceeded(TextSpan),
+    IterationLimitExceeded(TextSpan),
+    StringInArithmetic(TextSpan),
+    TupleInArithmetic(TextSpan),
+    DivisionByZero(TextSpan),
+    IntegerOverflow(TextSpan),
+    DomainError(TextSpan),
+    AssertionFailed(TextSpan),
+    MissingReturnValue,
+    MissingValue(TextSpan),
+    OutsideOfLoop(TextSpan),
+}
+
+impl SolverError {
+    // The spans are sliced from `source`, the program the error occurred in
+    pub fn message(&self, source: &str) -> String {
+        match self {
+            SolverError::UndefinedVariable(span) => {
+                format!("Undefined variable '{}'", span.literal(source))
+            }
+            SolverError::ArgumentCountMismatch {
+                function,
+                expected,
+                found,
+            } => format!(
+                "Function '{}' expects {} arguments but got {}",
+                function.literal(source),
+                expected,
+                found
+            ),
+            SolverError::UndefinedFunction(span) => {
+                format!("Undefined function '{}'", span.literal(source))
+            }
+            SolverError::RecursionLimitExceeded(_) => {
+                "Maximum recursion depth exceeded".to_string()
+            }
+            SolverError::IterationLimitExceeded(_) => {
+                "Maximum number of loop iterations exceeded".to_string()
+            }
+            SolverError::StringInArithmetic(_) => {
+                "Strings can't be used in arithmetic expressions".to_string()
+            }
+            SolverError::TupleInArithmetic(_) => {
+                "Tuples can't be used in arithmetic expressions".to_string()
+            }
+            SolverError::DivisionByZero(_) => "Division by zero".to_string(),
+            SolverError::IntegerOverflow(_) => "Integer overflow".to_string(),
+            SolverError::DomainError(span) => {
+                format!("Argument out of the domain of '{}'", span.literal(source))
+            }
+            SolverError::AssertionFailed(_) => "Assertion failed".to_string(),
+            SolverError::MissingReturnValue => "Program did not produce a value".to_string(),
+            SolverError::MissingValue(span) => {
+                format!("Expression '{}' has no value", span.literal(source))
+            }
+            SolverError::OutsideOfLoop(span) => {
+                format!("'{}' outside of a loop", span.literal(source))
+            }
+        }
+    }
+}
+
+// Why an operation on values could not be evaluated, the caller reports it
+// at the span of the operator
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum OperationError {
+    DivisionByZero,
+    IntegerOverflow,
+    StringOperand,
+    TupleOperand,
+}
+
+impl OperationError {
+    pub(crate) fn at(self, span: TextSpan) -> SolverError {
+        match self {
+            OperationError::DivisionByZero => SolverError::DivisionByZero(span),
+            OperationError::IntegerOverflow => SolverError::IntegerOverflow(span),
+            OperationError::StringOperand => SolverError::StringInArithmetic(span),
+            OperationError::TupleOperand => SolverError::TupleInArithmetic(span),
+        }
+    }
+}
+
+// Integers are evaluated as integers as long as both operands are integers,
+// mixed operations promote the integer operand to a float. Strings can only
+// be concatenated and compared for equality with other strings, tuples only
+// for equality with other tuples. Non-integers are floats unless the solver
+// uses another numeric backend.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value<N = f64> {
+    Int(i64),
+    Float(N),
+    Str(String),
+    Tuple(Vec<Value<N>>),
+}
+
+impl<N: Numeric> Value<N> {
+    // strings and tuples are not numbers
+    pub(crate) fn as_number(&self) -> N {
+        match self {
+            Value::Int(i) => N::from_i64(*i),
+            Value::Float(f) => *f,
+            Value::Str(_) | Value::Tuple(_) => N::NAN,
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> f64 {
+        self.as_number().to_f64()
+    }
+
+    fn as_i64(&self) -> i64 {
+        match self {
+            Value::Int(i) => *i,
+            Value::Float(f) => f.to_f64() as i64,
+            Value::Str(_) | Value::Tuple(_) => 0,
+        }
+    }
+
+    pub(crate) fn is_str(&self) -> bool {
+        matches!(self, Value::Str(_))
+    }
+
+    pub(crate) fn is_tuple(&self) -> bool {
+        matches!(self, Value::Tuple(_))
+    }
+
+    // the empty string is false like zero, a tuple is always true
+    pub(crate) fn is_truthy(&self) -> bool {
+        match self {
+            Value::Str(string) => !string.is_empty(),
+            Value::Tuple(_) => true,
+            _ => self.as_number() != N::from_i64(0),
+        }
+    }
+
+    // None if an integer power overflows
+    pub(crate) fn pow(self, exponent: Value<N>) -> Option<Value<N>> {
+        Some(match (self, exponent) {
+            // negative exponents leave the integers
+            (Value::Int(base), Value::Int(exponent)) => match u32::try_from(exponent) {
+                Ok(exponent) => Value::Int(base.checked_pow(exponent)?),
+                Err(_) => Value::Float(N::from_i64(base).powf(N::from_i64(exponent))),
+            },
+            (base, exponent) => Value::Float(base.as_number().powf(exponent.as_number())),
+        })
+    }
+
+    pub(crate) fn unary(self, operator: &ASTUnaryOperatorKind) -> Result<Value<N>, OperationError> {
+        if *operator != ASTUnaryOperatorKind::LogicNot {
+            if self.is_str() {
+                return Err(OperationError::StringOperand);
+            }
+            if self.is_tuple() {
+                return Err(OperationError::TupleOperand);
+            }
+        }
+        Ok(match operator {
+            ASTUnaryOperatorKind::BitwiseNOT => Value::Int(self.as_i64().not()),
+            ASTUnaryOperatorKind::LogicNot => Value::from(!self.is_truthy()),
+            ASTUnaryOperatorKind::Plus => self,
+            ASTUnaryOperatorKind::Minus => match self {
+                Value::Int(i) => {
+                    Value::Int(i.checked_neg().ok_or(OperationError::IntegerOverflow)?)
+                }
+                _ => Value::Float(-self.as_number()),
+            },
+        })
+    }
+
+    // Both operands are evaluated, the short-circuit of the logic operators
+    // is up to the caller. A division by zero is an error, floats would
+    // silently produce infinity or NaN.
+    pub(crate) fn binary(
+        self,
+        operator: &ASTBinaryOperatorKind,
+        right: Value<N>,
+    ) -> Result<Value<N>, OperationError> {
+        let left = self;
+        if left.is_tuple() || right.is_tuple() {
+            return left.tuple_binary(operator, right);
+        }
+        if left.is_str() || right.is_str() {
+            return left.string_binary(operator, right);
+        }
+        Ok(match operator {
+            ASTBinaryOperatorKind::Plus
+            | ASTBinaryOperatorKind::Minus
+            | ASTBinaryOperatorKind::Multiply
+            | ASTBinaryOperatorKind::Divide
+            | ASTBinaryOperatorKind::Modulo
+            | ASTBinaryOperatorKind::Power => return left.arithmetic(operator, right),
+            ASTBinaryOperatorKind::EqualTo => {
+                Value::from(left.compare(&right).is_some_and(Ordering::is_eq))
+            }
+            ASTBinaryOperatorKind::NotEqualTo => {
+                Value::from(!left.compare(&right).is_some_and(Ordering::is_eq))
+            }
+            ASTBinaryOperatorKind::LogicAND => Value::from(left.is_truthy() && right.is_truthy()),
+            ASTBinaryOperatorKind::LogicOR => Value::from(left.is_truthy() || right.is_truthy()),
+            ASTBinaryOperatorKind::GreaterThan => {
+                Value::from(left.compare(&right).is_some_and(Ordering::is_gt))
+            }
+            ASTBinaryOperatorKind::GreaterThanOrEqual => {
+                Value::from(left.compare(&right).is_some_and(Ordering::is_ge))
+            }
+            ASTBinaryOperatorKind::LessThan => {
+                Value::from(left.compare(&right).is_some_and(Ordering::is_lt))
+            }
+            ASTBinaryOperatorKind::LessThanOrEqual => {
+                Value::from(left.compare(&right).is_some_and(Ordering::is_le))
+            }
+            ASTBinaryOperatorKind::BitwiseOR => Value::Int(left.as_i64() | right.as_i64()),
+            ASTBinaryOperatorKind::BitwiseAND => Value::Int(left.as_i64() & right.as_i64()),
+            ASTBinaryOperatorKind::BitwiseXOR => Value::Int(left.as_i64() ^ right.as_i64()),
+        })
+    }
+
+    // Integers are compared exactly, only a mixed pair is promoted, e.g. 2^53 + 1
+    // and 2^53 differ although they are the same float. Unordered with NaN.
+    fn compare(&self, right: &Value<N>) -> Option<Ordering> {
+        match (self, right) {
+            (Value::Int(left), Value::Int(right)) => Some(left.cmp(right)),
+            (left, right) => left.as_number().partial_cmp(&right.as_number()),
+        }
+    }
+
+    // logic operators only look at the truthiness of the strings
+    fn string_binary(
+        self,
+        operator: &ASTBinaryOperatorKind,
+        right: Value<N>,
+    ) -> Result<Value<N>, OperationError> {
+        Ok(match (operator, self, right) {
+            (ASTBinaryOperatorKind::Plus, Value::Str(left), Value::Str(right)) => {
+                Value::Str(left + &right)
+            }
+            (ASTBinaryOperatorKind::EqualTo, Value::Str(left), Value::Str(right)) => {
+                Value::from(left == right)
+            }
+            (ASTBinaryOperatorKind::NotEqualTo, Value::Str(left), Value::Str(right)) => {
+                Value::from(left != right)
+            }
+            (ASTBinaryOperatorKind::LogicAND, left, right) => {
+                Value::from(left.is_truthy() && right.is_truthy())
+            }
+            (ASTBinaryOperatorKind::LogicOR, left, right) => {
+                Value::from(left.is_truthy() || right.is_truthy())
+            }
+            _ => return Err(OperationError::StringOperand),
+        })
+    }
+
+    // tuples are equal if all of their elements are
+    fn tuple_binary(
+        self,
+        operator: &ASTBinaryOperatorKind,
+        right: Value<N>,
+    ) -> Result<Value<N>, OperationError> {
+        Ok(match (operator, self, right) {
+            (ASTBinaryOperatorKind::EqualTo, Value::Tuple(left), Value::Tuple(right)) => {
+                Value::from(Self::elements_equal(left, right)?)
+            }
+            (ASTBinaryOperatorKind::NotEqualTo, Value::Tuple(left), Value::Tuple(right)) => {
+                Value::from(!Self::elements_equal(left, right)?)
+            }
+            (ASTBinaryOperatorKind::LogicAND, left, right) => {
+                Value::from(left.is_truthy() && right.is_truthy())
+            }
+            (ASTBinaryOperatorKind::LogicOR, left, right) => {
+                Value::from(left.is_truthy() || right.is_truthy())
+            }
+            _ => return Err(OperationError::TupleOperand),
+        })
+    }
+
+    fn elements_equal(left: Vec<Value<N>>, right: Vec<Value<N>>) -> Result<bool, OperationError> {
+        if left.len() != right.len() {
+            return Ok(false);
+        }
+        for (left, right) in left.into_iter().zip(right) {
+            if !left
+                .binary(&ASTBinaryOperatorKind::EqualTo, right)?
+                .is_truthy()
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn arithmetic(
+        self,
+        operator: &ASTBinaryOperatorKind,
+        right: Value<N>,
+    ) -> Result<Value<N>, OperationError> {
+        let is_division = matches!(
+            operator,
+            ASTBinaryOperatorKind::Divide | ASTBinaryOperatorKind::Modulo
+        );
+        if is_division && right.as_number() == N::from_i64(0) {
+            return Err(OperationError::DivisionByZero);
+        }
+
+        if *operator == ASTBinaryOperatorKind::Power {
+            return self.pow(right).ok_or(OperationError::IntegerOverflow);
+        }
+
+        let (Value::Int(left), Value::Int(right)) = (&self, &right) else {
+            let (left, right) = (self.as_number(), right.as_number());
+            return Ok(Value::Float(match operator {
+                ASTBinaryOperatorKind::Plus => left + right,
+                ASTBinaryOperatorKind::Minus => left - right,
+                ASTBinaryOperatorKind::Multiply => left * right,
+                ASTBinaryOperatorKind::Divide => left / right,
+                _ => left % right,
+            }));
+        };
+
+        let (left, right) = (*left, *right);
+        // the division by zero is handled above, `i64::MIN / -1` overflows
+        match operator {
+            ASTBinaryOperatorKind::Plus => left.checked_add(right),
+            ASTBinaryOperatorKind::Minus => left.checked_sub(right),
+            ASTBinaryOperatorKind::Multiply => left.checked_mul(right),
+            ASTBinaryOperatorKind::Divide => left.checked_div(right),
+            _ => left.checked_rem(right),
+        }
+        .map(Value::Int)
+        .ok_or(OperationError::IntegerOverflow)
+    }
+}
+
+impl<N> From<bool> for Value<N> {
+    fn from(value: bool) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Value<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(float) => write!(f, "{}", float),
+            Value::Str(string) => write!(f, "\"{}\"", string),
+            Value::Tuple(elements) => {
+                let elements: Vec<String> =
+                    elements.iter().map(|element| element.to_string()).collect();
+                match elements.as_slice() {
+                    [element] => write!(f, "({},)", element),
+                    _ => write!(f, "({})", elements.join(", ")),
+                }
+            }
+        }
+    }
+}
+
+// Base integers in the printed result are written in
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Radix {
+    Binary,
+    Octal,
+    #[default]
+    Decimal,
+    Hexadecimal,
+}
+
+// How numbers in the printed result are formatted, without a precision the
+// shortest representation of floats is used
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    pub precision: Option<usize>,
+    // `4.0` is printed as `4`
+    pub integral_without_point: bool,
+    // `1000000.0` is printed as `1e6`
+    pub scientific: bool,
+    pub radix: Radix,
+}
+
+impl<N: Numeric> Value<N> {
+    pub fn format(&self, format: NumberFormat) -> String {
+        match self {
+            // negative integers keep their sign, `-255` is `-0xff`
+            Value::Int(int) => {
+                let sign = if *int < 0 { "-" } else { "" };
+                let magnitude = int.unsigned_abs();
+                match format.radix {
+                    Radix::Binary => format!("{}{:#b}", sign, magnitude),
+                    Radix::Octal => format!("{}{:#o}", sign, magnitude),
+                    Radix::Decimal => int.to_string(),
+                    Radix::Hexadecimal => format!("{}{:#x}", sign, magnitude),
+                }
+            }
+            // other backends are rounded like floats
+            Value::Float(number) => {
+                let float = number.to_f64();
+                let formatted = match (format.scientific, format.precision) {
+                    (false, Some(precision)) => format!("{:.*}", precision, float),
+                    (false, None) => number.to_string(),
+                    (true, Some(precision)) => format!("{:.*e}", precision, float),
+                    (true, None) => format!("{:e}", float),
+                };
+                // only the mantissa of the scientific notation is shortened
+                let (mantissa, exponent) = match formatted.split_once('e') {
+                    Some((mantissa, exponent)) => (mantissa, format!("e{}", exponent)),
+                    None => (formatted.as_str(), String::new()),
+                };
+                // the rounded value decides, `3.999` with precision 2 is `4`
+                match mantissa.split_once('.') {
+                    Some((integral, fraction))
+                        if format.integral_without_point && fraction.chars().all(|c| c == '0') =>
+                    {
+                        format!("{}{}", integral, exponent)
+                    }
+                    _ => formatted.clone(),
+                }
+            }
+            Value::Tuple(elements) => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|element| element.format(format))
+                    .collect();
+                match elements.as_slice() {
+                    [element] => format!("({},)", element),
+                    _ => format!("({})", elements.join(", ")),
+                }
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+// Where the output of print goes, shared so the caller can read it back
+pub type OutputCell = Rc<RefCell<dyn Write>>;
+
+// Variables of a scope in declaration order. Resolved variables are found
+// by their slot, the others by name.
+struct Scope<N> {
+    slots: HashMap<String, usize>,
+    values: Vec<Value<N>>,
+    // slots declared by a function, they aren't listed as bindings
+    function_slots: HashSet<usize>,
+    // functions declared in the scope, they can't be called once it's left
+    functions: HashMap<String, Rc<ASTFunctionStatement>>,
+}
+
+impl<N> Scope<N> {
+    fn new() -> Self {
+        Self {
+            slots: HashMap::new(),
+            values: Vec::new(),
+            function_slots: HashSet::new(),
+            functions: HashMap::new(),
+        }
+    }
+
+    // a variable declared again in the same scope keeps its slot
+    fn insert(&mut self, identifier: String, value: Value<N>) -> usize {
+        let slot = match self.slots.get(&identifier) {
+            Some(slot) => {
+                self.values[*slot] = value;
+                *slot
+            }
+            None => {
+                self.slots.insert(identifier, self.values.len());
+                self.values.push(value);
+                self.values.len() - 1
+            }
+        };
+        self.function_slots.remove(&slot);
+        slot
+    }
+
+    // functions take a slot like variables, so that the resolved slots match
+    fn insert_function(&mut self, function: &ASTFunctionStatement, value: Value<N>) {
+        let identifier = function.identifier.literal.clone();
+        let slot = self.insert(identifier.clone(), value);
+        self.function_slots.insert(slot);
+        self.functions.insert(identifier, Rc::new(function.clone()));
+    }
+
+    fn get_mut(&mut self, identifier: &str) -> Option<&mut Value<N>> {
+        self.slots
+            .get(identifier)
+            .map(|slot| &mut self.values[*slot])
+    }
+
+    fn variables(&self) -> impl Iterator<Item = (&String, &Value<N>)> {
+        self.slots
+            .iter()
+            .filter(|(_, slot)| !self.function_slots.contains(slot))
+            .map(|(identifier, slot)| (identifier, &self.values[*slot]))
+    }
+}
+
+impl<N, const LENGTH: usize> From<[(String, Value<N>); LENGTH]> for Scope<N> {
+    fn from(variables: [(String, Value<N>); LENGTH]) -> Self {
+        let mut scope = Scope::new();
+        for (identifier, value) in variables {
+            scope.insert(identifier, value);
+        }
+        scope
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ControlFlow {
+    Return,
+    Break,
+    Continue,
+}
+
+pub struct ASTSolver<N: Numeric = f64> {
+    result: Option<Value<N>>,
+    scopes: Vec<Scope<N>>,
+    call_depth: usize,
+    max_call_depth: usize,
+    // loops around the evaluated statement in the current function
+    loops: usize,
+    max_loop_iterations: Option<usize>,
+    resolve_names: bool,
+    number_format: NumberFormat,
+    // set by return, break and continue, the remaining statements are skipped
+    // until the function or the loop is left
+    control_flow: Option<ControlFlow>,
+    errors: Vec<SolverError>,
+    output: OutputCell,
+    diagnostics: DiagnosticsColletionCell,
 }
 
 impl ASTSolver {
-    pub fn new() -> Self {
+    pub fn new(diagnostics: DiagnosticsColletionCell) -> Self {
+        Self::with_numeric(diagnostics)
+    }
+}
+
+impl<N: Numeric> ASTSolver<N> {
+    // Result of an expression that could not be evaluated, e.g. an undefined
+    // variable. NaN propagates through arithmetic so evaluation can continue.
+    const UNDEFINED: Value<N> = Value::Float(N::NAN);
+    // every call nests several visitor frames on the native stack, the limit
+    // has to be hit before a 2 MB thread stack of a debug build overflows
+    const DEFAULT_MAX_CALL_DEPTH: usize = 250;
+
+    // Computes with another numeric backend than floats, e.g. exact fractions
+    pub fn with_numeric(diagnostics: DiagnosticsColletionCell) -> Self {
         Self {
             scopes: vec![Scope::new()],
             result: None,
-            functions: HashMap::new(),
+            call_depth: 0,
+            loops: 0,
+            max_call_depth: Self::DEFAULT_MAX_CALL_DEPTH,
+            max_loop_iterations: None,
+            resolve_names: true,
+            number_format: NumberFormat::default(),
+            control_flow: None,
+            errors: Vec::new(),
+            output: Rc::new(RefCell::new(std::io::stdout())),
+            diagnostics,
+        }
+    }
+
+    // Evaluates all statements of the ast, the value of the last evaluated
+    // expression is the result. A program without expressions, e.g. an empty
+    // one, produces no value. Scopes and functions are kept between calls.
+    pub fn evaluate(&mut self, ast: &Ast) -> Result<Option<Value<N>>, SolverError> {
+        self.result = None;
+        self.control_flow = None;
+        self.errors.clear();
+        if self.resolve_names {
+            let scopes = self
+                .scopes
+                .iter()
+                .map(|scope| scope.slots.clone())
+                .collect();
+            Resolver::new(scopes).resolve(ast);
+        }
+        self.hoist_functions(&ast.statements);
+        // a return outside of a function ends the program
+        for statement in ast.statements.iter() {
+            self.visit_statement(statement);
+            if self.interrupted() {
+                break;
+            }
+        }
+
+        if let Some(error) = self.errors.first() {
+            return Err(error.clone());
+        }
+        Ok(self.result.clone())
+    }
+
+    fn interrupted(&self) -> bool {
+        self.control_flow.is_some()
+    }
+
+    #[allow(dead_code)]
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    // Loops are unbounded unless a maximum number of iterations is set
+    #[allow(dead_code)]
+    pub fn set_max_loop_iterations(&mut self, max_loop_iterations: usize) {
+        self.max_loop_iterations = Some(max_loop_iterations);
+    }
+
+    // Variables are resolved to their slot before evaluating, without it
+    // every access looks them up by name
+    #[allow(dead_code)]
+    pub fn set_resolve_names(&mut self, resolve_names: bool) {
+        self.resolve_names = resolve_names;
+    }
+
+    // print writes to stdout unless another output is set
+    #[allow(dead_code)]
+    pub fn set_output(&mut self, output: OutputCell) {
+        self.output = output;
+    }
+
+    // Number of decimals of floats in the printed result
+    #[allow(dead_code)]
+    pub fn set_print_precision(&mut self, precision: usize) {
+        self.number_format.precision = Some(precision);
+    }
+
+    #[allow(dead_code)]
+    pub fn set_print_integral_floats_without_point(&mut self, without_point: bool) {
+        self.number_format.integral_without_point = without_point;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_print_scientific(&mut self, scientific: bool) {
+        self.number_format.scientific = scientific;
+    }
+
+    pub fn set_number_format(&mut self, number_format: NumberFormat) {
+        self.number_format = number_format;
+    }
+
+    // Integers in the printed result are decimal unless another radix is set
+    #[allow(dead_code)]
+    pub fn set_result_radix(&mut self, radix: Radix) {
+        self.number_format.radix = radix;
+    }
+
+    // The value of the last evaluated expression. Calling a function whose
+    // body didn't produce a value leaves no result, using it is reported.
+    fn value(&mut self, span: &TextSpan) -> Value<N> {
+        if let Some(value) = &self.result {
+            return value.clone();
         }
+        self.diagnostics
+            .borrow_mut()
+            .report_missing_value(span.clone());
+        self.errors.push(SolverError::MissingValue(span.clone()));
+        self.result = Some(Self::UNDEFINED);
+        Self::UNDEFINED
     }
 
     pub fn print_result(&self) {
-        println!("Solver result: {}", self.result.unwrap());
+        println!("Solver result: {}", self.formatted_result());
+    }
+
+    fn formatted_result(&self) -> String {
+        match &self.result {
+            Some(result) => result.format(self.number_format),
+            None => SolverError::MissingReturnValue.message(""),
+        }
+    }
+
+    // Variables visible from the innermost scope sorted by name, shadowed
+    // variables are left out. After evaluate only the global scope is left.
+    #[allow(dead_code)]
+    pub fn current_bindings(&self) -> Vec<(String, Value<N>)> {
+        let mut bindings: BTreeMap<&String, Value<N>> = BTreeMap::new();
+        for scope in self.scopes.iter() {
+            for (identifier, value) in scope.variables() {
+                bindings.insert(identifier, value.clone());
+            }
+        }
+        bindings
+            .into_iter()
+            .map(|(identifier, value)| (identifier.clone(), value))
+            .collect()
+    }
+
+    // One line per scope from the global scope to the innermost one
+    #[allow(dead_code)]
+    pub fn dump_scopes(&self) -> String {
+        let mut dump = String::new();
+        for (depth, scope) in self.scopes.iter().enumerate() {
+            let mut variables: Vec<(&String, &Value<N>)> = scope.variables().collect();
+            variables.sort_by(|left, right| left.0.cmp(right.0));
+            let variables: Vec<String> = variables
+                .iter()
+                .map(|(identifier, value)| format!("{} = {}", identifier, value))
+                .collect();
+            dump.push_str(&format!("scope {}: {}\n", depth, variables.join(", ")));
+        }
+        dump
     }
 
-    fn enter_scope(&mut self, scope_variables: Scope) {
+    fn enter_scope(&mut self, scope_variables: Scope<N>) {
         self.scopes.push(scope_variables);
         // self.active_scope += 1;
     }
@@ -34,154 +740,1934 @@ impl ASTSolver {
         // self.active_scope -= 1;
     }
 
-    fn add_identifier_to_scope(&mut self, identifier: &String, value: f64) {
+    fn add_identifier_to_scope(&mut self, identifier: &str, value: Value<N>) {
         self.scopes
             .last_mut()
             .unwrap()
-            .insert(identifier.clone(), value);
+            .insert(identifier.to_string(), value);
+    }
+
+    // The slot of a resolved variable is only missing if its declaration
+    // wasn't evaluated, e.g. because of a return. Then it's looked up by name
+    // like an unresolved one.
+    fn variable_mut(
+        &mut self,
+        identifier: &str,
+        resolved: Option<ResolvedVariable>,
+    ) -> Option<&mut Value<N>> {
+        let slot = resolved
+            .filter(|_| self.resolve_names)
+            .and_then(|resolved| {
+                let index = self.scopes.len().checked_sub(resolved.depth + 1)?;
+                (resolved.slot < self.scopes[index].values.len()).then_some((index, resolved.slot))
+            });
+        match slot {
+            Some((index, slot)) => Some(&mut self.scopes[index].values[slot]),
+            None => self
+                .scopes
+                .iter_mut()
+                .rev()
+                .find_map(|scope| scope.get_mut(identifier)),
+        }
     }
 
-    fn check_identifier_in_scope(&self, identifier: &String) -> bool {
-        for scope in self.scopes.iter().rev() {
-            if scope.contains_key(identifier) {
-                return true;
+    // The innermost visible function with the given name and the number of
+    // scopes up to the one declaring it. The body sees only these scopes.
+    fn function(&self, identifier: &str) -> Option<(Rc<ASTFunctionStatement>, usize)> {
+        self.scopes
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, scope)| {
+                let function = scope.functions.get(identifier)?;
+                Some((Rc::clone(function), index + 1))
+            })
+    }
+
+    // Functions can be called before their declaration in the same block
+    fn hoist_functions(&mut self, statements: &[ASTStatement]) {
+        for statement in statements {
+            if let ASTStatementKind::FuncDecl(function) = &statement.kind {
+                self.visit_funtion_statement(function);
             }
         }
-        return false;
     }
 
-    fn get_identifier_in_scope(&self, identifier: &String) -> Option<f64> {
-        for scope in self.scopes.iter().rev() {
-            if scope.contains_key(identifier) {
-                return scope.get(identifier).copied();
+    // Without a loop break and continue would leave the function like a
+    // return, the symbol checker doesn't run before every evaluation
+    fn report_outside_of_loop(&mut self, keyword: &Token) {
+        if self.loops > 0 {
+            return;
+        }
+        self.diagnostics
+            .borrow_mut()
+            .report_outside_of_loop(keyword);
+        self.errors
+            .push(SolverError::OutsideOfLoop(keyword.span.clone()));
+    }
+
+    // Reports the error and returns the value evaluation continues with
+    fn report_operation_error(&mut self, error: OperationError, span: &TextSpan) -> Value<N> {
+        let mut diagnostics = self.diagnostics.borrow_mut();
+        match error {
+            OperationError::DivisionByZero => diagnostics.report_division_by_zero(span.clone()),
+            OperationError::IntegerOverflow => diagnostics.report_integer_overflow(span.clone()),
+            OperationError::StringOperand => diagnostics.report_string_in_arithmetic(span.clone()),
+            OperationError::TupleOperand => diagnostics.report_tuple_in_arithmetic(span.clone()),
+        }
+        self.errors.push(error.at(span.clone()));
+        Self::UNDEFINED
+    }
+
+    fn call_builtin(&mut self, builtin: &Builtin<N>, expr: &ASTFunctionCallExpression) {
+        // the arguments are evaluated even if their number doesn't match,
+        // their side effects and errors are not lost
+        let mut arguments: Vec<Value<N>> = Vec::new();
+        for argument in expr.arguments.iter() {
+            self.visit_expression(argument);
+            if self.interrupted() {
+                return;
             }
+            arguments.push(self.value(&argument.span));
+        }
+
+        if builtin.arity != expr.arguments.len() {
+            self.diagnostics
+                .borrow_mut()
+                .report_number_of_function_arguments_mismatch(
+                    &expr.identifier,
+                    builtin.arity,
+                    expr.arguments.len(),
+                );
+            self.errors.push(SolverError::ArgumentCountMismatch {
+                function: expr.identifier.span.clone(),
+                expected: builtin.arity,
+                found: expr.arguments.len(),
+            });
+            self.result = Some(Self::UNDEFINED);
+            return;
         }
-        return None;
+
+        self.result = match builtin.call(&arguments, &mut *self.output.borrow_mut()) {
+            Some(value) => Some(value),
+            None => {
+                self.diagnostics
+                    .borrow_mut()
+                    .report_domain_error(&expr.identifier);
+                self.errors
+                    .push(SolverError::DomainError(expr.identifier.span.clone()));
+                Some(Self::UNDEFINED)
+            }
+        };
     }
 }
 
-impl ASTVisitor for ASTSolver {
+impl<N: Numeric> ASTVisitor for ASTSolver<N> {
     fn visit_return_statement(&mut self, statement: &ASTReturnStatement) {
         self.visit_expression(&statement.expr);
+        self.control_flow = Some(ControlFlow::Return);
     }
     fn visit_let_statement(&mut self, statement: &super::ASTLetStatement) {
         self.visit_expression(&statement.initializer);
-        self.add_identifier_to_scope(&statement.identifier.span.literal, self.result.unwrap());
+        // a return inside of a block expression leaves the declaration
+        if self.interrupted() {
+            return;
+        }
+        let value = self.value(&statement.initializer.span);
+        self.add_identifier_to_scope(&statement.identifier.literal, value);
     }
 
     fn visit_var_statement(&mut self, statement: &super::ASTVarStatement) {
         self.visit_expression(&statement.initializer);
-        self.add_identifier_to_scope(&statement.identifier.span.literal, self.result.unwrap());
+        if self.interrupted() {
+            return;
+        }
+        let value = self.value(&statement.initializer.span);
+        self.add_identifier_to_scope(&statement.identifier.literal, value);
+    }
+
+    fn visit_compound_statement(&mut self, statement: &super::ASTCompoundStatement) {
+        self.enter_scope(Scope::new());
+        self.hoist_functions(&statement.statements);
+        for statement in statement.statements.iter() {
+            self.visit_statement(statement);
+            if self.interrupted() {
+                break;
+            }
+        }
+        self.leave_scope();
+    }
+
+    // the result of the last statement isn't the value of a block without a
+    // trailing expression
+    fn visit_block_expression(&mut self, block: &super::ASTCompoundStatement) {
+        self.visit_compound_statement(block);
+        if !block.has_value && !self.interrupted() {
+            self.result = None;
+        }
     }
 
     fn visit_if_statement(&mut self, statement: &super::ASTIfStatement) {
         self.visit_expression(&statement.condition);
-        let condition = self.result.unwrap();
+        if self.interrupted() {
+            return;
+        }
+        let condition = self.value(&statement.condition.span);
 
-        if condition != 0.0 {
+        if condition.is_truthy() {
             self.visit_statement(&statement.then_branch);
         } else if let Some(else_branch) = &statement.else_branch {
             self.visit_statement(&else_branch.else_branch);
         }
     }
 
-    fn visit_for_loop_statement(&mut self, statement: &super::ASTForStatement) {}
+    // Runs the first arm whose pattern equals the scrutinee, `_` matches any
+    // value. Like a condition the scrutinee is the result if no arm runs.
+    fn visit_match_statement(&mut self, statement: &super::ASTMatchStatement) {
+        self.visit_expression(&statement.scrutinee);
+        if self.interrupted() {
+            return;
+        }
+        let scrutinee = self.value(&statement.scrutinee.span);
+        for arm in statement.arms.iter() {
+            let matches = match &arm.pattern {
+                Some(pattern) => {
+                    self.visit_expression(pattern);
+                    let pattern = self.value(&pattern.span);
+                    scrutinee.is_str() == pattern.is_str()
+                        && scrutinee
+                            .clone()
+                            .binary(&ASTBinaryOperatorKind::EqualTo, pattern)
+                            .is_ok_and(|equal| equal.is_truthy())
+                }
+                None => true,
+            };
+            self.result = Some(scrutinee.clone());
+            if matches {
+                self.visit_statement(&arm.body);
+                break;
+            }
+        }
+    }
 
-    fn visit_while_loop_statement(&mut self, statement: &super::ASTWhileStatement) {}
+    fn visit_for_loop_statement(&mut self, _statement: &super::ASTForStatement) {}
 
-    fn visit_funtion_statement(&mut self, function: &super::ASTFunctionStatement) {
-        self.functions
-            .insert(function.identifier.span.literal.clone(), function.clone());
+    fn visit_while_loop_statement(&mut self, statement: &super::ASTWhileStatement) {
+        let mut iterations: usize = 0;
+        loop {
+            self.visit_expression(&statement.condition);
+            if self.interrupted() || !self.value(&statement.condition.span).is_truthy() {
+                break;
+            }
+
+            if self
+                .max_loop_iterations
+                .is_some_and(|max_loop_iterations| iterations >= max_loop_iterations)
+            {
+                self.diagnostics
+                    .borrow_mut()
+                    .report_iteration_limit_exceeded(statement.keyword.span.clone(), iterations);
+                self.errors.push(SolverError::IterationLimitExceeded(
+                    statement.keyword.span.clone(),
+                ));
+                break;
+            }
+
+            self.loops += 1;
+            self.visit_statement(&statement.body);
+            self.loops -= 1;
+            iterations += 1;
+            match self.control_flow {
+                Some(ControlFlow::Break) => {
+                    self.control_flow = None;
+                    break;
+                }
+                Some(ControlFlow::Continue) => self.control_flow = None,
+                Some(ControlFlow::Return) => break,
+                None => (),
+            }
+        }
+    }
+
+    fn visit_break_statement(&mut self, statement: &super::ASTBreakStatement) {
+        self.report_outside_of_loop(&statement.keyword);
+        self.control_flow = Some(ControlFlow::Break);
+    }
+
+    fn visit_continue_statement(&mut self, statement: &super::ASTContinueStatement) {
+        self.report_outside_of_loop(&statement.keyword);
+        self.control_flow = Some(ControlFlow::Continue);
+    }
 
-        self.add_identifier_to_scope(&function.identifier.span.literal, 0.0);
+    // a failed assertion is reported and the program continues
+    fn visit_assert_statement(&mut self, statement: &super::ASTAssertStatement) {
+        self.visit_expression(&statement.condition);
+        if !self.interrupted() && !self.value(&statement.condition.span).is_truthy() {
+            self.diagnostics
+                .borrow_mut()
+                .report_assertion_failed(statement.condition.span.clone());
+            self.errors.push(SolverError::AssertionFailed(
+                statement.condition.span.clone(),
+            ));
+        }
+    }
+
+    fn visit_funtion_statement(&mut self, function: &super::ASTFunctionStatement) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert_function(function, Value::Int(0));
     }
 
     fn visit_assignment_expression(&mut self, expr: &super::ASTAssignmentExpression) {
         self.visit_expression(&expr.expr);
-        for scope in self.scopes.iter_mut().rev() {
-            if let Some(value) = scope.get_mut(&expr.identifier.span.literal) {
-                *value = self.result.unwrap();
+        if self.interrupted() {
+            return;
+        }
+        // the value of the assignment expression is the assigned value
+        let result = self.value(&expr.expr.span);
+        match self.variable_mut(&expr.identifier.literal, expr.resolved()) {
+            Some(value) => *value = result,
+            None => {
+                self.diagnostics
+                    .borrow_mut()
+                    .report_undefined_variable(&expr.identifier);
+                self.errors
+                    .push(SolverError::UndefinedVariable(expr.identifier.span.clone()));
             }
         }
     }
 
     fn visit_function_call_expression(&mut self, expr: &super::ASTFunctionCallExpression) {
-        if !self.check_identifier_in_scope(&expr.identifier.span.literal) {}
+        // once the recursion limit is hit, all pending calls are abandoned
+        if self
+            .errors
+            .iter()
+            .any(|error| matches!(error, SolverError::RecursionLimitExceeded(_)))
+        {
+            self.result = Some(Self::UNDEFINED);
+            return;
+        }
 
-        let func = self
-            .functions
-            .get(&expr.identifier.span.literal)
-            .unwrap()
-            .clone();
-        let mut arguments: Scope = Scope::new();
+        if let Some(builtin) = Builtin::lookup(&expr.identifier.literal) {
+            self.call_builtin(builtin, expr);
+            return;
+        }
+
+        let Some((func, scope_depth)) = self.function(&expr.identifier.literal) else {
+            self.diagnostics
+                .borrow_mut()
+                .report_undefined_function(&expr.identifier);
+            self.errors
+                .push(SolverError::UndefinedFunction(expr.identifier.span.clone()));
+            self.result = Some(Self::UNDEFINED);
+            return;
+        };
+
+        if self.call_depth >= self.max_call_depth {
+            self.diagnostics
+                .borrow_mut()
+                .report_recursion_limit_exceeded(expr.identifier.span.clone(), self.max_call_depth);
+            self.errors.push(SolverError::RecursionLimitExceeded(
+                expr.identifier.span.clone(),
+            ));
+            self.result = Some(Self::UNDEFINED);
+            return;
+        }
+
+        let found = expr.arguments.len();
+        if found < func.required_arguments() || found > func.arguments.len() {
+            let expected = if found < func.required_arguments() {
+                func.required_arguments()
+            } else {
+                func.arguments.len()
+            };
+            self.diagnostics
+                .borrow_mut()
+                .report_number_of_function_arguments_mismatch(&expr.identifier, expected, found);
+            self.errors.push(SolverError::ArgumentCountMismatch {
+                function: expr.identifier.span.clone(),
+                expected,
+                found,
+            });
+            self.result = Some(Self::UNDEFINED);
+            return;
+        }
+
+        let mut arguments: Scope<N> = Scope::new();
 
         // evaluate arguments and add them to scope
-        // arguments.push(expr.identifier.span.literal.clone());
+        // arguments.push(expr.identifier.literal.clone());
         for (arg_expr, func_arg) in expr.arguments.iter().zip(func.arguments.iter()) {
-            self.visit_expression(&arg_expr);
-            let arg_name = func_arg.identifier.span.literal.clone();
+            self.visit_expression(arg_expr);
+            if self.interrupted() {
+                return;
+            }
+            let arg_name = func_arg.identifier.literal.clone();
 
-            arguments.insert(arg_name, self.result.unwrap());
+            arguments.insert(arg_name, self.value(&arg_expr.span));
         }
+        // scoping is lexical, the body sees its arguments and the scopes
+        // enclosing the declaration but not the locals of the caller
+        let caller_scopes = self.scopes.split_off(scope_depth);
         self.enter_scope(arguments);
+        // defaults are evaluated in the scope of the arguments, so they can
+        // refer to the preceding arguments
+        for func_arg in func.arguments.iter().skip(found) {
+            if let Some(default) = &func_arg.default {
+                self.visit_expression(default);
+                let value = self.value(&default.span);
+                self.add_identifier_to_scope(&func_arg.identifier.literal, value);
+            }
+        }
+        self.call_depth += 1;
+        // a loop around the call can't be left from the body
+        let loops = std::mem::take(&mut self.loops);
 
         // todo: that check should be done before
         if let super::ASTStatementKind::Compound(statement) = &func.body.kind {
+            self.hoist_functions(&statement.statements);
             for statement in statement.statements.iter() {
                 self.visit_statement(statement);
+                if self.interrupted() {
+                    break;
+                }
             }
         }
 
+        self.control_flow = None;
+        self.loops = loops;
+        self.call_depth -= 1;
         self.leave_scope();
+        self.scopes.extend(caller_scopes);
     }
 
     fn visit_variable_expression(&mut self, expr: &super::ASTVariableExpression) {
-        self.result = self.get_identifier_in_scope(&expr.identifier.span.literal);
-        // self.result = Some(*self.variables.get(expr.identifier()).unwrap());
+        let value = self
+            .variable_mut(&expr.identifier.literal, expr.resolved())
+            .cloned();
+        if value.is_none() {
+            self.diagnostics
+                .borrow_mut()
+                .report_undefined_variable(&expr.identifier);
+            self.errors
+                .push(SolverError::UndefinedVariable(expr.identifier.span.clone()));
+        }
+        self.result = Some(value.unwrap_or(Self::UNDEFINED));
     }
 
     fn visit_unary_expression(&mut self, expr: &super::ASTUnaryExpression) {
         self.visit_expression(&expr.expr);
-        self.result = Some(match expr.operator.kind {
-            super::ASTUnaryOperatorKind::BitwiseNOT => (self.result.unwrap() as i64).not() as f64,
-            super::ASTUnaryOperatorKind::LogicNot => ((self.result.unwrap() == 0.0) as i64) as f64,
-            super::ASTUnaryOperatorKind::Minus => self.result.unwrap() * -1.0,
+        if self.interrupted() {
+            return;
+        }
+        let value = self.value(&expr.expr.span);
+        self.result = Some(match value.unary(&expr.operator.kind) {
+            Ok(value) => value,
+            Err(error) => self.report_operation_error(error, &expr.operator.token.span),
         });
     }
+    // Operands are evaluated from left to right, side effects of the left
+    // operand (e.g. an assignment) are visible to the right operand
     fn visit_binary_expression(&mut self, expr: &super::ASTBinaryExpression) {
         self.visit_expression(&expr.left);
-        let left = self.result.unwrap();
+        if self.interrupted() {
+            return;
+        }
+        let left = self.value(&expr.left.span);
+
+        // short-circuit logic operators, the right operand is only evaluated if needed
+        match expr.operator.kind {
+            ASTBinaryOperatorKind::LogicAND if !left.is_truthy() => {
+                self.result = Some(Value::from(false));
+                return;
+            }
+            ASTBinaryOperatorKind::LogicOR if left.is_truthy() => {
+                self.result = Some(Value::from(true));
+                return;
+            }
+            _ => (),
+        }
+
         self.visit_expression(&expr.right);
-        let right = self.result.unwrap();
-        self.result = Some(match expr.operator.kind {
-            ASTBinaryOperatorKind::Plus => left + right,
-            ASTBinaryOperatorKind::Minus => left - right,
-            ASTBinaryOperatorKind::Multiply => left * right,
-            ASTBinaryOperatorKind::Divide => left / right,
-            ASTBinaryOperatorKind::EqualTo => (left == right) as i64 as f64,
-            ASTBinaryOperatorKind::NotEqualTo => (left != right) as i64 as f64,
-            ASTBinaryOperatorKind::LogicAND => ((left != 0.0) && (right != 0.0)) as i64 as f64,
-            ASTBinaryOperatorKind::LogicOR => ((left != 0.0) || (right != 0.0)) as i64 as f64,
-            ASTBinaryOperatorKind::GreaterThan => (left > right) as i64 as f64,
-            ASTBinaryOperatorKind::GreaterThanOrEqual => (left >= right) as i64 as f64,
-            ASTBinaryOperatorKind::LessThan => (left < right) as i64 as f64,
-            ASTBinaryOperatorKind::LessThanOrEqual => (left <= right) as i64 as f64,
-            ASTBinaryOperatorKind::BitwiseOR => ((left as i64) | (right as i64)) as f64,
-            ASTBinaryOperatorKind::BitwiseAND => ((left as i64) & (right as i64)) as f64,
-            ASTBinaryOperatorKind::BitwiseXOR => ((left as i64) ^ (right as i64)) as f64,
-        })
+        if self.interrupted() {
+            return;
+        }
+        let right = self.value(&expr.right.span);
+        self.result = Some(match left.binary(&expr.operator.kind, right) {
+            Ok(value) => value,
+            Err(error) => self.report_operation_error(error, &expr.operator.token.span),
+        });
     }
 
     fn visit_parenthesised_expression(&mut self, expr: &super::ASTParenthesizedExpression) {
         self.visit_expression(&expr.expr);
     }
 
-    fn visit_binary_operator(&mut self, op: &ASTBinaryOperator) {}
+    fn visit_ternary_expression(&mut self, expr: &super::ASTTernaryExpression) {
+        self.visit_expression(&expr.condition);
+        if self.interrupted() {
+            return;
+        }
+        if self.value(&expr.condition.span).is_truthy() {
+            self.visit_expression(&expr.then_expr);
+        } else {
+            self.visit_expression(&expr.else_expr);
+        }
+    }
+
+    fn visit_tuple_expression(&mut self, expr: &super::ASTTupleExpression) {
+        let mut elements = Vec::new();
+        for element in expr.elements.iter() {
+            self.visit_expression(element);
+            if self.interrupted() {
+                return;
+            }
+            elements.push(self.value(&element.span));
+        }
+        self.result = Some(Value::Tuple(elements));
+    }
+
+    fn visit_binary_operator(&mut self, _op: &ASTBinaryOperator) {}
 
     fn visit_integer(&mut self, integer: &i64) {
-        self.result = Some(integer.clone() as f64);
+        self.result = Some(Value::Int(*integer));
     }
     fn visit_float(&mut self, float: &f64) {
-        self.result = Some(float.clone());
+        self.result = Some(Value::Float(N::from_f64(*float)));
+    }
+    fn visit_boolean(&mut self, boolean: &bool) {
+        self.result = Some(Value::from(*boolean));
+    }
+    // characters are evaluated as their code point
+    fn visit_char(&mut self, c: &char) {
+        self.result = Some(Value::Int(*c as i64));
+    }
+    fn visit_string(&mut self, string: &super::Token) {
+        if let TokenKind::StringLiteral(string) = &string.kind {
+            self.result = Some(Value::Str(string.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ASTSolver, NumberFormat, Radix, Scope, SolverError, Value};
+    use crate::ast::{numeric::Rational, parser::Parser, Ast};
+    use crate::diagnostics::{DiagnosticsColletion, DiagnosticsColletionCell};
+    use std::{cell::RefCell, rc::Rc};
+
+    fn parse(input: &str) -> (Ast, DiagnosticsColletionCell) {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut parser = Parser::from_input(input.to_string(), Rc::clone(&diagnostics));
+        let ast = parser.parse();
+        assert_eq!(diagnostics.borrow().diagnostics.len(), 0);
+        (ast, diagnostics)
+    }
+
+    fn solve_with_diagnostics(
+        input: &str,
+    ) -> (Result<Option<Value>, SolverError>, DiagnosticsColletionCell) {
+        let (ast, diagnostics) = parse(input);
+        let mut solver = ASTSolver::new(Rc::clone(&diagnostics));
+        (solver.evaluate(&ast), diagnostics)
+    }
+
+    fn solve(input: &str) -> Result<Option<Value>, SolverError> {
+        let (result, diagnostics) = solve_with_diagnostics(input);
+        assert_eq!(diagnostics.borrow().diagnostics.len(), 0);
+        result
+    }
+
+    fn solve_rational(input: &str) -> Result<Option<Value<Rational>>, SolverError> {
+        let (ast, diagnostics) = parse(input);
+        let mut solver = ASTSolver::<Rational>::with_numeric(Rc::clone(&diagnostics));
+        let result = solver.evaluate(&ast);
+        assert_eq!(diagnostics.borrow().diagnostics.len(), 0);
+        result
+    }
+
+    // names are resolved by default, looking them up by name has to agree
+    fn solve_resolved_and_by_name(input: &str) -> Result<Option<Value>, SolverError> {
+        let result = solve(input);
+        let (ast, diagnostics) = parse(input);
+        let mut solver = ASTSolver::new(diagnostics);
+        solver.set_resolve_names(false);
+        assert_eq!(solver.evaluate(&ast), result, "{input}");
+        result
+    }
+
+    // a program whose time is spent in looking up variables of outer scopes
+    fn lookup_heavy_program(iterations: usize) -> String {
+        format!(
+            "let a: i32 = 1; let b: i32 = 2; let c: i32 = 3; var sum: i32 = 0; var i: i32 = 0;
+            while i < {iterations} {{ {{ {{ {{ sum = sum + a * b + c - a; i = i + 1; }} }} }} }} sum;"
+        )
+    }
+
+    #[test]
+    fn should_resolve_names_like_lookup_by_name() {
+        for input in [
+            "let a: i32 = 1; { a; let a: i32 = 2; a * 10; }",
+            "var a: i32 = 1; { a = a + 1; { var b: i32 = a; b = b * 3; a = b; } } a;",
+            "func fib(n) -> i32 { if n < 2 { return n; } return fib(n - 1) + fib(n - 2); } fib(10);",
+            "func f(a, b = a * 2, c = b + 1) -> i32 { return a + b + c; } f(1) * 100 + f(1, 1);",
+            "func f() -> i32 { return later; } let later: i32 = 5; f();",
+            "let x: i32 = { let a: i32 = 2; { let a: i32 = 3; a } + a }; x;",
+            "var n: i32 = 0; match n { 0 => { let n: i32 = 7; n; } } n = n + 1;",
+            "func f(n) -> i32 { let a: i32 = 1; { if n > 0 { return n; } let b: i32 = 2; } return a; } f(0) + f(3);",
+        ] {
+            assert!(solve_resolved_and_by_name(input).is_ok(), "{input}");
+        }
+        assert_eq!(
+            solve_resolved_and_by_name(&lookup_heavy_program(10)),
+            Ok(Some(Value::Int(40)))
+        );
+    }
+
+    #[test]
+    fn should_scope_functions_to_their_block() {
+        assert_eq!(
+            solve_resolved_and_by_name(
+                "{ let hidden: i32 = 2; func f() -> i32 { return hidden; } let z: i32 = 99; f(); }"
+            ),
+            Ok(Some(Value::Int(2)))
+        );
+        assert_eq!(
+            solve_resolved_and_by_name(
+                "func outer(x) -> i32 { let y: i32 = 10; func inner() -> i32 { return x + y; } return inner(); } outer(3);"
+            ),
+            Ok(Some(Value::Int(13)))
+        );
+
+        for input in [
+            "{ let hidden: i32 = 2; func f() -> i32 { return hidden; } } let z: i32 = 99; let y: i32 = 98; f();",
+            "func outer() -> i32 { func f() -> i32 { return 1; } return f(); } outer(); f();",
+        ] {
+            for resolve_names in [true, false] {
+                let (ast, diagnostics) = parse(input);
+                let mut solver = ASTSolver::new(diagnostics);
+                solver.set_resolve_names(resolve_names);
+                assert!(
+                    matches!(solver.evaluate(&ast), Err(SolverError::UndefinedFunction(span)) if span.start == input.len() - 4),
+                    "{input}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_resolve_names_of_earlier_evaluations() {
+        let (_, diagnostics) = parse("");
+        let mut solver = ASTSolver::new(diagnostics);
+        for (input, expected) in [
+            (
+                "let a: i32 = 1; func f(x) -> i32 { return x + a; }",
+                Some(Value::Int(1)),
+            ),
+            ("var b: i32 = f(1); b;", Some(Value::Int(2))),
+            ("let a: i32 = 10; b = f(b); b + a;", Some(Value::Int(22))),
+        ] {
+            let (ast, _) = parse(input);
+            assert_eq!(solver.evaluate(&ast), Ok(expected), "{input}");
+        }
+        assert_eq!(solver.dump_scopes(), "scope 0: a = 10, b = 12\n");
+    }
+
+    // cargo test --release -- --ignored solve_lookup_heavy_program
+    #[test]
+    #[ignore]
+    fn should_solve_lookup_heavy_program_faster_with_resolved_names() {
+        let (ast, diagnostics) = parse(&lookup_heavy_program(200_000));
+        let time = |resolve_names: bool| {
+            let mut solver = ASTSolver::new(Rc::clone(&diagnostics));
+            solver.set_resolve_names(resolve_names);
+            let start = std::time::Instant::now();
+            assert_eq!(solver.evaluate(&ast), Ok(Some(Value::Int(800_000))));
+            start.elapsed()
+        };
+        let by_name = time(false);
+        let resolved = time(true);
+        assert!(
+            resolved < by_name,
+            "by name: {:?}, resolved: {:?}",
+            by_name,
+            resolved
+        );
+    }
+
+    #[test]
+    fn should_solve_exponent_literal() {
+        assert_eq!(solve("1.5e2;"), Ok(Some(Value::Float(150.0))));
+    }
+
+    #[test]
+    fn should_subtract_negated_operand() {
+        assert_eq!(solve("1 - -1;"), Ok(Some(Value::Int(2))));
+        assert_eq!(solve("1--1;"), Ok(Some(Value::Int(2))));
+        assert_eq!(solve("1 + +1;"), Ok(Some(Value::Int(2))));
+        assert_eq!(solve("1 - - -1;"), Ok(Some(Value::Int(0))));
+    }
+
+    #[test]
+    fn should_solve_chained_unary_operators() {
+        assert_eq!(solve("!!0;"), Ok(Some(Value::Int(0))));
+        assert_eq!(solve("- -5;"), Ok(Some(Value::Int(5))));
+        assert_eq!(solve("~~0;"), Ok(Some(Value::Int(0))));
+        assert_eq!(solve("!-1;"), Ok(Some(Value::Int(0))));
+        assert_eq!(solve("+3 - -2;"), Ok(Some(Value::Int(5))));
+        assert_eq!(solve("-2 * 3;"), Ok(Some(Value::Int(-6))));
+        assert_eq!(solve("-5 * -5;"), Ok(Some(Value::Int(25))));
+        assert_eq!(solve("-2 ** 2;"), Ok(Some(Value::Int(4))));
+    }
+
+    #[test]
+    fn should_solve_power_right_associative() {
+        assert_eq!(solve("2 ** 3 ** 2;"), Ok(Some(Value::Int(512))));
+        assert_eq!(solve("(2 ** 3) ** 2;"), Ok(Some(Value::Int(64))));
+        assert_eq!(solve("2 * 3 ** 2;"), Ok(Some(Value::Int(18))));
+        assert_eq!(solve("4.0 ** 0.5;"), Ok(Some(Value::Float(2.0))));
+        assert_eq!(solve("2 ** (0 - 1);"), Ok(Some(Value::Float(0.5))));
+    }
+
+    #[test]
+    fn should_truncate_integer_division() {
+        assert_eq!(solve("7 / 2;"), Ok(Some(Value::Int(3))));
+        assert_eq!(solve("(0 - 7) / 2;"), Ok(Some(Value::Int(-3))));
+        assert_eq!(solve("7 % (0 - 2);"), Ok(Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn should_promote_mixed_operands_to_float() {
+        assert_eq!(solve("7 / 2.0;"), Ok(Some(Value::Float(3.5))));
+        assert_eq!(solve("1 + 0.5;"), Ok(Some(Value::Float(1.5))));
+        assert_eq!(solve("7.5 % 2;"), Ok(Some(Value::Float(1.5))));
+    }
+
+    #[test]
+    fn should_compare_integers_exactly() {
+        // 2^53 + 1 and 2^53 are the same as floats
+        for (input, expected) in [
+            ("9007199254740993 == 9007199254740992;", 0),
+            ("9007199254740993 != 9007199254740992;", 1),
+            ("9007199254740993 > 9007199254740992;", 1),
+            ("9007199254740993 <= 9007199254740992;", 0),
+            ("9223372036854775807 == 9223372036854775806;", 0),
+            ("9007199254740992 == 9007199254740992.0;", 1),
+            ("2 < 2.5;", 1),
+        ] {
+            assert_eq!(solve(input), Ok(Some(Value::Int(expected))), "{input}");
+        }
+    }
+
+    #[test]
+    fn should_report_integer_overflow() {
+        for input in [
+            "let big: i64 = 4611686018427387904; big * 2;",
+            "9223372036854775807 + 1;",
+            "let min: i64 = -9223372036854775807 - 1; -min;",
+            "let min: i64 = -9223372036854775807 - 1; min / -1;",
+            "2 ** 63;",
+        ] {
+            let (result, diagnostics) = solve_with_diagnostics(input);
+            assert!(
+                matches!(result, Err(SolverError::IntegerOverflow(_))),
+                "{input}"
+            );
+            let diagnostics = &diagnostics.borrow().diagnostics;
+            assert_eq!(diagnostics.len(), 1, "{input}");
+            assert_eq!(diagnostics[0].message, "Integer overflow");
+        }
+        // floats don't overflow to an error
+        assert_eq!(
+            solve("4611686018427387904 * 2.0;"),
+            Ok(Some(Value::Float(9223372036854775808.0)))
+        );
+        assert_eq!(solve("2 ** 62;"), Ok(Some(Value::Int(4611686018427387904))));
+    }
+
+    #[test]
+    fn should_report_integer_division_by_zero() {
+        let (result, diagnostics) = solve_with_diagnostics("1 / 0;");
+        assert!(matches!(result, Err(SolverError::DivisionByZero(_))));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Division by zero");
+    }
+
+    #[test]
+    fn should_report_floating_division_by_zero() {
+        for input in ["1.5 / 0.0;", "1 / 0.0;", "2.5 % 0;"] {
+            let (result, diagnostics) = solve_with_diagnostics(input);
+            assert!(matches!(result, Err(SolverError::DivisionByZero(_))));
+            assert_eq!(
+                diagnostics.borrow().diagnostics[0].message,
+                "Division by zero"
+            );
+        }
+    }
+
+    #[test]
+    fn should_report_division_by_zero_variable() {
+        let input = "let zero: i32 = 0; let x: f64 = 4.0; x / zero;";
+        let (result, diagnostics) = solve_with_diagnostics(input);
+        assert!(matches!(result, Err(SolverError::DivisionByZero(span)) if span.start == 39));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].span.literal(input), "/");
+
+        let (result, _) = solve_with_diagnostics("var n: i32 = 3; n = n - 3; 10 % n;");
+        assert!(matches!(result, Err(SolverError::DivisionByZero(_))));
+    }
+
+    #[test]
+    fn should_solve_modulo() {
+        assert_eq!(solve("10 % 3;"), Ok(Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn should_solve_modulo_assignment() {
+        assert_eq!(
+            solve("var a: i32 = 17; a %= 5; a;"),
+            Ok(Some(Value::Int(2)))
+        );
+    }
+
+    #[test]
+    fn should_solve_modulo_with_multiplicative_precedence() {
+        assert_eq!(solve("2 + 10 % 4 * 3;"), Ok(Some(Value::Int(8))));
+    }
+
+    #[test]
+    fn should_report_undefined_variable() {
+        let input = "let y: i32 = x + 1;";
+        let (result, diagnostics) = solve_with_diagnostics(input);
+        assert!(
+            matches!(result, Err(SolverError::UndefinedVariable(span)) if span.literal(input) == "x")
+        );
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Undefined variable 'x'");
+    }
+
+    #[test]
+    fn should_solve_comparisons() {
+        assert_eq!(solve("3 > 2;"), Ok(Some(Value::Int(1))));
+        assert_eq!(solve("2 == 2;"), Ok(Some(Value::Int(1))));
+        assert_eq!(solve("5 <= 4;"), Ok(Some(Value::Int(0))));
+        assert_eq!(solve("5 != 4;"), Ok(Some(Value::Int(1))));
+        assert_eq!(solve("4 >= 5;"), Ok(Some(Value::Int(0))));
+        assert_eq!(solve("1 < 2;"), Ok(Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn should_compose_comparisons_with_arithmetic() {
+        assert_eq!(solve("(3 > 2) + (2 == 2);"), Ok(Some(Value::Int(2))));
+    }
+
+    #[test]
+    fn should_solve_logic_operators() {
+        assert_eq!(solve("2 && 3;"), Ok(Some(Value::Int(1))));
+        assert_eq!(solve("2 && 0;"), Ok(Some(Value::Int(0))));
+        assert_eq!(solve("0 || 7;"), Ok(Some(Value::Int(1))));
+        assert_eq!(solve("0 || 0;"), Ok(Some(Value::Int(0))));
+    }
+
+    #[test]
+    fn should_solve_bitwise_operators() {
+        assert_eq!(solve("12 | 3;"), Ok(Some(Value::Int(15))));
+        assert_eq!(solve("12 & 6;"), Ok(Some(Value::Int(4))));
+        assert_eq!(solve("12 ^ 6;"), Ok(Some(Value::Int(10))));
+    }
+
+    #[test]
+    fn should_short_circuit_logic_operators() {
+        let input = "\
+        var a: i32 = 0;
+        func f() -> i32 { a = 1; return 1; }
+        0 && f();
+        1 || f();
+        a;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(0))));
+    }
+
+    #[test]
+    fn should_evaluate_operands_left_to_right() {
+        let input = "\
+        var i: i32 = 0;
+        (i = i + 1) + (i = i + 1) * 10;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(21))));
+
+        let input = "\
+        var i: i32 = 1;
+        (i = i * 2) - (i = i + 3);
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(-3))));
+    }
+
+    #[test]
+    fn should_evaluate_call_arguments_left_to_right() {
+        let input = "\
+        var i: i32 = 0;
+        func f(a: i32, b: i32) -> i32 { return a * 10 + b; }
+        f(i = i + 1, i = i + 1);
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(12))));
+    }
+
+    #[test]
+    fn should_only_evaluate_right_operand_when_needed() {
+        let input = "\
+        var i: i32 = 0;
+        ((i = i + 1) > 5 && (i = i + 10) > 0) || (i = i + 100) > 0;
+        i;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(101))));
+    }
+
+    #[test]
+    fn should_solve_boolean_literals() {
+        assert_eq!(solve("true;"), Ok(Some(Value::Int(1))));
+        assert_eq!(solve("false || true;"), Ok(Some(Value::Int(1))));
+        assert_eq!(solve("true && false;"), Ok(Some(Value::Int(0))));
+    }
+
+    #[test]
+    fn should_accept_boolean_literal_as_condition() {
+        let input = "\
+        var a: i32 = 0;
+        if (true) { a = 1; } else { a = 2; }
+        a;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn should_report_string_in_arithmetic() {
+        let (result, diagnostics) = solve_with_diagnostics("\"lift\" + 1;");
+        assert!(matches!(result, Err(SolverError::StringInArithmetic(_))));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Strings can't be used in arithmetic expressions"
+        );
+    }
+
+    #[test]
+    fn should_concatenate_strings() {
+        assert_eq!(
+            solve("\"foo\" + \"bar\";"),
+            Ok(Some(Value::Str("foobar".to_string())))
+        );
+        assert_eq!(
+            solve("let a: str = \"foo\"; var b: str = a; b = b + \"bar\"; b + a;"),
+            Ok(Some(Value::Str("foobarfoo".to_string())))
+        );
+        assert_eq!(solve("\"foo\" == \"foo\";"), Ok(Some(Value::Int(1))));
+        assert_eq!(solve("\"foo\" != \"bar\";"), Ok(Some(Value::Int(1))));
+        assert_eq!(solve("\"\" || \"foo\";"), Ok(Some(Value::Int(1))));
+        assert_eq!(solve("!\"\";"), Ok(Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn should_report_string_operand_of_other_operators() {
+        for input in [
+            "\"a\" - 1;",
+            "\"a\" * \"b\";",
+            "\"a\" < \"b\";",
+            "\"a\" == 1;",
+            "-\"a\";",
+        ] {
+            let (result, diagnostics) = solve_with_diagnostics(input);
+            assert!(
+                matches!(&result, Err(SolverError::StringInArithmetic(span)) if span.literal(input) != "\"a\""),
+                "{input}"
+            );
+            assert_eq!(diagnostics.borrow().diagnostics.len(), 1, "{input}");
+        }
+        let (result, _) = solve_with_diagnostics("sqrt(\"a\");");
+        assert!(matches!(result, Err(SolverError::DomainError(_))));
+    }
+
+    #[test]
+    fn should_format_result_with_precision() {
+        let (ast, diagnostics) = parse("10.0 / 3;");
+        let mut solver = ASTSolver::new(diagnostics);
+        solver.evaluate(&ast).unwrap();
+        assert_eq!(solver.formatted_result(), "3.3333333333333335");
+        solver.set_print_precision(2);
+        assert_eq!(solver.formatted_result(), "3.33");
+
+        let (ast, _) = parse("(1.0 / 8, 2, \"a\");");
+        solver.evaluate(&ast).unwrap();
+        assert_eq!(solver.formatted_result(), "(0.12, 2, \"a\")");
+    }
+
+    #[test]
+    fn should_format_integral_floats_without_point() {
+        let format = NumberFormat {
+            precision: Some(2),
+            integral_without_point: true,
+            ..NumberFormat::default()
+        };
+        assert_eq!(Value::Float(4.0).format(format), "4");
+        assert_eq!(Value::Float(3.999).format(format), "4");
+        assert_eq!(Value::Float(-0.5).format(format), "-0.50");
+        assert_eq!(Value::Float(4.0).format(NumberFormat::default()), "4");
+        assert_eq!(
+            Value::Float(4.0).format(NumberFormat {
+                precision: Some(1),
+                ..NumberFormat::default()
+            }),
+            "4.0"
+        );
+    }
+
+    #[test]
+    fn should_format_integer_result_in_radix() {
+        let (ast, diagnostics) = parse("255;");
+        let mut solver = ASTSolver::new(diagnostics);
+        solver.evaluate(&ast).unwrap();
+        solver.set_result_radix(Radix::Hexadecimal);
+        assert_eq!(solver.formatted_result(), "0xff");
+        solver.set_result_radix(Radix::Binary);
+        assert_eq!(solver.formatted_result(), "0b11111111");
+        solver.set_result_radix(Radix::Octal);
+        assert_eq!(solver.formatted_result(), "0o377");
+
+        let (ast, _) = parse("(-255, 2.5);");
+        solver.set_result_radix(Radix::Hexadecimal);
+        solver.evaluate(&ast).unwrap();
+        assert_eq!(solver.formatted_result(), "(-0xff, 2.5)");
+    }
+
+    #[test]
+    fn should_format_float_result_in_scientific_notation() {
+        let (ast, diagnostics) = parse("1000000.0;");
+        let mut solver = ASTSolver::new(diagnostics);
+        solver.evaluate(&ast).unwrap();
+        solver.set_print_scientific(true);
+        assert_eq!(solver.formatted_result(), "1e6");
+        solver.set_print_precision(2);
+        assert_eq!(solver.formatted_result(), "1.00e6");
+        solver.set_print_integral_floats_without_point(true);
+        assert_eq!(solver.formatted_result(), "1e6");
+
+        let format = NumberFormat {
+            scientific: true,
+            ..NumberFormat::default()
+        };
+        assert_eq!(Value::Float(0.00125).format(format), "1.25e-3");
+        assert_eq!(Value::Float(f64::INFINITY).format(format), "inf");
+    }
+
+    #[test]
+    fn should_print_to_output() {
+        let (ast, diagnostics) =
+            parse("print(1 + 2); let name: str = \"lift\"; print(\"hello \" + name); 4;");
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let mut solver = ASTSolver::new(Rc::clone(&diagnostics));
+        solver.set_output(output.clone());
+        assert_eq!(solver.evaluate(&ast), Ok(Some(Value::Int(4))));
+        assert_eq!(String::from_utf8_lossy(&output.borrow()), "3\nhello lift\n");
+    }
+
+    #[test]
+    fn should_solve_hexadecimal_and_binary_literals() {
+        assert_eq!(solve("0xFF + 0b1010;"), Ok(Some(Value::Int(265))));
+    }
+
+    #[test]
+    fn should_produce_no_value_without_expressions() {
+        assert_eq!(solve(""), Ok(None));
+        assert_eq!(solve("func f() -> i32 { return 1; }"), Ok(None));
+    }
+
+    #[test]
+    fn should_call_function_with_empty_body() {
+        assert_eq!(solve("func f() {} f();"), Ok(None));
+        assert_eq!(solve("func f() {} f(); 1;"), Ok(Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn should_report_use_of_missing_value() {
+        let input = "func f() {} f() + 1;";
+        let (result, diagnostics) = solve_with_diagnostics(input);
+        assert!(
+            matches!(result, Err(SolverError::MissingValue(span)) if span.literal(input) == "f()")
+        );
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Expression has no value");
+        assert_eq!(diagnostics[0].span.literal(input), "f()");
+    }
+
+    #[test]
+    fn should_report_too_few_function_arguments() {
+        let input = "\
+        func f(a: i32, b: i32) -> i32 { return a + b; }
+        f(1);
+        ";
+        let (result, diagnostics) = solve_with_diagnostics(input);
+        assert!(matches!(
+            result,
+            Err(SolverError::ArgumentCountMismatch {
+                expected: 2,
+                found: 1,
+                ..
+            })
+        ));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Function 'f' expects 2 arguments but got 1"
+        );
+        assert_eq!(diagnostics[0].span.literal(input), "f");
+    }
+
+    #[test]
+    fn should_report_too_many_function_arguments() {
+        let input = "\
+        func f(a: i32) -> i32 { return a; }
+        f(1, 2, 3);
+        ";
+        let (result, diagnostics) = solve_with_diagnostics(input);
+        assert!(matches!(
+            result,
+            Err(SolverError::ArgumentCountMismatch {
+                expected: 1,
+                found: 3,
+                ..
+            })
+        ));
+        assert_eq!(diagnostics.borrow().diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn should_report_undefined_function() {
+        let input = "g(1);";
+        let (result, diagnostics) = solve_with_diagnostics(input);
+        assert!(
+            matches!(result, Err(SolverError::UndefinedFunction(span)) if span.literal(input) == "g")
+        );
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Undefined function 'g'");
+    }
+
+    #[test]
+    fn should_report_unbounded_recursion() {
+        let input = "\
+        func f(x: i32) -> i32 { return f(x) + f(x); }
+        f(1);
+        ";
+        let (ast, diagnostics) = parse(input);
+
+        let mut solver = ASTSolver::new(Rc::clone(&diagnostics));
+        solver.set_max_call_depth(50);
+        assert!(matches!(
+            solver.evaluate(&ast),
+            Err(SolverError::RecursionLimitExceeded(_))
+        ));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Maximum recursion depth of 50 exceeded"
+        );
+    }
+
+    #[test]
+    fn should_report_unbounded_recursion_at_default_limit() {
+        for input in [
+            "func f() -> i32 { return f(); } f();",
+            "func f(x) -> i32 { if x > 0 { let y: i32 = 1 + (2 * f(x)); return y; } return 0; } f(1);",
+        ] {
+            let (result, diagnostics) = solve_with_diagnostics(input);
+            assert!(
+                matches!(result, Err(SolverError::RecursionLimitExceeded(_))),
+                "{input}"
+            );
+            assert_eq!(
+                diagnostics.borrow().diagnostics[0].message,
+                "Maximum recursion depth of 250 exceeded"
+            );
+        }
+    }
+
+    #[test]
+    fn should_call_function_before_declaration() {
+        let input = "\
+        f(2);
+        func f(x: i32) -> i32 { return g(x) + 1; }
+        func g(x: i32) -> i32 { return x * 10; }
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(21))));
+    }
+
+    #[test]
+    fn should_call_mutually_recursive_functions() {
+        let input = "\
+        func a(n: i32) -> i32 { if n == 0 { return 0; } return b(n - 1) + 1; }
+        func b(n: i32) -> i32 { if n == 0 { return 100; } return a(n - 1) * 2; }
+        a(4);
+        ";
+        // a(4) = b(3) + 1 = a(2) * 2 + 1 = (b(1) + 1) * 2 + 1 = (a(0) * 2 + 1) * 2 + 1
+        assert_eq!(solve(input), Ok(Some(Value::Int(3))));
+    }
+
+    #[test]
+    fn should_fill_in_default_parameter_values() {
+        let input = "\
+        func f(x, y = 2){ return x + y; }
+        f(5);
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(7))));
+
+        let input = "\
+        func f(x: i32, y: i32 = x * 2, z: i32 = 1) -> i32 { return x + y + z; }
+        f(1) * 100 + f(1, 1) * 10 + f(1, 1, 0);
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(432))));
+    }
+
+    #[test]
+    fn should_report_missing_argument_without_default() {
+        let (result, diagnostics) =
+            solve_with_diagnostics("func f(x, y = 2) { return x + y; } f();");
+        assert!(matches!(
+            result,
+            Err(SolverError::ArgumentCountMismatch {
+                expected: 1,
+                found: 0,
+                ..
+            })
+        ));
+        assert_eq!(diagnostics.borrow().diagnostics.len(), 1);
+
+        let (result, _) = solve_with_diagnostics("func f(x, y = 2) { return x + y; } f(1, 2, 3);");
+        assert!(matches!(
+            result,
+            Err(SolverError::ArgumentCountMismatch {
+                expected: 2,
+                found: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn should_stop_function_execution_on_return() {
+        let input = "\
+        func f(x: i32) -> i32 { return x; return 999; }
+        f(1);
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn should_skip_side_effects_after_return() {
+        let input = "\
+        var a: i32 = 0;
+        func f() -> i32 { return 1; a = 5; }
+        f();
+        a;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(0))));
+    }
+
+    #[test]
+    fn should_return_from_nested_if() {
+        let input = "\
+        func f(x){ if(x>0){return 1;} return 0; }
+        f(5);
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn should_return_from_nested_blocks_and_loops() {
+        let input = "\
+        var a: i32 = 0;
+        func f(x: i32) -> i32 {
+            var i: i32 = 0;
+            while i < 10 {
+                { if i == x { return i * 10; } }
+                i = i + 1;
+            }
+            a = 99;
+            return -1;
+        }
+        f(3) + a;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(30))));
+    }
+
+    #[test]
+    fn should_continue_caller_after_nested_return() {
+        let input = "\
+        func g(x: i32) -> i32 { if x > 0 { return 1; } else { return 2; } }
+        func f() -> i32 { var a: i32 = g(1); a = a + g(0); return a; }
+        f();
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(3))));
+    }
+
+    #[test]
+    fn should_stop_program_on_top_level_return() {
+        let input = "\
+        var a: i32 = 1;
+        if a > 0 { return a; }
+        { a = 2; }
+        a = 3;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn should_evaluate_then_branch() {
+        let input = "\
+        var a: i32 = 0;
+        if 2 > 1 { a = 1; } else { a = 2; }
+        a;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn should_evaluate_else_branch() {
+        let input = "\
+        var a: i32 = 0;
+        if 2 < 1 { a = 1; } else { a = 2; }
+        a;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(2))));
+    }
+
+    #[test]
+    fn should_evaluate_only_matching_branch_of_else_if_chain() {
+        for (value, expected) in [(-5, 1), (0, 2), (5, 3)] {
+            let input = format!(
+                "\
+                let x: i32 = {};
+                var a: i32 = 0;
+                var evaluated: i32 = 0;
+                if x < 0 {{ evaluated += 1; a = 1; }}
+                else if x == 0 {{ evaluated += 1; a = 2; }}
+                else {{ evaluated += 1; a = 3; }}
+                a * 10 + evaluated;
+                ",
+                value
+            );
+            assert_eq!(solve(&input), Ok(Some(Value::Int(expected * 10 + 1))));
+        }
+    }
+
+    #[test]
+    fn should_pop_scope_of_else_if_branch() {
+        let input = "\
+        if 0 { let b: i32 = 1; } else if 1 { let b: i32 = 2; } else { let b: i32 = 3; }
+        b;
+        ";
+        assert!(matches!(
+            solve_with_diagnostics(input).0,
+            Err(SolverError::UndefinedVariable(_))
+        ));
+    }
+
+    #[test]
+    fn should_skip_conditional_without_else() {
+        let input = "\
+        var a: i32 = 0;
+        if 0 { a = 1; }
+        a;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(0))));
+    }
+
+    #[test]
+    fn should_scope_conditional_branch_variables() {
+        let input = "\
+        if 1 { let b: i32 = 2; }
+        b;
+        ";
+        assert!(matches!(
+            solve_with_diagnostics(input).0,
+            Err(SolverError::UndefinedVariable(_))
+        ));
+    }
+
+    #[test]
+    fn should_evaluate_block_statements() {
+        let input = "\
+        var a: i32 = 1;
+        { let b: i32 = 2; a = a + b; }
+        a;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(3))));
+    }
+
+    #[test]
+    fn should_evaluate_block_expression_to_trailing_expression() {
+        assert_eq!(
+            solve("let x: i32 = { let a: i32 = 2; a * a }; x;"),
+            Ok(Some(Value::Int(4)))
+        );
+        assert_eq!(
+            solve("var y: i32 = 1 + { let b: i32 = 3; { b * 2 } }; y;"),
+            Ok(Some(Value::Int(7)))
+        );
+        assert_eq!(
+            solve("func f(n) -> i32 { n * 2 } f(4);"),
+            Ok(Some(Value::Int(8)))
+        );
+    }
+
+    #[test]
+    fn should_report_use_of_block_without_value() {
+        let input = "let x: i32 = 1 + { 2; }; x;";
+        let (result, diagnostics) = solve_with_diagnostics(input);
+        assert!(
+            matches!(result, Err(SolverError::MissingValue(span)) if span.literal(input) == "{ 2; }")
+        );
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Expression has no value");
+    }
+
+    #[test]
+    fn should_return_from_block_expression() {
+        let input = "\
+        func f(n) -> i32 {
+            let x: i32 = 1 + { if n > 0 { return n; } 0 };
+            return x;
+        }
+        f(5) + f(0);
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(6))));
+    }
+
+    #[test]
+    fn should_run_matching_arm() {
+        let input = "\
+        func pick(x) -> i32 {
+            var r: i32 = 0;
+            match x {
+                1 => { r = 10; }
+                2 => { r = 20; }
+                _ => { r = -1; }
+            }
+            return r;
+        }
+        pick(1) * 100 + pick(2) + pick(7);
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(1019))));
+    }
+
+    #[test]
+    fn should_evaluate_char_as_code_point() {
+        assert_eq!(solve("'a';"), Ok(Some(Value::Int(97))));
+        assert_eq!(solve("'\\n' + 1;"), Ok(Some(Value::Int(11))));
+    }
+
+    #[test]
+    fn should_match_literal_patterns() {
+        assert_eq!(
+            solve("match \"b\" { \"a\" => { 1; } \"b\" => { 2; } }"),
+            Ok(Some(Value::Int(2)))
+        );
+        assert_eq!(
+            solve("match -2.0 { 2 => { 1; } -2 => { 2; } }"),
+            Ok(Some(Value::Int(2)))
+        );
+        // without a matching arm the scrutinee is the result
+        assert_eq!(solve("match 3 { 1 => { 10; } }"), Ok(Some(Value::Int(3))));
+    }
+
+    #[test]
+    fn should_only_evaluate_chosen_branch_of_ternary() {
+        assert_eq!(solve("1 ? 10 : 20;"), Ok(Some(Value::Int(10))));
+        assert_eq!(solve("0 ? 10 : 0 ? 20 : 30;"), Ok(Some(Value::Int(30))));
+        let input = "\
+        var n: i32 = 0;
+        1 ? 10 : (n = 5);
+        0 ? (n = 7) : 20;
+        n;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(0))));
+    }
+
+    #[test]
+    fn should_return_tuple_from_function() {
+        let input = "func f(a) { return (a, a + 1); } f(1);";
+        assert_eq!(
+            solve(input),
+            Ok(Some(Value::Tuple(vec![Value::Int(1), Value::Int(2)])))
+        );
+        assert_eq!(
+            solve("(1, \"a\", (2.5,)) == (1.0, \"a\", (2.5,));"),
+            Ok(Some(Value::Int(1)))
+        );
+        assert_eq!(solve("(1, 2) != (1, 2, 3);"), Ok(Some(Value::Int(1))));
+        assert_eq!(
+            Value::<f64>::Tuple(vec![Value::Int(1), Value::Tuple(vec![Value::Int(2)])]).to_string(),
+            "(1, (2,))"
+        );
+    }
+
+    #[test]
+    fn should_report_tuple_in_arithmetic() {
+        let (result, diagnostics) = solve_with_diagnostics("(1, 2) + 1;");
+        assert!(matches!(result, Err(SolverError::TupleInArithmetic(_))));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Tuples can't be used in arithmetic expressions"
+        );
+    }
+
+    #[test]
+    fn should_only_bind_identifier_parameters() {
+        // the parser drops everything in a parameter list that is not an
+        // identifier, the call only sees the parameter `x`
+        for input in [
+            "func f(1, x) -> i32 { return x; } f(2);",
+            "func f(x, \"s\") -> i32 { return x; } f(2);",
+            "func f(x, 2.5) -> i32 { return x; } f(2);",
+        ] {
+            let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+            let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+            assert_eq!(diagnostics.borrow().error_count(), 1, "{input}");
+            let result = ASTSolver::new(Rc::clone(&diagnostics)).evaluate(&ast);
+            assert_eq!(result, Ok(Some(Value::Int(2))), "{input}");
+        }
+    }
+
+    #[test]
+    fn should_hide_locals_of_caller_from_function() {
+        let input = "\
+        let g: i32 = 10;
+        func f() -> i32 { return g + x; }
+        { let x: i32 = 1; f(); }
+        ";
+        let (result, diagnostics) = solve_with_diagnostics(input);
+        assert!(matches!(
+            result,
+            Err(SolverError::UndefinedVariable(span)) if span.literal(input) == "x"
+        ));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Undefined variable 'x'");
+        // the locals are visible again once the call returns
+        assert_eq!(
+            solve("func f(a) -> i32 { return a; } { let x: i32 = 1; f(2) + x; }"),
+            Ok(Some(Value::Int(3)))
+        );
+    }
+
+    #[test]
+    fn should_reject_call_of_nested_function_after_its_declaring_call() {
+        for input in [
+            "func outer(x) { func inner() -> i32 { return x; } return 0; } func other(y) -> i32 { return inner(); } outer(3); other(42);",
+            "func outer(x) { func inner() -> i32 { return x; } return 0; } outer(3); inner();",
+        ] {
+            let (result, diagnostics) = solve_with_diagnostics(input);
+            assert!(
+                matches!(result, Err(SolverError::UndefinedFunction(span)) if span.literal(input) == "inner"),
+                "{input}"
+            );
+            let diagnostics = &diagnostics.borrow().diagnostics;
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].message, "Undefined function 'inner'");
+        }
+    }
+
+    #[test]
+    fn should_see_variables_enclosing_function_declaration() {
+        assert_eq!(
+            solve_resolved_and_by_name(
+                "func outer(x) -> i32 { func inner() -> i32 { return x; } return inner(); } outer(3);"
+            ),
+            Ok(Some(Value::Int(3)))
+        );
+        assert_eq!(
+            solve_resolved_and_by_name("{ let x: i32 = 1; func f() -> i32 { return x; } f(); }"),
+            Ok(Some(Value::Int(1)))
+        );
+        // a recursive call and a call from a nested block see the same scopes
+        let input = "\
+        func outer(x) -> i32 {
+            func count(n) -> i32 { if n == 0 { return x; } return count(n - 1) + 1; }
+            { let x: i32 = 100; return count(2); }
+        }
+        outer(5);
+        ";
+        assert_eq!(solve_resolved_and_by_name(input), Ok(Some(Value::Int(7))));
+    }
+
+    #[test]
+    fn should_reassign_variable_of_enclosing_scope() {
+        let input = "\
+        let x: i32 = 1;
+        { let y: i32 = 2; x = x + y; }
+        x;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(3))));
+    }
+
+    #[test]
+    fn should_only_reassign_nearest_variable() {
+        let input = "\
+        let x: i32 = 1;
+        { let x: i32 = 2; x = 5; }
+        x;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn should_restore_shadowed_binding_after_block() {
+        let input = "\
+        let x: i32 = 1;
+        let y: i32 = { let x: i32 = x + 10; x * 2 };
+        x * 100 + y;
+        ";
+        assert_eq!(solve_resolved_and_by_name(input), Ok(Some(Value::Int(122))));
+    }
+
+    #[test]
+    fn should_evaluate_assignment_to_assigned_value() {
+        assert_eq!(solve("var a: i32 = 1; a = 4;"), Ok(Some(Value::Int(4))));
+    }
+
+    #[test]
+    fn should_report_assignment_to_undefined_variable() {
+        let input = "x = 1;";
+        let (result, diagnostics) = solve_with_diagnostics(input);
+        assert!(
+            matches!(result, Err(SolverError::UndefinedVariable(span)) if span.literal(input) == "x")
+        );
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Undefined variable 'x'");
+    }
+
+    #[test]
+    fn should_evaluate_compound_assignments() {
+        assert_eq!(solve("let x: i32 = 1; x += 4; x;"), Ok(Some(Value::Int(5))));
+        assert_eq!(
+            solve("var x: i32 = 10; x -= 4; x;"),
+            Ok(Some(Value::Int(6)))
+        );
+        assert_eq!(
+            solve("var x: i32 = 3; x *= 4; x;"),
+            Ok(Some(Value::Int(12)))
+        );
+        assert_eq!(solve("var x: i32 = 9; x /= 2; x;"), Ok(Some(Value::Int(4))));
+        assert_eq!(solve("var x: i32 = 6; x &= 3; x;"), Ok(Some(Value::Int(2))));
+        assert_eq!(solve("var x: i32 = 6; x |= 1; x;"), Ok(Some(Value::Int(7))));
+        assert_eq!(solve("var x: i32 = 6; x ^= 3; x;"), Ok(Some(Value::Int(5))));
+    }
+
+    #[test]
+    fn should_evaluate_compound_assignment_operand_first() {
+        assert_eq!(
+            solve("var x: i32 = 2; x *= 1 + 2; x;"),
+            Ok(Some(Value::Int(6)))
+        );
+    }
+
+    #[test]
+    fn should_not_leak_block_variables() {
+        let input = "\
+        { let x: i32 = 1; }
+        x;
+        ";
+        assert!(matches!(
+            solve_with_diagnostics(input).0,
+            Err(SolverError::UndefinedVariable(span)) if span.literal(input) == "x"
+        ));
+    }
+
+    #[test]
+    fn should_evaluate_while_loop() {
+        let input = "\
+        var i: i32 = 1;
+        var sum: i32 = 0;
+        while i <= 5 {
+            sum += i;
+            i += 1;
+        }
+        sum;
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Int(15))));
+    }
+
+    #[test]
+    fn should_break_and_continue_counting_loop() {
+        // even numbers are skipped, the loop ends after 7
+        let input = "\
+        var i: i32 = 0;
+        var sum: i32 = 0;
+        while i < 10 {
+            i += 1;
+            if i % 2 == 0 { continue; }
+            if i > 7 { break; }
+            sum += i;
+        }
+        sum * 100 + i;
+        ";
+        assert_eq!(
+            solve_resolved_and_by_name(input),
+            Ok(Some(Value::Int(1609)))
+        );
+        // only the innermost loop is left, a return leaves all of them
+        let input = "\
+        func f() {
+            var outer: i32 = 0;
+            var inner: i32 = 0;
+            while outer < 3 {
+                outer += 1;
+                while true {
+                    inner += 1;
+                    break;
+                }
+            }
+            while true { return outer * 10 + inner; }
+        }
+        f();
+        ";
+        assert_eq!(solve_resolved_and_by_name(input), Ok(Some(Value::Int(33))));
+    }
+
+    #[test]
+    fn should_report_break_and_continue_leaving_function() {
+        for (input, keyword) in [
+            ("func f() -> i32 { break; return 1; } var i: i32 = 0; while i < 1 { i += 1; f(); } i;", "break"),
+            ("func f() { continue; } f();", "continue"),
+            ("break; 1;", "break"),
+        ] {
+            let (result, diagnostics) = solve_with_diagnostics(input);
+            assert!(
+                matches!(result, Err(SolverError::OutsideOfLoop(span)) if span.literal(input) == keyword),
+                "{input}"
+            );
+            let diagnostics = &diagnostics.borrow().diagnostics;
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(
+                diagnostics[0].message,
+                format!("'{}' outside of a loop", keyword)
+            );
+        }
+    }
+
+    #[test]
+    fn should_report_runaway_while_loop() {
+        let input = "\
+        var i: i32 = 0;
+        while 1 { i += 1; }
+        ";
+        let (ast, diagnostics) = parse(input);
+
+        let mut solver = ASTSolver::new(Rc::clone(&diagnostics));
+        solver.set_max_loop_iterations(100);
+        assert!(matches!(
+            solver.evaluate(&ast),
+            Err(SolverError::IterationLimitExceeded(_))
+        ));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Loop exceeded the maximum of 100 iterations"
+        );
+    }
+
+    #[test]
+    fn should_call_builtin_functions() {
+        assert_eq!(solve("sqrt(16);"), Ok(Some(Value::Float(4.0))));
+        assert_eq!(solve("abs(-3);"), Ok(Some(Value::Int(3))));
+        assert_eq!(solve("abs(-2.5);"), Ok(Some(Value::Float(2.5))));
+        assert_eq!(solve("min(3, -4);"), Ok(Some(Value::Int(-4))));
+        assert_eq!(solve("max(3, 4.5);"), Ok(Some(Value::Float(4.5))));
+        assert_eq!(solve("pow(2, 10);"), Ok(Some(Value::Int(1024))));
+        assert_eq!(solve("pow(4.0, 0.5);"), Ok(Some(Value::Float(2.0))));
+        assert_eq!(
+            solve("floor(2.5) + ceil(2.5);"),
+            Ok(Some(Value::Float(5.0)))
+        );
+        assert_eq!(solve("floor(7);"), Ok(Some(Value::Int(7))));
+    }
+
+    #[test]
+    fn should_use_builtin_inside_user_function() {
+        let input = "\
+        func hypot(a: f64, b: f64) -> f64 { return sqrt(a * a + b * b); }
+        hypot(3.0, 4.0);
+        ";
+        assert_eq!(solve(input), Ok(Some(Value::Float(5.0))));
+    }
+
+    #[test]
+    fn should_report_builtin_argument_count_mismatch() {
+        let (result, diagnostics) = solve_with_diagnostics("max(1);");
+        assert!(matches!(
+            result,
+            Err(SolverError::ArgumentCountMismatch {
+                expected: 2,
+                found: 1,
+                ..
+            })
+        ));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Function 'max' expects 2 arguments but got 1"
+        );
+    }
+
+    #[test]
+    fn should_evaluate_builtin_arguments_on_count_mismatch() {
+        let (ast, diagnostics) = parse("max(print(16)); sqrt(y, 1);");
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let mut solver = ASTSolver::new(Rc::clone(&diagnostics));
+        solver.set_output(output.clone());
+        assert!(matches!(
+            solver.evaluate(&ast),
+            Err(SolverError::ArgumentCountMismatch { .. })
+        ));
+        assert_eq!(String::from_utf8_lossy(&output.borrow()), "16\n");
+        let messages: Vec<String> = diagnostics
+            .borrow()
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.clone())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Function 'max' expects 2 arguments but got 1",
+                "Undefined variable 'y'",
+                "Function 'sqrt' expects 1 arguments but got 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_builtin_domain_error() {
+        for input in ["sqrt(-1);", "pow(-8, 0.5);"] {
+            let (result, diagnostics) = solve_with_diagnostics(input);
+            assert!(matches!(result, Err(SolverError::DomainError(span)) if span.start == 0));
+            let diagnostics = &diagnostics.borrow().diagnostics;
+            assert_eq!(diagnostics.len(), 1);
+            assert!(diagnostics[0]
+                .message
+                .starts_with("Argument out of the domain of '"));
+        }
+    }
+
+    #[test]
+    fn should_pass_true_assertion() {
+        assert_eq!(solve("assert(1 == 1); 5;"), Ok(Some(Value::Int(5))));
+        assert_eq!(
+            solve("let x: f64 = 0.5; assert x; 1;"),
+            Ok(Some(Value::Int(1)))
+        );
+    }
+
+    #[test]
+    fn should_report_failed_assertion() {
+        let input = "let x: i32 = 3;\nassert(1 == 2);\nx;";
+        let (result, diagnostics) = solve_with_diagnostics(input);
+        assert!(matches!(
+            result,
+            Err(SolverError::AssertionFailed(span)) if span.literal(input) == "(1 == 2)" && span.start == 22
+        ));
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Assertion failed");
+
+        let (result, _) = solve_with_diagnostics("var n: i32 = 1; n -= 1; assert n;");
+        assert!(matches!(result, Err(SolverError::AssertionFailed(_))));
+    }
+
+    #[test]
+    fn should_list_bindings_after_evaluation() {
+        let (ast, diagnostics) =
+            parse("func f(x) { return x; } let a: i32 = 1; let b: i32 = a + 1;");
+        let mut solver = ASTSolver::new(Rc::clone(&diagnostics));
+        assert_eq!(solver.evaluate(&ast), Ok(Some(Value::Int(2))));
+        assert_eq!(
+            solver.current_bindings(),
+            vec![
+                ("a".to_string(), Value::Int(1)),
+                ("b".to_string(), Value::Int(2))
+            ]
+        );
+        assert_eq!(solver.dump_scopes(), "scope 0: a = 1, b = 2\n");
+    }
+
+    #[test]
+    fn should_compute_exactly_with_rational_backend() {
+        assert_eq!(
+            solve_rational("1.0 / 3 + 1.0 / 3 + 1.0 / 3 == 1;"),
+            Ok(Some(Value::Int(1)))
+        );
+        assert_eq!(solve("0.1 + 0.2 == 0.3;"), Ok(Some(Value::Int(0))));
+        assert_eq!(solve_rational("0.1 + 0.2 == 0.3;"), Ok(Some(Value::Int(1))));
+        assert_eq!(
+            solve_rational("1.0 / 3;"),
+            Ok(Some(Value::Float(Rational::new(1, 3))))
+        );
+        assert_eq!(
+            solve_rational("1.0 / 3;").unwrap().unwrap().to_string(),
+            "1/3"
+        );
+        // integer division stays integer division
+        assert_eq!(solve_rational("1 / 3;"), Ok(Some(Value::Int(0))));
+        assert_eq!(
+            solve_rational("pow(2.0 / 3, 2) - min(0.5, 1.5) + abs(-0.25);"),
+            Ok(Some(Value::Float(Rational::new(7, 36))))
+        );
+    }
+
+    #[test]
+    fn should_hide_shadowed_bindings() {
+        let (_, diagnostics) = parse("");
+        let mut solver = ASTSolver::new(diagnostics);
+        solver.add_identifier_to_scope("a", Value::Int(1));
+        solver.enter_scope(Scope::from([("a".to_string(), Value::Float(0.5))]));
+        assert_eq!(
+            solver.current_bindings(),
+            vec![("a".to_string(), Value::Float(0.5))]
+        );
+        assert_eq!(solver.dump_scopes(), "scope 0: a = 1\nscope 1: a = 0.5\n");
+    }
+
+    #[test]
+    fn should_list_variables_named_like_a_function() {
+        let (ast, diagnostics) =
+            parse("func f() -> i32 { return 1; } func g() {} let a: i32 = f(); let f: i32 = 2;");
+        let mut solver = ASTSolver::new(Rc::clone(&diagnostics));
+        assert_eq!(solver.evaluate(&ast), Ok(Some(Value::Int(2))));
+        solver.enter_scope(Scope::from([("g".to_string(), Value::Float(0.5))]));
+        assert_eq!(
+            solver.current_bindings(),
+            vec![
+                ("a".to_string(), Value::Int(1)),
+                ("f".to_string(), Value::Int(2)),
+                ("g".to_string(), Value::Float(0.5))
+            ]
+        );
+        assert_eq!(
+            solver.dump_scopes(),
+            "scope 0: a = 1, f = 2\nscope 1: g = 0.5\n"
+        );
     }
 }
@@ -1,13 +1,79 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::RangeInclusive,
+};
 
 use crate::diagnostics::DiagnosticsColletionCell;
 
-use super::ASTVisitor;
+use super::{
+    builtins::Builtin,
+    lexer::{TextSpan, Token},
+    ASTStatement, ASTStatementKind, ASTVisitor, Ast,
+};
 
+struct Symbol {
+    name: String,
+    // declaration of the symbol, function arguments have none and are never
+    // reported as unused
+    declaration: Option<Token>,
+    is_function: bool,
+    used: bool,
+}
+
+impl Symbol {
+    fn argument(name: String) -> Self {
+        Self {
+            name,
+            declaration: None,
+            is_function: false,
+            used: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Function,
+    Variable,
+    Parameter,
+}
+
+// A declaration as listed by tooling, e.g. for the symbols of a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolInfo {
+    pub name: String,
+    pub kind: SymbolKind,
+    // the identifier of the declaration
+    pub span: TextSpan,
+    // function the symbol is declared in, None in the outermost scope
+    pub container: Option<String>,
+    // number of enclosing scopes, 0 in the outermost scope. A function opens
+    // one scope for its parameters and its body block another one.
+    pub scope_depth: usize,
+}
+
+#[derive(Default)]
+struct Scope {
+    symbols: Vec<Symbol>,
+    // variables declared by a later statement of the scope
+    declared_later: HashSet<String>,
+}
+
+// Variables have to be declared before they are used. Functions are hoisted,
+// they can be called anywhere in the scope they are declared in, even before
+// their declaration.
 pub struct SymbolChecker {
     active_scope: usize,
-    scopes: Vec<Vec<String>>,
-    functions: HashMap<String, Vec<String>>,
+    scopes: Vec<Scope>,
+    // number of arguments a function accepts, parameters with a default
+    // value may be omitted
+    functions: HashMap<String, RangeInclusive<usize>>,
+    // every declaration in the order it was seen
+    declarations: Vec<SymbolInfo>,
+    // functions whose body is visited, the innermost last
+    containers: Vec<String>,
+    // loops around the visited statement in the current function
+    loops: usize,
     diagnostics: DiagnosticsColletionCell,
 }
 
@@ -15,37 +81,211 @@ impl SymbolChecker {
     pub fn new(diagnostics: DiagnosticsColletionCell) -> Self {
         Self {
             active_scope: 0,
-            scopes: vec![Vec::new()],
+            scopes: vec![Scope::default()],
             functions: HashMap::new(),
+            declarations: Vec::new(),
+            containers: Vec::new(),
+            loops: 0,
             diagnostics,
         }
     }
 
-    fn get_active_scope(&self) -> &Vec<String> {
-        self.scopes.get(self.active_scope).unwrap()
+    // The declarations of the checked ast in source order
+    pub fn symbols(&self) -> Vec<SymbolInfo> {
+        let mut symbols = self.declarations.clone();
+        symbols.sort_by_key(|symbol| symbol.span.start);
+        symbols
     }
 
-    fn enter_scope(&mut self, scope_variables: Vec<String>) {
-        self.scopes.push(scope_variables);
+    // Checks all statements of the ast, symbols of the outermost scope are
+    // reported as unused once the whole ast has been visited
+    pub fn check(&mut self, ast: &Ast) {
+        self.declare_block(&ast.statements);
+        ast.visit(self);
+        let scope = std::mem::take(&mut self.scopes[0]);
+        self.report_unused_symbols(scope);
+    }
+
+    fn enter_scope(&mut self, scope_variables: Vec<Symbol>) {
+        self.scopes.push(Scope {
+            symbols: scope_variables,
+            declared_later: HashSet::new(),
+        });
         self.active_scope += 1;
     }
 
     fn leave_scope(&mut self) {
-        self.scopes.pop();
+        let scope = self.scopes.pop().unwrap();
         self.active_scope -= 1;
+        self.report_unused_symbols(scope);
+    }
+
+    // Looks ahead at the statements of a block before they are visited:
+    // functions are declared right away and variables are remembered to
+    // detect uses before their declaration.
+    fn declare_block(&mut self, statements: &[ASTStatement]) {
+        for statement in statements {
+            match &statement.kind {
+                ASTStatementKind::Let(statement) => {
+                    self.declare_later(&statement.identifier.literal)
+                }
+                ASTStatementKind::Var(statement) => {
+                    self.declare_later(&statement.identifier.literal)
+                }
+                ASTStatementKind::FuncDecl(function) => self.declare_function(function),
+                _ => (),
+            }
+        }
+    }
+
+    fn declare_later(&mut self, identifier: &str) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .declared_later
+            .insert(identifier.to_string());
+    }
+
+    fn declare_function(&mut self, function: &super::ASTFunctionStatement) {
+        let scope = self.scopes.last().unwrap();
+        let already_declared = scope.symbols.iter().any(|symbol| {
+            symbol.is_function
+                && symbol
+                    .declaration
+                    .as_ref()
+                    .is_some_and(|declaration| declaration.span == function.identifier.span)
+        });
+        if already_declared {
+            return;
+        }
+
+        let redefined = scope
+            .symbols
+            .iter()
+            .any(|symbol| symbol.is_function && symbol.name == function.identifier.literal);
+        // builtins are resolved first, the declaration could never be called
+        let redefined = redefined || Builtin::<f64>::lookup(&function.identifier.literal).is_some();
+        if redefined {
+            self.diagnostics
+                .borrow_mut()
+                .report_function_already_defined(&function.identifier);
+        }
+
+        self.add_identifier_to_scope(&function.identifier, true);
+        self.functions.insert(
+            function.identifier.literal.clone(),
+            function.required_arguments()..=function.arguments.len(),
+        );
+    }
+
+    fn is_declared_later(&self, identifier: &str) -> bool {
+        self.scopes
+            .iter()
+            .any(|scope| scope.declared_later.contains(identifier))
+    }
+
+    // Only the first statement after a return is reported. Functions are
+    // hoisted, so they can still be called from before the return.
+    fn report_unreachable_code(&mut self, statements: &[ASTStatement]) {
+        let unreachable = statements
+            .iter()
+            .skip_while(|statement| !matches!(statement.kind, ASTStatementKind::Return(_)))
+            .skip(1)
+            .find(|statement| !matches!(statement.kind, ASTStatementKind::FuncDecl(_)));
+        if let Some(statement) = unreachable {
+            self.diagnostics
+                .borrow_mut()
+                .report_unreachable_code(statement.span().clone());
+        }
+    }
+
+    fn report_unused_symbols(&mut self, scope: Scope) {
+        for symbol in scope.symbols {
+            // a leading underscore marks a symbol as intentionally unused
+            if symbol.used || symbol.name.starts_with('_') {
+                continue;
+            }
+            let Some(declaration) = symbol.declaration else {
+                continue;
+            };
+            if symbol.is_function {
+                self.diagnostics
+                    .borrow_mut()
+                    .report_unused_function(&declaration);
+            } else {
+                self.diagnostics
+                    .borrow_mut()
+                    .report_unused_variable(&declaration);
+            }
+        }
     }
 
-    fn add_identifier_to_scope(&mut self, identifier: &String) {
-        self.scopes.last_mut().unwrap().push(identifier.clone());
+    // A variable can shadow one of an enclosing scope, but not one that is
+    // declared in the same scope
+    fn add_identifier_to_scope(&mut self, identifier: &super::Token, is_function: bool) {
+        let redeclared = !is_function
+            && self
+                .scopes
+                .last()
+                .unwrap()
+                .symbols
+                .iter()
+                .any(|symbol| !symbol.is_function && symbol.name == identifier.literal);
+        if redeclared {
+            self.diagnostics
+                .borrow_mut()
+                .report_variable_already_declared(identifier);
+        }
+        self.declarations.push(SymbolInfo {
+            name: identifier.literal.clone(),
+            kind: if is_function {
+                SymbolKind::Function
+            } else {
+                SymbolKind::Variable
+            },
+            span: identifier.span.clone(),
+            container: self.containers.last().cloned(),
+            scope_depth: self.active_scope,
+        });
+        self.scopes.last_mut().unwrap().symbols.push(Symbol {
+            name: identifier.literal.clone(),
+            declaration: Some(identifier.clone()),
+            is_function,
+            used: false,
+        });
     }
 
-    fn check_identifier_in_scope(&self, identifier: &String) -> bool {
-        for scope in self.scopes.iter().rev() {
-            if scope.contains(identifier) {
+    // A variable that is read or assigned has to be declared before
+    fn use_variable(&mut self, identifier: &super::Token) {
+        if self.use_identifier_in_scope(&identifier.literal) {
+            return;
+        }
+        if self.is_declared_later(&identifier.literal) {
+            self.diagnostics
+                .borrow_mut()
+                .report_variable_used_before_declaration(identifier);
+        } else {
+            self.diagnostics
+                .borrow_mut()
+                .report_undefined_variable(identifier);
+        }
+    }
+
+    // Marks the innermost symbol with the given name as used, shadowed symbols
+    // stay untouched
+    fn use_identifier_in_scope(&mut self, identifier: &str) -> bool {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(symbol) = scope
+                .symbols
+                .iter_mut()
+                .rev()
+                .find(|symbol| symbol.name == identifier)
+            {
+                symbol.used = true;
                 return true;
             }
         }
-        return false;
+        false
     }
 }
 
@@ -55,94 +295,150 @@ impl ASTVisitor for SymbolChecker {
     }
 
     fn visit_let_statement(&mut self, statement: &super::ASTLetStatement) {
-        self.add_identifier_to_scope(&statement.identifier.span.literal);
+        // the initializer can't refer to the variable it initializes
         self.visit_expression(&statement.initializer);
+        self.add_identifier_to_scope(&statement.identifier, false);
     }
 
     fn visit_var_statement(&mut self, statement: &super::ASTVarStatement) {
-        self.add_identifier_to_scope(&statement.identifier.span.literal);
         self.visit_expression(&statement.initializer);
+        self.add_identifier_to_scope(&statement.identifier, false);
     }
 
     fn visit_compound_statement(&mut self, statement: &super::ASTCompoundStatement) {
         self.enter_scope(Vec::new());
+        self.declare_block(&statement.statements);
         for statement in statement.statements.iter() {
             self.visit_statement(statement);
         }
+        self.report_unreachable_code(&statement.statements);
         self.leave_scope();
     }
 
     fn visit_if_statement(&mut self, statement: &super::ASTIfStatement) {
+        self.visit_expression(&statement.condition);
         self.visit_statement(&statement.then_branch);
         if let Some(else_branch) = &statement.else_branch {
             self.visit_statement(&else_branch.else_branch);
         }
     }
 
-    fn visit_for_loop_statement(&mut self, statement: &super::ASTForStatement) {}
+    fn visit_for_loop_statement(&mut self, _statement: &super::ASTForStatement) {}
+
+    fn visit_while_loop_statement(&mut self, statement: &super::ASTWhileStatement) {
+        self.visit_expression(&statement.condition);
+        self.loops += 1;
+        self.visit_statement(&statement.body);
+        self.loops -= 1;
+    }
 
-    fn visit_while_loop_statement(&mut self, statement: &super::ASTWhileStatement) {}
+    fn visit_break_statement(&mut self, statement: &super::ASTBreakStatement) {
+        if self.loops == 0 {
+            self.diagnostics
+                .borrow_mut()
+                .report_outside_of_loop(&statement.keyword);
+        }
+    }
+
+    fn visit_continue_statement(&mut self, statement: &super::ASTContinueStatement) {
+        if self.loops == 0 {
+            self.diagnostics
+                .borrow_mut()
+                .report_outside_of_loop(&statement.keyword);
+        }
+    }
+
+    fn visit_assert_statement(&mut self, statement: &super::ASTAssertStatement) {
+        self.visit_expression(&statement.condition);
+    }
 
     fn visit_funtion_statement(&mut self, function: &super::ASTFunctionStatement) {
-        self.add_identifier_to_scope(&function.identifier.span.literal);
+        self.declare_function(function);
 
         let mut arguments_names: Vec<String> = Vec::new();
         // add arguments to scope of local variable call
         for arg in function.arguments.iter() {
-            arguments_names.push(arg.identifier.span.literal.clone());
+            if arguments_names.contains(&arg.identifier.literal) {
+                self.diagnostics
+                    .borrow_mut()
+                    .report_duplicate_parameter(&arg.identifier);
+            }
+            arguments_names.push(arg.identifier.literal.clone());
         }
-        self.functions.insert(
-            function.identifier.span.literal.clone(),
-            arguments_names.clone(),
-        );
 
-        // arguments_names.push(function.identifier.span.literal.clone());
-        self.enter_scope(arguments_names);
+        self.containers.push(function.identifier.literal.clone());
+        self.enter_scope(arguments_names.into_iter().map(Symbol::argument).collect());
+        for arg in function.arguments.iter() {
+            self.declarations.push(SymbolInfo {
+                name: arg.identifier.literal.clone(),
+                kind: SymbolKind::Parameter,
+                span: arg.identifier.span.clone(),
+                container: Some(function.identifier.literal.clone()),
+                scope_depth: self.active_scope,
+            });
+        }
+        for arg in function.arguments.iter() {
+            if let Some(default) = &arg.default {
+                self.visit_expression(default);
+            }
+        }
 
+        // a loop around the declaration can't be left from the body
+        let loops = std::mem::take(&mut self.loops);
         self.visit_statement(&function.body);
-        // match &function.body.kind {
-        //     super::ASTStatementKind::Compound(statement) => {
-        //         for statement in statement.statements.iter() {
-        //             self.visit_statement(statement);
-        //         }
-        //     }
-        //     _ => todo!(),
-        // };
+        self.loops = loops;
         self.leave_scope();
+        self.containers.pop();
     }
 
-    fn visit_assignment_expression(&mut self, expr: &super::ASTAssignmentExpression) {}
+    fn visit_assignment_expression(&mut self, expr: &super::ASTAssignmentExpression) {
+        self.visit_expression(&expr.expr);
+        self.use_variable(&expr.identifier);
+    }
 
+    // the arguments are checked even if the call itself is invalid
     fn visit_function_call_expression(&mut self, expr: &super::ASTFunctionCallExpression) {
-        if !self.check_identifier_in_scope(&expr.identifier().to_string()) {
-            self.diagnostics
-                .borrow_mut()
-                .report_undefined_variable(expr.identifier.span.clone());
+        for arg in expr.arguments.iter() {
+            self.visit_expression(arg);
+        }
+
+        if let Some(builtin) = Builtin::<f64>::lookup(expr.identifier()) {
+            if builtin.arity != expr.arguments.len() {
+                self.diagnostics
+                    .borrow_mut()
+                    .report_number_of_function_arguments_mismatch(
+                        &expr.identifier,
+                        builtin.arity,
+                        expr.arguments.len(),
+                    );
+            }
+            return;
         }
 
-        let expected_number_of_arguments = self.functions.get(expr.identifier()).unwrap().len();
-        if expected_number_of_arguments != expr.arguments.len() {
+        let in_scope = self.use_identifier_in_scope(expr.identifier());
+        let accepted = self.functions.get(expr.identifier()).cloned();
+        let Some(accepted) = accepted.filter(|_| in_scope) else {
             self.diagnostics
                 .borrow_mut()
-                .report_number_of_function_arguments_mismatch(
-                    expr.identifier.span.clone(),
-                    expected_number_of_arguments,
-                    expr.arguments.len(),
-                );
+                .report_undefined_function(&expr.identifier);
             return;
-        }
+        };
 
-        for arg in expr.arguments.iter() {
-            self.visit_expression(arg);
+        let found = expr.arguments.len();
+        if !accepted.contains(&found) {
+            let expected = if found < *accepted.start() {
+                *accepted.start()
+            } else {
+                *accepted.end()
+            };
+            self.diagnostics
+                .borrow_mut()
+                .report_number_of_function_arguments_mismatch(&expr.identifier, expected, found);
         }
     }
 
     fn visit_variable_expression(&mut self, expr: &super::ASTVariableExpression) {
-        if !self.check_identifier_in_scope(&expr.identifier().to_string()) {
-            self.diagnostics
-                .borrow_mut()
-                .report_undefined_variable(expr.identifier.span.clone());
-        }
+        self.use_variable(&expr.identifier);
     }
 
     fn visit_unary_expression(&mut self, expr: &super::ASTUnaryExpression) {
@@ -158,7 +454,309 @@ impl ASTVisitor for SymbolChecker {
         self.visit_expression(&expr.expr);
     }
 
-    fn visit_binary_operator(&mut self, op: &super::ASTBinaryOperator) {}
-    fn visit_integer(&mut self, integer: &i64) {}
-    fn visit_float(&mut self, float: &f64) {}
+    fn visit_binary_operator(&mut self, _op: &super::ASTBinaryOperator) {}
+    fn visit_integer(&mut self, _integer: &i64) {}
+    fn visit_float(&mut self, _float: &f64) {}
+    fn visit_boolean(&mut self, _boolean: &bool) {}
+    fn visit_string(&mut self, _string: &super::Token) {}
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::{SymbolChecker, SymbolKind};
+    use crate::ast::{parser::Parser, Ast};
+    use crate::diagnostics::{DiagnosticsColletion, DiagnosticsColletionCell, Severity};
+
+    fn parse(input: &str) -> (Ast, DiagnosticsColletionCell) {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut parser = Parser::from_input(input.to_string(), Rc::clone(&diagnostics));
+        let ast = parser.parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        (ast, diagnostics)
+    }
+
+    fn check(input: &str) -> Vec<(Severity, String)> {
+        let (ast, diagnostics) = parse(input);
+        SymbolChecker::new(Rc::clone(&diagnostics)).check(&ast);
+        let diagnostics = diagnostics
+            .borrow()
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.severity, diagnostic.message.clone()))
+            .collect();
+        diagnostics
+    }
+
+    #[test]
+    fn should_list_declared_symbols() {
+        let input = "let limit: i32 = 3; func twice(n) -> i32 { var r: i32 = n * 2; return r; } twice(limit);";
+        let (ast, _) = parse(input);
+        let symbols: Vec<(String, SymbolKind, String, Option<String>, usize)> = ast
+            .symbols()
+            .into_iter()
+            .map(|symbol| {
+                (
+                    symbol.name,
+                    symbol.kind,
+                    symbol.span.literal(input).to_string(),
+                    symbol.container,
+                    symbol.scope_depth,
+                )
+            })
+            .collect();
+        assert_eq!(
+            symbols,
+            vec![
+                (
+                    "limit".to_string(),
+                    SymbolKind::Variable,
+                    "limit".to_string(),
+                    None,
+                    0
+                ),
+                (
+                    "twice".to_string(),
+                    SymbolKind::Function,
+                    "twice".to_string(),
+                    None,
+                    0
+                ),
+                (
+                    "n".to_string(),
+                    SymbolKind::Parameter,
+                    "n".to_string(),
+                    Some("twice".to_string()),
+                    1
+                ),
+                (
+                    "r".to_string(),
+                    SymbolKind::Variable,
+                    "r".to_string(),
+                    Some("twice".to_string()),
+                    2
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_warn_about_unused_let() {
+        assert_eq!(
+            check("let a: i32 = 1;"),
+            vec![(Severity::Warning, "Unused variable 'a'".to_string())]
+        );
+    }
+
+    #[test]
+    fn should_not_warn_about_function_used_once() {
+        assert!(check("func f(x: i32) -> i32 { return x; } f(1);").is_empty());
+    }
+
+    #[test]
+    fn should_warn_about_unused_function() {
+        assert_eq!(
+            check("func f() -> i32 { return 1; }"),
+            vec![(Severity::Warning, "Unused function 'f'".to_string())]
+        );
+    }
+
+    #[test]
+    fn should_warn_about_shadowed_unused_binding() {
+        assert_eq!(
+            check("let a: i32 = 1; { let a: i32 = 2; a; }"),
+            vec![(Severity::Warning, "Unused variable 'a'".to_string())]
+        );
+    }
+
+    #[test]
+    fn should_report_redeclaration_in_same_scope() {
+        assert_eq!(
+            check("let a: i32 = 1; var a: i32 = 2; a;"),
+            vec![
+                (
+                    Severity::Error,
+                    "Variable 'a' already declared in this scope".to_string()
+                ),
+                (Severity::Warning, "Unused variable 'a'".to_string()),
+            ]
+        );
+        // arguments are in a scope of their own, like the blocks of loops
+        assert!(check(
+            "func f(a) -> i32 { let a: i32 = a * 2; return a; } f(1); let b: i32 = 1; while b < 2 { let b: i32 = 3; b; }"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn should_report_break_and_continue_outside_of_loop() {
+        assert_eq!(
+            check("break; while true { func f() { continue; } f(); break; }"),
+            vec![
+                (Severity::Error, "'break' outside of a loop".to_string()),
+                (Severity::Error, "'continue' outside of a loop".to_string()),
+            ]
+        );
+        assert!(
+            check("var i: i32 = 0; while i < 3 { i = i + 1; if i == 2 { continue; } }").is_empty()
+        );
+    }
+
+    #[test]
+    fn should_warn_about_code_after_return() {
+        let input = "func f() -> i32 { return 1; let x: i32 = 2; x; } f();";
+        let (ast, diagnostics) = parse(input);
+        SymbolChecker::new(Rc::clone(&diagnostics)).check(&ast);
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        let unreachable: Vec<&str> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.message == "Unreachable code after return")
+            .map(|diagnostic| diagnostic.span.literal(input))
+            .collect();
+        assert_eq!(unreachable, vec!["let x: i32 = 2;"]);
+
+        assert!(
+            check("func f() -> i32 { return g(); func g() -> i32 { return 1; } } f();").is_empty()
+        );
+        assert!(check("func f(n) -> i32 { if n { return 1; } return 2; } f(1);").is_empty());
+    }
+
+    #[test]
+    fn should_warn_about_unused_binding_in_block() {
+        assert_eq!(
+            check("{ let b: i32 = 1; }"),
+            vec![(Severity::Warning, "Unused variable 'b'".to_string())]
+        );
+    }
+
+    #[test]
+    fn should_not_warn_about_underscore_prefixed_names() {
+        assert!(check("let _a: i32 = 1; func _f() -> i32 { return 1; }").is_empty());
+    }
+
+    #[test]
+    fn should_report_variable_used_before_declaration() {
+        assert_eq!(
+            check("x; let x: i32 = 1; x;"),
+            vec![(
+                Severity::Error,
+                "Variable 'x' used before declaration".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_check_both_sides_of_assignment() {
+        assert_eq!(
+            check("let a: i32 = 1; a = b;"),
+            vec![(Severity::Error, "Undefined variable 'b'".to_string())]
+        );
+        assert_eq!(
+            check("c = 1;"),
+            vec![(Severity::Error, "Undefined variable 'c'".to_string())]
+        );
+        // an assigned variable counts as used
+        assert_eq!(check("var d: i32 = 1; d = 2;"), vec![]);
+    }
+
+    #[test]
+    fn should_check_arguments_of_call_with_wrong_count() {
+        assert_eq!(
+            check("func f(a) -> i32 { return a; } f(1, x); sqrt(y, 2);"),
+            vec![
+                (Severity::Error, "Undefined variable 'x'".to_string()),
+                (
+                    Severity::Error,
+                    "Function 'f' expects 1 arguments but got 2".to_string()
+                ),
+                (Severity::Error, "Undefined variable 'y'".to_string()),
+                (
+                    Severity::Error,
+                    "Function 'sqrt' expects 1 arguments but got 2".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_variable_used_in_own_initializer() {
+        assert_eq!(
+            check("{ let y: i32 = y + 1; y; }"),
+            vec![(
+                Severity::Error,
+                "Variable 'y' used before declaration".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_allow_forward_reference_to_function() {
+        assert!(
+            check("f(); func f() -> i32 { return g(); } func g() -> i32 { return 1; }").is_empty()
+        );
+    }
+
+    #[test]
+    fn should_report_redefined_function() {
+        let input = "func f() -> i32 { return 1; } func f() -> i32 { return 2; } f();";
+        let (ast, diagnostics) = parse(input);
+        SymbolChecker::new(Rc::clone(&diagnostics)).check(&ast);
+
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        let errors: Vec<_> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Function 'f' already defined");
+        assert_eq!(errors[0].span.start, 35);
+    }
+
+    #[test]
+    fn should_report_duplicate_parameter() {
+        let (ast, diagnostics) = parse("func f(x, x) -> i32 { return x; } f(1, 2);");
+        SymbolChecker::new(Rc::clone(&diagnostics)).check(&ast);
+
+        let diagnostics = &diagnostics.borrow().diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Duplicate parameter 'x'");
+        assert_eq!(diagnostics[0].span.start, 10);
+    }
+
+    #[test]
+    fn should_accept_calls_omitting_default_arguments() {
+        assert!(check("func f(x, y = x + 1) { return y; } f(1); f(1, 2);").is_empty());
+        assert_eq!(
+            check("func f(x, y = 1) { return x + y; } f(1, 2, 3);"),
+            vec![(
+                Severity::Error,
+                "Function 'f' expects 2 arguments but got 3".to_string()
+            )]
+        );
+        assert_eq!(
+            check("func f(x, y = z) { return x + y; } f(1);"),
+            vec![(Severity::Error, "Undefined variable 'z'".to_string())]
+        );
+    }
+
+    #[test]
+    fn should_resolve_builtin_functions() {
+        assert!(check("sqrt(abs(-4));").is_empty());
+        assert_eq!(
+            check("min(1, 2, 3);"),
+            vec![(
+                Severity::Error,
+                "Function 'min' expects 2 arguments but got 3".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_report_redefined_builtin() {
+        assert!(check("func sqrt(x) { return x; }").contains(&(
+            Severity::Error,
+            "Function 'sqrt' already defined".to_string()
+        )));
+    }
 }
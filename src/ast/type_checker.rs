@@ -0,0 +1,652 @@
+use core::fmt;
+use std::collections::HashMap;
+
+use crate::diagnostics::DiagnosticsColletionCell;
+
+use super::{
+    lexer::{TextSpan, TokenKind},
+    ASTBinaryOperatorKind, ASTExpressionKind, ASTStatement, ASTStatementKind, ASTUnaryOperatorKind,
+    ASTVisitor, Ast,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Type {
+    Integer,
+    Float,
+    Bool,
+    Char,
+    Str,
+    Tuple,
+    Void,
+    // integer literals are untyped and may be used where a float is expected
+    IntegerLiteral,
+    Unknown,
+}
+
+impl Type {
+    fn from_token(kind: &TokenKind) -> Self {
+        match kind {
+            TokenKind::I8
+            | TokenKind::I16
+            | TokenKind::I32
+            | TokenKind::I64
+            | TokenKind::U8
+            | TokenKind::U16
+            | TokenKind::U32
+            | TokenKind::U64 => Type::Integer,
+            TokenKind::F32 | TokenKind::F64 => Type::Float,
+            TokenKind::Bool => Type::Bool,
+            TokenKind::Char => Type::Char,
+            TokenKind::Str => Type::Str,
+            TokenKind::Void => Type::Void,
+            _ => Type::Unknown,
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Type::Integer | Type::Float | Type::IntegerLiteral)
+    }
+
+    fn is_assignable_from(&self, other: &Type) -> bool {
+        *self == *other
+            || *self == Type::Unknown
+            || *other == Type::Unknown
+            || (*other == Type::IntegerLiteral && self.is_numeric())
+    }
+
+    // Integers and floats can't be mixed, only untyped integer literals adapt
+    // to the type of the other operand.
+    fn is_mixed_with(&self, other: &Type) -> bool {
+        matches!(
+            (self, other),
+            (Type::Integer, Type::Float) | (Type::Float, Type::Integer)
+        )
+    }
+
+    fn common_numeric(&self, other: &Type) -> Type {
+        match (self, other) {
+            (Type::IntegerLiteral, other) | (other, Type::IntegerLiteral) if other.is_numeric() => {
+                *other
+            }
+            (left, right) if left == right && left.is_numeric() => *left,
+            _ => Type::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Integer | Type::IntegerLiteral => write!(f, "integer"),
+            Type::Float => write!(f, "float"),
+            Type::Bool => write!(f, "bool"),
+            Type::Char => write!(f, "char"),
+            Type::Str => write!(f, "str"),
+            Type::Tuple => write!(f, "tuple"),
+            Type::Void => write!(f, "void"),
+            Type::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+struct FunctionSignature {
+    arguments: Vec<Type>,
+    return_type: Type,
+}
+
+pub struct TypeChecker {
+    // type of the last visited expression
+    result: Type,
+    scopes: Vec<HashMap<String, Type>>,
+    functions: HashMap<String, FunctionSignature>,
+    diagnostics: DiagnosticsColletionCell,
+}
+
+impl TypeChecker {
+    pub fn new(diagnostics: DiagnosticsColletionCell) -> Self {
+        Self {
+            result: Type::Unknown,
+            scopes: vec![HashMap::new()],
+            functions: HashMap::new(),
+            diagnostics,
+        }
+    }
+
+    // Signatures of all functions of the outermost scope are collected first
+    // so calls before the declaration are checked as well
+    pub fn check(&mut self, ast: &Ast) {
+        self.declare_functions(&ast.statements);
+        ast.visit(self);
+    }
+
+    fn declare_functions(&mut self, statements: &[ASTStatement]) {
+        for statement in statements {
+            if let ASTStatementKind::FuncDecl(function) = &statement.kind {
+                self.functions.insert(
+                    function.identifier.literal.clone(),
+                    Self::signature(function),
+                );
+            }
+        }
+    }
+
+    fn signature(function: &super::ASTFunctionStatement) -> FunctionSignature {
+        FunctionSignature {
+            arguments: function
+                .arguments
+                .iter()
+                .map(|arg| {
+                    arg.data_type
+                        .as_ref()
+                        .map_or(Type::Unknown, |data_type| Type::from_token(&data_type.kind))
+                })
+                .collect(),
+            return_type: Type::from_token(&function.return_type.kind),
+        }
+    }
+
+    fn type_of(&mut self, expr: &super::ASTExpression) -> Type {
+        self.result = Type::Unknown;
+        self.visit_expression(expr);
+        self.result
+    }
+
+    fn declare(&mut self, identifier: &str, ty: Type) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(identifier.to_string(), ty);
+    }
+
+    fn lookup(&self, identifier: &str) -> Type {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(identifier))
+            .copied()
+            .unwrap_or(Type::Unknown)
+    }
+
+    fn check_declaration(
+        &mut self,
+        identifier: &super::lexer::Token,
+        data_type: &TokenKind,
+        initializer: &super::ASTExpression,
+    ) {
+        let found = self.type_of(initializer);
+        let declared = Type::from_token(data_type);
+        if !declared.is_assignable_from(&found) {
+            self.diagnostics.borrow_mut().report_mismatched_types(
+                &declared,
+                &found,
+                identifier.span.clone(),
+            );
+        }
+        let ty = if declared == Type::Unknown {
+            found
+        } else {
+            declared
+        };
+        self.declare(&identifier.literal, ty);
+    }
+
+    // The value of an integer literal, also negated or in parentheses. A
+    // negative exponent of `**` leaves the integers, which is only known for
+    // a literal before the program runs.
+    fn integer_constant(expr: &super::ASTExpression) -> Option<i64> {
+        match &expr.kind {
+            ASTExpressionKind::IntegerLiteral(i) => Some(*i),
+            ASTExpressionKind::Parenthesized(expr) => Self::integer_constant(&expr.expr),
+            ASTExpressionKind::Unary(unary) => match unary.operator.kind {
+                ASTUnaryOperatorKind::Plus => Self::integer_constant(&unary.expr),
+                ASTUnaryOperatorKind::Minus => Self::integer_constant(&unary.expr)?.checked_neg(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn check_bitwise_operand(&mut self, operand: &Type, span: &TextSpan) {
+        if *operand == Type::Float {
+            self.diagnostics
+                .borrow_mut()
+                .report_bitwise_on_float(span.clone());
+        }
+    }
+}
+
+impl ASTVisitor for TypeChecker {
+    fn visit_return_statement(&mut self, statement: &super::ASTReturnStatement) {
+        self.visit_expression(&statement.expr);
+    }
+
+    fn visit_let_statement(&mut self, statement: &super::ASTLetStatement) {
+        self.check_declaration(
+            &statement.identifier,
+            &statement.data_type.kind,
+            &statement.initializer,
+        );
+    }
+
+    fn visit_var_statement(&mut self, statement: &super::ASTVarStatement) {
+        self.check_declaration(
+            &statement.identifier,
+            &statement.data_type.kind,
+            &statement.initializer,
+        );
+    }
+
+    fn visit_compound_statement(&mut self, statement: &super::ASTCompoundStatement) {
+        self.scopes.push(HashMap::new());
+        self.declare_functions(&statement.statements);
+        for statement in statement.statements.iter() {
+            self.visit_statement(statement);
+        }
+        self.scopes.pop();
+    }
+
+    fn visit_block_expression(&mut self, block: &super::ASTCompoundStatement) {
+        self.visit_compound_statement(block);
+        // the value was the last visited expression
+        if block.value().is_none() {
+            self.result = Type::Void;
+        }
+    }
+
+    fn visit_if_statement(&mut self, statement: &super::ASTIfStatement) {
+        self.visit_expression(&statement.condition);
+        self.visit_statement(&statement.then_branch);
+        if let Some(else_branch) = &statement.else_branch {
+            self.visit_statement(&else_branch.else_branch);
+        }
+    }
+
+    // the patterns have to be comparable with the scrutinee
+    fn visit_match_statement(&mut self, statement: &super::ASTMatchStatement) {
+        let scrutinee = self.type_of(&statement.scrutinee);
+        for arm in statement.arms.iter() {
+            if let Some(pattern) = &arm.pattern {
+                let found = self.type_of(pattern);
+                if !scrutinee.is_assignable_from(&found) && !found.is_assignable_from(&scrutinee) {
+                    self.diagnostics.borrow_mut().report_mismatched_types(
+                        &scrutinee,
+                        &found,
+                        pattern.span.clone(),
+                    );
+                }
+            }
+            self.visit_statement(&arm.body);
+        }
+    }
+
+    fn visit_for_loop_statement(&mut self, statement: &super::ASTForStatement) {
+        let start = self.type_of(&statement.range.0);
+        let end = self.type_of(&statement.range.1);
+        let loop_variable = match start.common_numeric(&end) {
+            Type::IntegerLiteral => Type::Integer,
+            ty => ty,
+        };
+        self.scopes.push(HashMap::new());
+        self.declare(&statement.loop_variable.literal, loop_variable);
+        self.visit_statement(&statement.body);
+        self.scopes.pop();
+    }
+
+    fn visit_while_loop_statement(&mut self, statement: &super::ASTWhileStatement) {
+        self.visit_expression(&statement.condition);
+        self.visit_statement(&statement.body);
+    }
+
+    fn visit_assert_statement(&mut self, statement: &super::ASTAssertStatement) {
+        self.visit_expression(&statement.condition);
+    }
+
+    fn visit_funtion_statement(&mut self, function: &super::ASTFunctionStatement) {
+        let signature = Self::signature(function);
+        let arguments = signature.arguments.clone();
+        self.functions
+            .insert(function.identifier.literal.clone(), signature);
+
+        let mut scope = HashMap::new();
+        for (arg, ty) in function.arguments.iter().zip(arguments) {
+            scope.insert(arg.identifier.literal.clone(), ty);
+        }
+        self.scopes.push(scope);
+        for arg in function.arguments.iter() {
+            let Some(default) = &arg.default else {
+                continue;
+            };
+            let found = self.type_of(default);
+            let expected = self.lookup(&arg.identifier.literal);
+            if !expected.is_assignable_from(&found) {
+                self.diagnostics.borrow_mut().report_mismatched_types(
+                    &expected,
+                    &found,
+                    arg.identifier.span.clone(),
+                );
+            }
+        }
+        self.visit_statement(&function.body);
+        self.scopes.pop();
+    }
+
+    fn visit_assignment_expression(&mut self, expr: &super::ASTAssignmentExpression) {
+        let found = self.type_of(&expr.expr);
+        let expected = self.lookup(&expr.identifier.literal);
+        if !expected.is_assignable_from(&found) {
+            self.diagnostics.borrow_mut().report_mismatched_types(
+                &expected,
+                &found,
+                expr.identifier.span.clone(),
+            );
+        }
+        self.result = expected;
+    }
+
+    fn visit_function_call_expression(&mut self, expr: &super::ASTFunctionCallExpression) {
+        let found: Vec<Type> = expr.arguments.iter().map(|arg| self.type_of(arg)).collect();
+
+        let Some(signature) = self.functions.get(expr.identifier()) else {
+            self.result = Type::Unknown;
+            return;
+        };
+        let return_type = signature.return_type;
+        let mismatches: Vec<(usize, Type, Type)> = signature
+            .arguments
+            .iter()
+            .zip(found)
+            .enumerate()
+            .filter(|(_, (expected, found))| !expected.is_assignable_from(found))
+            .map(|(i, (expected, found))| (i + 1, *expected, found))
+            .collect();
+
+        for (position, expected, found) in mismatches {
+            self.diagnostics.borrow_mut().report_argument_type_mismatch(
+                position,
+                &expected,
+                &found,
+                &expr.identifier,
+            );
+        }
+        self.result = return_type;
+    }
+
+    fn visit_variable_expression(&mut self, expr: &super::ASTVariableExpression) {
+        self.result = self.lookup(expr.identifier());
+    }
+
+    fn visit_unary_expression(&mut self, expr: &super::ASTUnaryExpression) {
+        let operand = self.type_of(&expr.expr);
+        self.result = match expr.operator.kind {
+            ASTUnaryOperatorKind::Plus | ASTUnaryOperatorKind::Minus => operand,
+            ASTUnaryOperatorKind::BitwiseNOT => {
+                self.check_bitwise_operand(&operand, &expr.operator.token.span);
+                operand
+            }
+            ASTUnaryOperatorKind::LogicNot => Type::Bool,
+        };
+    }
+
+    fn visit_binary_expression(&mut self, expr: &super::ASTBinaryExpression) {
+        let left = self.type_of(&expr.left);
+        let right = self.type_of(&expr.right);
+        let span = &expr.operator.token.span;
+
+        if left.is_mixed_with(&right) {
+            self.diagnostics.borrow_mut().report_mixed_numeric_operands(
+                &left,
+                &right,
+                &expr.operator.token,
+            );
+        }
+
+        self.result = match expr.operator.kind {
+            // strings are concatenated
+            ASTBinaryOperatorKind::Plus if left == Type::Str && right == Type::Str => Type::Str,
+            ASTBinaryOperatorKind::Plus
+            | ASTBinaryOperatorKind::Minus
+            | ASTBinaryOperatorKind::Multiply
+            | ASTBinaryOperatorKind::Divide
+            | ASTBinaryOperatorKind::Modulo
+            | ASTBinaryOperatorKind::Power => {
+                let result = left.common_numeric(&right);
+                let negative_exponent = Self::integer_constant(&expr.right).is_some_and(|i| i < 0);
+                if expr.operator.kind == ASTBinaryOperatorKind::Power
+                    && matches!(result, Type::Integer | Type::IntegerLiteral)
+                    && negative_exponent
+                {
+                    Type::Float
+                } else {
+                    result
+                }
+            }
+            ASTBinaryOperatorKind::BitwiseOR
+            | ASTBinaryOperatorKind::BitwiseAND
+            | ASTBinaryOperatorKind::BitwiseXOR => {
+                self.check_bitwise_operand(&left, span);
+                self.check_bitwise_operand(&right, span);
+                left.common_numeric(&right)
+            }
+            ASTBinaryOperatorKind::EqualTo
+            | ASTBinaryOperatorKind::NotEqualTo
+            | ASTBinaryOperatorKind::LogicAND
+            | ASTBinaryOperatorKind::LogicOR
+            | ASTBinaryOperatorKind::GreaterThan
+            | ASTBinaryOperatorKind::GreaterThanOrEqual
+            | ASTBinaryOperatorKind::LessThan
+            | ASTBinaryOperatorKind::LessThanOrEqual => Type::Bool,
+        };
+    }
+
+    fn visit_parenthesised_expression(&mut self, expr: &super::ASTParenthesizedExpression) {
+        self.result = self.type_of(&expr.expr);
+    }
+
+    // both branches need the same type, untyped integer literals adapt
+    fn visit_ternary_expression(&mut self, expr: &super::ASTTernaryExpression) {
+        self.type_of(&expr.condition);
+        let then_type = self.type_of(&expr.then_expr);
+        let else_type = self.type_of(&expr.else_expr);
+        self.result = if then_type.is_assignable_from(&else_type) {
+            then_type
+        } else if else_type.is_assignable_from(&then_type) {
+            else_type
+        } else {
+            self.diagnostics.borrow_mut().report_mismatched_types(
+                &then_type,
+                &else_type,
+                expr.else_expr.span.clone(),
+            );
+            Type::Unknown
+        };
+    }
+
+    fn visit_tuple_expression(&mut self, expr: &super::ASTTupleExpression) {
+        for element in expr.elements.iter() {
+            self.type_of(element);
+        }
+        self.result = Type::Tuple;
+    }
+
+    fn visit_binary_operator(&mut self, _op: &super::ASTBinaryOperator) {}
+
+    fn visit_error(&mut self, _span: &TextSpan) {
+        self.result = Type::Unknown;
+    }
+
+    fn visit_integer(&mut self, _integer: &i64) {
+        self.result = Type::IntegerLiteral;
+    }
+
+    fn visit_float(&mut self, _float: &f64) {
+        self.result = Type::Float;
+    }
+
+    fn visit_boolean(&mut self, _boolean: &bool) {
+        self.result = Type::Bool;
+    }
+
+    fn visit_char(&mut self, _char: &char) {
+        self.result = Type::Char;
+    }
+
+    fn visit_string(&mut self, _string: &super::lexer::Token) {
+        self.result = Type::Str;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::ast::parser::Parser;
+    use crate::diagnostics::DiagnosticsColletion;
+
+    use super::TypeChecker;
+
+    fn check(input: &str) -> Vec<String> {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut parser = Parser::from_input(input.to_string(), Rc::clone(&diagnostics));
+        let ast = parser.parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+
+        TypeChecker::new(Rc::clone(&diagnostics)).check(&ast);
+        let messages = diagnostics
+            .borrow()
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.clone())
+            .collect();
+        messages
+    }
+
+    #[test]
+    fn should_allow_integer_literals_in_float_expressions() {
+        assert!(check("let a: f64 = (7.2 - 10) / 2; let b: f64 = 1;").is_empty());
+    }
+
+    #[test]
+    fn should_type_string_concatenation_as_string() {
+        assert!(check("let a: str = \"foo\" + \"bar\";").is_empty());
+    }
+
+    #[test]
+    fn should_type_block_expression_by_its_value() {
+        assert!(check("let x: i32 = { let a: i32 = 2; a * a };").is_empty());
+        let messages = check("let x: i32 = { let a: i32 = 2; };");
+        assert_eq!(
+            messages,
+            vec!["Mismatched types: expected integer, found void".to_string()]
+        );
+    }
+
+    #[test]
+    fn should_type_negative_integer_exponent_as_float() {
+        assert_eq!(
+            check("let x: i32 = 2 ** -1;"),
+            vec!["Mismatched types: expected integer, found float".to_string()]
+        );
+        assert!(check("let a: i32 = 2; let x: f64 = a ** (-2) + 1;").is_empty());
+        assert!(check("let x: i32 = 2 ** 3; let y: i32 = 2 ** - -1;").is_empty());
+    }
+
+    #[test]
+    fn should_check_char_literals() {
+        assert!(check("let c: char = 'a';").is_empty());
+        assert_eq!(
+            check("let s: str = 'a';"),
+            vec!["Mismatched types: expected str, found char".to_string()]
+        );
+    }
+
+    #[test]
+    fn should_check_ternary_branches() {
+        assert!(check("let a: i32 = 1; let b: i32 = a > 0 ? a : 2;").is_empty());
+        assert_eq!(
+            check("let s: str = 1 ? \"a\" : 2;"),
+            vec!["Mismatched types: expected str, found integer".to_string()]
+        );
+    }
+
+    #[test]
+    fn should_report_match_pattern_of_other_type() {
+        assert!(check("let a: f64 = 1.5; match a { 1 => {} 2.5 => {} }").is_empty());
+        let messages = check("let s: str = \"a\"; match s { \"b\" => {} 1 => {} }");
+        assert_eq!(
+            messages,
+            vec!["Mismatched types: expected str, found integer".to_string()]
+        );
+    }
+
+    #[test]
+    fn should_report_mixed_integer_and_float_operands() {
+        let messages = check("let a: i32 = 1; let b: f64 = 2.5; a + b;");
+        assert_eq!(
+            messages,
+            vec!["Cannot mix integer and float operands in '+'".to_string()]
+        );
+    }
+
+    #[test]
+    fn should_report_float_initializer_for_integer() {
+        let messages = check("let a: i32 = 2.5;");
+        assert_eq!(
+            messages,
+            vec!["Mismatched types: expected integer, found float".to_string()]
+        );
+    }
+
+    #[test]
+    fn should_report_bitwise_operator_on_float() {
+        let messages = check("let a: f64 = 2.5 | 1;");
+        assert_eq!(
+            messages,
+            vec!["Bitwise operators can't be applied to floats".to_string()]
+        );
+    }
+
+    #[test]
+    fn should_report_call_site_type_mismatch() {
+        let messages = check(
+            "func f(x: i32, y: f64) -> f64 { return y; }
+             let a: f64 = 1.5;
+             f(a, a);",
+        );
+        assert_eq!(
+            messages,
+            vec!["Argument 1 of function 'f' expects integer, but found float".to_string()]
+        );
+    }
+
+    #[test]
+    fn should_accept_unannotated_parameters() {
+        assert!(check("func f(x, y: f64) -> f64 { return x + y; } f(2.5, 1);").is_empty());
+    }
+
+    #[test]
+    fn should_check_default_parameter_values() {
+        assert!(check("func f(x: f64 = 1, y = true) -> f64 { return x; } f();").is_empty());
+        assert_eq!(
+            check("func f(x: i32 = 1.5) -> i32 { return x; }"),
+            vec!["Mismatched types: expected integer, found float".to_string()]
+        );
+    }
+
+    #[test]
+    fn should_check_calls_before_declaration() {
+        let messages = check(
+            "let a: f64 = 1.5;
+             f(a);
+             func f(x: i32) -> i32 { return g(x); }
+             func g(x: i32) -> i32 { return f(x); }",
+        );
+        assert_eq!(
+            messages,
+            vec!["Argument 1 of function 'f' expects integer, but found float".to_string()]
+        );
+        assert_eq!(
+            check("let b: bool = h(); func h() -> i32 { return 1; }"),
+            vec!["Mismatched types: expected bool, found integer".to_string()]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt;
+
+use crate::ast::solver::{NumberFormat, Radix};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+    // Prints every token of the lexer
+    Tokens,
+    // Prints the tree and the highlighted source
+    Ast,
+    // Checks and evaluates the program, printing its result
+    Eval,
+    // Only reports the diagnostics of the program
+    Check,
+    Repl,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub mode: Mode,
+    pub path: String,
+    // print how long each compilation phase took
+    pub time: bool,
+    // how the result of the program is printed
+    pub number_format: NumberFormat,
+    // evaluate with exact fractions instead of floats
+    pub exact: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliError {
+    UnknownFlag(String),
+    ConflictingModes,
+    ConflictingRadixes,
+    UnexpectedArgument(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::UnknownFlag(flag) => write!(f, "Unknown flag '{}'", flag),
+            CliError::ConflictingModes => write!(f, "Only one mode can be selected"),
+            CliError::ConflictingRadixes => write!(f, "Only one radix can be selected"),
+            CliError::UnexpectedArgument(argument) => {
+                write!(f, "Unexpected argument '{}'", argument)
+            }
+        }
+    }
+}
+
+impl Options {
+    pub const USAGE: &'static str = "\
+Usage: lift-compiler [--tokens | --ast | --eval | --check] [--time]
+                    [--hex | --oct | --bin] [--sci] [--exact] [FILE]
+       lift-compiler --repl
+
+  --tokens print the tokens of FILE
+  --ast    print the syntax tree of FILE
+  --eval   check and evaluate FILE (default)
+  --check  only report the diagnostics of FILE
+  --repl   start an interactive session
+  --time   print the time spent in each phase
+  --hex    print integers in the result as hexadecimal
+  --oct    print integers in the result as octal
+  --bin    print integers in the result as binary
+  --sci    print floats in the result in scientific notation
+  --exact  evaluate with exact fractions instead of floats
+
+FILE defaults to math.txt";
+
+    const DEFAULT_PATH: &'static str = "math.txt";
+
+    // Parses the arguments without the program name
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut mode = None;
+        let mut path = None;
+        let mut time = false;
+        let mut radix = None;
+        let mut number_format = NumberFormat::default();
+        let mut exact = false;
+        for arg in args {
+            let selected = match arg.as_str() {
+                "--time" => {
+                    time = true;
+                    continue;
+                }
+                "--sci" => {
+                    number_format.scientific = true;
+                    continue;
+                }
+                "--exact" => {
+                    exact = true;
+                    continue;
+                }
+                "--hex" | "--oct" | "--bin" => {
+                    let selected = match arg.as_str() {
+                        "--hex" => Radix::Hexadecimal,
+                        "--oct" => Radix::Octal,
+                        _ => Radix::Binary,
+                    };
+                    if radix.replace(selected).is_some() {
+                        return Err(CliError::ConflictingRadixes);
+                    }
+                    continue;
+                }
+                "--tokens" => Mode::Tokens,
+                "--ast" => Mode::Ast,
+                "--eval" => Mode::Eval,
+                "--check" => Mode::Check,
+                "--repl" => Mode::Repl,
+                flag if flag.starts_with("--") => return Err(CliError::UnknownFlag(arg)),
+                _ => {
+                    if path.is_some() {
+                        return Err(CliError::UnexpectedArgument(arg));
+                    }
+                    path = Some(arg);
+                    continue;
+                }
+            };
+            if mode.replace(selected).is_some() {
+                return Err(CliError::ConflictingModes);
+            }
+        }
+
+        let mode = mode.unwrap_or(Mode::Eval);
+        if let (Mode::Repl, Some(path)) = (&mode, &path) {
+            return Err(CliError::UnexpectedArgument(path.clone()));
+        }
+        number_format.radix = radix.unwrap_or_default();
+        Ok(Self {
+            mode,
+            path: path.unwrap_or_else(|| Self::DEFAULT_PATH.to_string()),
+            time,
+            number_format,
+            exact,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CliError, Mode, Options};
+    use crate::ast::solver::{NumberFormat, Radix};
+
+    fn options(args: &[&str]) -> Result<Options, CliError> {
+        Options::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn should_default_to_evaluating_math_txt() {
+        assert_eq!(
+            options(&[]),
+            Ok(Options {
+                mode: Mode::Eval,
+                path: "math.txt".to_string(),
+                time: false,
+                number_format: NumberFormat::default(),
+                exact: false,
+            })
+        );
+    }
+
+    #[test]
+    fn should_accept_mode_before_and_after_path() {
+        let expected = Ok(Options {
+            mode: Mode::Check,
+            path: "main.lift".to_string(),
+            time: false,
+            number_format: NumberFormat::default(),
+            exact: false,
+        });
+        assert_eq!(options(&["--check", "main.lift"]), expected);
+        assert_eq!(options(&["main.lift", "--check"]), expected);
+    }
+
+    #[test]
+    fn should_accept_time_flag_with_any_mode() {
+        let options = options(&["--time", "--check", "main.lift"]).unwrap();
+        assert_eq!(options.mode, Mode::Check);
+        assert!(options.time);
+    }
+
+    #[test]
+    fn should_accept_result_format_flags() {
+        let selected = options(&["--sci", "main.lift", "--hex"]).unwrap();
+        assert_eq!(selected.mode, Mode::Eval);
+        assert_eq!(selected.number_format.radix, Radix::Hexadecimal);
+        assert!(selected.number_format.scientific);
+        assert!(!selected.exact);
+        assert!(options(&["--exact", "main.lift"]).unwrap().exact);
+        assert_eq!(
+            options(&["--bin", "--oct"]),
+            Err(CliError::ConflictingRadixes)
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_arguments() {
+        assert_eq!(
+            options(&["--token"]),
+            Err(CliError::UnknownFlag("--token".to_string()))
+        );
+        assert_eq!(
+            options(&["--ast", "--eval"]),
+            Err(CliError::ConflictingModes)
+        );
+        assert_eq!(
+            options(&["a.lift", "b.lift"]),
+            Err(CliError::UnexpectedArgument("b.lift".to_string()))
+        );
+        assert_eq!(
+            options(&["--repl", "a.lift"]),
+            Err(CliError::UnexpectedArgument("a.lift".to_string()))
+        );
+    }
+}
@@ -0,0 +1,622 @@
+pub mod vm;
+
+use std::collections::HashMap;
+
+use crate::ast::{
+    builtins::Builtin,
+    lexer::{TextSpan, TokenKind},
+    solver::Value,
+    ASTBinaryOperatorKind, ASTExpression, ASTExpressionKind, ASTFunctionStatement, ASTStatement,
+    ASTStatementKind, ASTUnaryOperatorKind, Ast,
+};
+
+// Instructions of a stack machine. Every frame has its own slots for the
+// variables of a function, the slots of the top-level frame are the globals.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Push(Value),
+    // the popped value becomes the result of the program
+    Pop,
+    Load(usize),
+    // the stored value stays on the stack, an assignment is an expression
+    Store(usize),
+    LoadGlobal(usize),
+    StoreGlobal(usize),
+    // pops that many values into a tuple
+    Tuple(usize),
+    Unary(ASTUnaryOperatorKind),
+    Binary(ASTBinaryOperatorKind),
+    Jump(usize),
+    // pops the condition, which becomes the result like in the solver
+    JumpIfFalse(usize),
+    Call {
+        function: usize,
+        arguments: usize,
+    },
+    CallBuiltin {
+        name: &'static str,
+        arguments: usize,
+    },
+    Return,
+    // a function body that ends without a return yields the last result
+    ReturnResult,
+    Assert,
+    Halt,
+}
+
+pub struct Function {
+    pub name: String,
+    // entry point for every accepted number of arguments, starting with the
+    // required ones. The code of the missing defaults runs before the body.
+    entries: Vec<usize>,
+    required_arguments: usize,
+    slots: usize,
+}
+
+impl Function {
+    pub fn entry(&self, arguments: usize) -> Option<usize> {
+        let provided_defaults = arguments.checked_sub(self.required_arguments)?;
+        self.entries.get(provided_defaults).copied()
+    }
+
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+}
+
+// The main code starts at address 0 and ends with Halt, the functions follow.
+// Every instruction has the span of the node it was generated for.
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    pub spans: Vec<TextSpan>,
+    pub functions: Vec<Function>,
+    pub globals: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CodegenError {
+    UndefinedVariable(TextSpan),
+    UndefinedFunction(TextSpan),
+    ArgumentCountMismatch(TextSpan),
+    Unsupported(TextSpan),
+}
+
+impl CodegenError {
+    // The spans are sliced from `source`, the compiled program
+    pub fn message(&self, source: &str) -> String {
+        match self {
+            CodegenError::UndefinedVariable(span) => {
+                format!("Undefined variable in '{}'", span.literal(source))
+            }
+            CodegenError::UndefinedFunction(span) => {
+                format!("Undefined function in '{}'", span.literal(source))
+            }
+            CodegenError::ArgumentCountMismatch(span) => {
+                format!("Wrong number of arguments in '{}'", span.literal(source))
+            }
+            CodegenError::Unsupported(span) => {
+                format!(
+                    "'{}' is not supported by the bytecode backend",
+                    span.literal(source)
+                )
+            }
+        }
+    }
+}
+
+pub fn compile(ast: &Ast) -> Result<Program, CodegenError> {
+    Compiler::new().compile(ast)
+}
+
+// A loop being compiled: `continue` jumps to its condition, the jumps of
+// `break` are patched once its end is known
+struct Loop {
+    start: usize,
+    breaks: Vec<usize>,
+}
+
+// Names are resolved while compiling: the variables of a function are its
+// arguments and declarations, everything else has to be a global. Loops over
+// ranges are not lowered yet.
+struct Compiler<'a> {
+    instructions: Vec<Instruction>,
+    spans: Vec<TextSpan>,
+    functions: Vec<&'a ASTFunctionStatement>,
+    function_indices: HashMap<&'a str, usize>,
+    globals: HashMap<String, usize>,
+    // scopes of the frame being compiled
+    scopes: Vec<HashMap<String, usize>>,
+    slots: usize,
+    in_function: bool,
+    loops: Vec<Loop>,
+}
+
+impl<'a> Compiler<'a> {
+    fn new() -> Self {
+        Self {
+            instructions: Vec::new(),
+            spans: Vec::new(),
+            functions: Vec::new(),
+            function_indices: HashMap::new(),
+            globals: HashMap::new(),
+            scopes: Vec::new(),
+            slots: 0,
+            in_function: false,
+            loops: Vec::new(),
+        }
+    }
+
+    fn compile(mut self, ast: &'a Ast) -> Result<Program, CodegenError> {
+        for statement in ast.statements() {
+            match statement.kind() {
+                ASTStatementKind::Let(statement) => self.declare_global(statement.identifier()),
+                ASTStatementKind::Var(statement) => self.declare_global(statement.identifier()),
+                _ => (),
+            }
+            self.collect_functions(statement)?;
+        }
+
+        self.scopes = vec![self.globals.clone()];
+        self.slots = self.globals.len();
+        for statement in ast.statements() {
+            self.statement(statement)?;
+        }
+        self.emit(Instruction::Halt, &TextSpan::new(0, 0));
+        let globals = self.slots;
+
+        self.in_function = true;
+        let mut functions = Vec::new();
+        for function in self.functions.clone() {
+            functions.push(self.function(function)?);
+        }
+
+        Ok(Program {
+            instructions: self.instructions,
+            spans: self.spans,
+            functions,
+            globals,
+        })
+    }
+
+    fn declare_global(&mut self, identifier: &str) {
+        let slot = self.globals.len();
+        self.globals.entry(identifier.to_string()).or_insert(slot);
+    }
+
+    // functions are callable before their declaration, in any block
+    fn collect_functions(&mut self, statement: &'a ASTStatement) -> Result<(), CodegenError> {
+        match statement.kind() {
+            ASTStatementKind::FuncDecl(function) => {
+                if self
+                    .function_indices
+                    .insert(function.identifier(), self.functions.len())
+                    .is_some()
+                {
+                    return Err(CodegenError::Unsupported(statement.span().clone()));
+                }
+                self.functions.push(function);
+                self.collect_functions(function.body())
+            }
+            ASTStatementKind::Compound(statement) => {
+                for statement in statement.statements() {
+                    self.collect_functions(statement)?;
+                }
+                Ok(())
+            }
+            ASTStatementKind::If(statement) => {
+                self.collect_functions(statement.then_branch())?;
+                match statement.else_branch() {
+                    Some(else_branch) => self.collect_functions(else_branch),
+                    None => Ok(()),
+                }
+            }
+            ASTStatementKind::While(statement) => self.collect_functions(statement.body()),
+            ASTStatementKind::For(statement) => self.collect_functions(statement.body()),
+            _ => Ok(()),
+        }
+    }
+
+    fn function(&mut self, function: &ASTFunctionStatement) -> Result<Function, CodegenError> {
+        let arguments = function.arguments();
+        self.scopes = vec![HashMap::new()];
+        self.slots = 0;
+        for argument in arguments {
+            self.declare(argument.identifier());
+        }
+
+        let required_arguments = arguments
+            .iter()
+            .take_while(|argument| argument.default().is_none())
+            .count();
+        let mut entries = Vec::new();
+        for (slot, argument) in arguments.iter().enumerate().skip(required_arguments) {
+            entries.push(self.instructions.len());
+            if let Some(default) = argument.default() {
+                self.expression(default)?;
+                self.emit(Instruction::Store(slot), default.span());
+                self.emit(Instruction::Pop, default.span());
+            }
+        }
+        entries.push(self.instructions.len());
+
+        self.statement(function.body())?;
+        self.emit(Instruction::ReturnResult, function.body().span());
+        Ok(Function {
+            name: function.identifier().to_string(),
+            entries,
+            required_arguments,
+            slots: self.slots,
+        })
+    }
+
+    fn emit(&mut self, instruction: Instruction, span: &TextSpan) -> usize {
+        self.instructions.push(instruction);
+        self.spans.push(span.clone());
+        self.instructions.len() - 1
+    }
+
+    // sets the target of an emitted jump
+    fn patch(&mut self, jump: usize, target: usize) {
+        match &mut self.instructions[jump] {
+            Instruction::Jump(address) | Instruction::JumpIfFalse(address) => *address = target,
+            _ => unreachable!("not a jump"),
+        }
+    }
+
+    fn declare(&mut self, identifier: &str) -> usize {
+        let slots = &mut self.slots;
+        *self
+            .scopes
+            .last_mut()
+            .unwrap()
+            .entry(identifier.to_string())
+            .or_insert_with(|| {
+                *slots += 1;
+                *slots - 1
+            })
+    }
+
+    fn load(&mut self, identifier: &str, span: &TextSpan) -> Result<(), CodegenError> {
+        let instruction = match self.resolve(identifier, span)? {
+            (slot, false) => Instruction::Load(slot),
+            (slot, true) => Instruction::LoadGlobal(slot),
+        };
+        self.emit(instruction, span);
+        Ok(())
+    }
+
+    fn store(&mut self, identifier: &str, span: &TextSpan) -> Result<(), CodegenError> {
+        let instruction = match self.resolve(identifier, span)? {
+            (slot, false) => Instruction::Store(slot),
+            (slot, true) => Instruction::StoreGlobal(slot),
+        };
+        self.emit(instruction, span);
+        Ok(())
+    }
+
+    // returns the slot and whether it is a global
+    fn resolve(&self, identifier: &str, span: &TextSpan) -> Result<(usize, bool), CodegenError> {
+        if let Some(slot) = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(identifier))
+        {
+            return Ok((*slot, false));
+        }
+        match self.globals.get(identifier) {
+            Some(slot) if self.in_function => Ok((*slot, true)),
+            _ => Err(CodegenError::UndefinedVariable(span.clone())),
+        }
+    }
+
+    fn statement(&mut self, statement: &ASTStatement) -> Result<(), CodegenError> {
+        let span = statement.span();
+        match statement.kind() {
+            ASTStatementKind::Expr(expr) => {
+                self.expression(expr)?;
+                self.emit(Instruction::Pop, span);
+            }
+            ASTStatementKind::Let(statement) => {
+                self.expression(statement.initializer())?;
+                let slot = self.declare(statement.identifier());
+                self.emit(Instruction::Store(slot), span);
+                self.emit(Instruction::Pop, span);
+            }
+            ASTStatementKind::Var(statement) => {
+                self.expression(statement.initializer())?;
+                let slot = self.declare(statement.identifier());
+                self.emit(Instruction::Store(slot), span);
+                self.emit(Instruction::Pop, span);
+            }
+            ASTStatementKind::Return(statement) => {
+                self.expression(statement.expression())?;
+                self.emit(Instruction::Return, span);
+            }
+            ASTStatementKind::Compound(statement) => {
+                self.scopes.push(HashMap::new());
+                for statement in statement.statements() {
+                    self.statement(statement)?;
+                }
+                self.scopes.pop();
+            }
+            // compiled separately
+            ASTStatementKind::FuncDecl(_) => (),
+            ASTStatementKind::If(statement) => {
+                self.expression(statement.condition())?;
+                let to_else = self.emit(Instruction::JumpIfFalse(0), span);
+                self.statement(statement.then_branch())?;
+                match statement.else_branch() {
+                    Some(else_branch) => {
+                        let to_end = self.emit(Instruction::Jump(0), span);
+                        self.patch(to_else, self.instructions.len());
+                        self.statement(else_branch)?;
+                        self.patch(to_end, self.instructions.len());
+                    }
+                    None => self.patch(to_else, self.instructions.len()),
+                }
+            }
+            ASTStatementKind::While(statement) => {
+                let start = self.instructions.len();
+                self.expression(statement.condition())?;
+                let to_end = self.emit(Instruction::JumpIfFalse(0), span);
+                self.loops.push(Loop {
+                    start,
+                    breaks: Vec::new(),
+                });
+                self.statement(statement.body())?;
+                let breaks = self.loops.pop().unwrap().breaks;
+                self.emit(Instruction::Jump(start), span);
+                let end = self.instructions.len();
+                self.patch(to_end, end);
+                for jump in breaks {
+                    self.patch(jump, end);
+                }
+            }
+            // the checker reports them outside of a loop
+            ASTStatementKind::Break(_) => {
+                let jump = self.emit(Instruction::Jump(0), span);
+                match self.loops.last_mut() {
+                    Some(innermost) => innermost.breaks.push(jump),
+                    None => return Err(CodegenError::Unsupported(span.clone())),
+                }
+            }
+            ASTStatementKind::Continue(_) => {
+                let Some(innermost) = self.loops.last() else {
+                    return Err(CodegenError::Unsupported(span.clone()));
+                };
+                self.emit(Instruction::Jump(innermost.start), span);
+            }
+            ASTStatementKind::Assert(statement) => {
+                self.expression(statement.condition())?;
+                self.emit(Instruction::Assert, statement.condition().span());
+            }
+            ASTStatementKind::For(_) | ASTStatementKind::Match(_) => {
+                return Err(CodegenError::Unsupported(span.clone()))
+            }
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self, expr: &ASTExpression) -> Result<(), CodegenError> {
+        let span = expr.span();
+        match expr.kind() {
+            ASTExpressionKind::IntegerLiteral(i) => {
+                self.emit(Instruction::Push(Value::Int(*i)), span);
+            }
+            ASTExpressionKind::FloatingLiteral(f) => {
+                self.emit(Instruction::Push(Value::Float(*f)), span);
+            }
+            ASTExpressionKind::BooleanLiteral(b) => {
+                self.emit(Instruction::Push(Value::from(*b)), span);
+            }
+            ASTExpressionKind::CharLiteral(c) => {
+                self.emit(Instruction::Push(Value::Int(*c as i64)), span);
+            }
+            ASTExpressionKind::Variable(variable) => self.load(variable.identifier(), span)?,
+            ASTExpressionKind::Assignment(assignment) => {
+                self.expression(assignment.expression())?;
+                self.store(assignment.identifier(), span)?;
+            }
+            ASTExpressionKind::Unary(unary) => {
+                self.expression(unary.operand())?;
+                self.emit(Instruction::Unary(unary.operator().clone()), span);
+            }
+            ASTExpressionKind::Binary(binary) => match binary.operator() {
+                // `a && b` is `a ? !!b : false` and `a || b` is `a ? true : !!b`
+                ASTBinaryOperatorKind::LogicAND | ASTBinaryOperatorKind::LogicOR => {
+                    let is_and = *binary.operator() == ASTBinaryOperatorKind::LogicAND;
+                    self.expression(binary.left())?;
+                    let to_right = self.emit(Instruction::JumpIfFalse(0), span);
+                    if is_and {
+                        self.expression(binary.right())?;
+                        self.emit(Instruction::Unary(ASTUnaryOperatorKind::LogicNot), span);
+                        self.emit(Instruction::Unary(ASTUnaryOperatorKind::LogicNot), span);
+                    } else {
+                        self.emit(Instruction::Push(Value::from(true)), span);
+                    }
+                    let to_end = self.emit(Instruction::Jump(0), span);
+                    self.patch(to_right, self.instructions.len());
+                    if is_and {
+                        self.emit(Instruction::Push(Value::from(false)), span);
+                    } else {
+                        self.expression(binary.right())?;
+                        self.emit(Instruction::Unary(ASTUnaryOperatorKind::LogicNot), span);
+                        self.emit(Instruction::Unary(ASTUnaryOperatorKind::LogicNot), span);
+                    }
+                    self.patch(to_end, self.instructions.len());
+                }
+                operator => {
+                    self.expression(binary.left())?;
+                    self.expression(binary.right())?;
+                    self.emit(Instruction::Binary(operator.clone()), span);
+                }
+            },
+            ASTExpressionKind::Parenthesized(parenthesized) => {
+                self.expression(parenthesized.expression())?
+            }
+            ASTExpressionKind::Ternary(ternary) => {
+                self.expression(ternary.condition())?;
+                let to_else = self.emit(Instruction::JumpIfFalse(0), span);
+                self.expression(ternary.then_expression())?;
+                let to_end = self.emit(Instruction::Jump(0), span);
+                self.patch(to_else, self.instructions.len());
+                self.expression(ternary.else_expression())?;
+                self.patch(to_end, self.instructions.len());
+            }
+            ASTExpressionKind::Tuple(tuple) => {
+                for element in tuple.elements() {
+                    self.expression(element)?;
+                }
+                self.emit(Instruction::Tuple(tuple.elements().len()), span);
+            }
+            ASTExpressionKind::FunctionCall(call) => {
+                for argument in call.arguments() {
+                    self.expression(argument)?;
+                }
+                let arguments = call.arguments().len();
+                // builtins are resolved before user defined functions
+                if let Some(builtin) = Builtin::<f64>::lookup(call.identifier()) {
+                    if builtin.arity != arguments {
+                        return Err(CodegenError::ArgumentCountMismatch(span.clone()));
+                    }
+                    self.emit(
+                        Instruction::CallBuiltin {
+                            name: builtin.name,
+                            arguments,
+                        },
+                        span,
+                    );
+                    return Ok(());
+                }
+                let Some(function) = self.function_indices.get(call.identifier()).copied() else {
+                    return Err(CodegenError::UndefinedFunction(span.clone()));
+                };
+                let declaration = self.functions[function];
+                let required_arguments = declaration
+                    .arguments()
+                    .iter()
+                    .take_while(|argument| argument.default().is_none())
+                    .count();
+                if arguments < required_arguments || arguments > declaration.arguments().len() {
+                    return Err(CodegenError::ArgumentCountMismatch(span.clone()));
+                }
+                self.emit(
+                    Instruction::Call {
+                        function,
+                        arguments,
+                    },
+                    span,
+                );
+            }
+            ASTExpressionKind::StringLiteral(token) => {
+                if let TokenKind::StringLiteral(string) = &token.kind {
+                    self.emit(Instruction::Push(Value::Str(string.clone())), span);
+                }
+            }
+            // functions are only collected from statements, a block without
+            // a value has no instruction to leave its result on the stack
+            ASTExpressionKind::Block(block) => {
+                let Some(value) = block.value() else {
+                    return Err(CodegenError::Unsupported(span.clone()));
+                };
+                let statements = &block.statements()[..block.statements().len() - 1];
+                if statements
+                    .iter()
+                    .any(|statement| matches!(statement.kind(), ASTStatementKind::FuncDecl(_)))
+                {
+                    return Err(CodegenError::Unsupported(span.clone()));
+                }
+                // leaving a loop from inside an expression would leave its
+                // operands on the stack
+                let loops = std::mem::take(&mut self.loops);
+                self.scopes.push(HashMap::new());
+                for statement in statements {
+                    self.statement(statement)?;
+                }
+                self.expression(value)?;
+                self.scopes.pop();
+                self.loops = loops;
+            }
+            ASTExpressionKind::Error(_) => return Err(CodegenError::Unsupported(span.clone())),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compile, CodegenError, Instruction};
+    use crate::ast::{parser::Parser, solver::Value, ASTBinaryOperatorKind, Ast};
+    use crate::diagnostics::DiagnosticsColletion;
+    use std::{cell::RefCell, rc::Rc};
+
+    fn parse(input: &str) -> Ast {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        ast
+    }
+
+    #[test]
+    fn should_lower_statements_to_instructions() {
+        let input = "var a: i32 = 1; while a < 3 { a = a + 1; }";
+        let program = compile(&parse(input)).unwrap();
+        assert_eq!(
+            program.instructions,
+            vec![
+                Instruction::Push(Value::Int(1)),
+                Instruction::Store(0),
+                Instruction::Pop,
+                Instruction::Load(0),
+                Instruction::Push(Value::Int(3)),
+                Instruction::Binary(ASTBinaryOperatorKind::LessThan),
+                Instruction::JumpIfFalse(13),
+                Instruction::Load(0),
+                Instruction::Push(Value::Int(1)),
+                Instruction::Binary(ASTBinaryOperatorKind::Plus),
+                Instruction::Store(0),
+                Instruction::Pop,
+                Instruction::Jump(3),
+                Instruction::Halt,
+            ]
+        );
+        assert_eq!(program.globals, 1);
+        assert_eq!(program.spans[5].literal(input), "a < 3");
+    }
+
+    #[test]
+    fn should_enter_function_after_provided_arguments() {
+        let program = compile(&parse("func f(a, b = 1, c = 2) { a + b + c; }")).unwrap();
+        let function = &program.functions[0];
+        assert_eq!(function.name, "f");
+        assert_eq!(function.slots(), 3);
+        assert!(function.entry(0).is_none());
+        assert!(function.entry(4).is_none());
+        let body = function.entry(3).unwrap();
+        // every omitted default is pushed, stored and popped
+        assert_eq!(function.entry(1), Some(body - 6));
+        assert_eq!(function.entry(2), Some(body - 3));
+        assert_eq!(program.instructions[body - 2], Instruction::Store(2));
+    }
+
+    #[test]
+    fn should_reject_what_the_backend_does_not_lower() {
+        assert!(matches!(
+            compile(&parse("for i in 0..3 { i; }")),
+            Err(CodegenError::Unsupported(_))
+        ));
+        // a function only sees its own variables and the globals
+        let input = "func outer(x) { func inner() { return x; } return inner(); }";
+        assert!(matches!(
+            compile(&parse(input)),
+            Err(CodegenError::UndefinedVariable(span)) if span.literal(input) == "x"
+        ));
+        // the operands of the expression would stay on the stack
+        let input = "while true { 1 + { break; 2 }; }";
+        assert!(matches!(
+            compile(&parse(input)),
+            Err(CodegenError::Unsupported(span)) if span.literal(input) == "break;"
+        ));
+    }
+}
@@ -0,0 +1,409 @@
+use crate::ast::{
+    builtins::Builtin,
+    lexer::TextSpan,
+    solver::{OutputCell, SolverError, Value},
+};
+use std::{cell::RefCell, rc::Rc};
+
+use super::{Instruction, Program};
+
+struct Frame {
+    return_address: usize,
+    // a return inside of a block expression leaves operands on the stack
+    stack_base: usize,
+    slots: Vec<Option<Value>>,
+}
+
+// Runs a compiled program with the semantics of the solver: the result is
+// the last value an expression statement, declaration, condition or return
+// produced. Evaluation stops at the first error.
+pub struct Vm {
+    stack: Vec<Value>,
+    // positions of the placeholders pushed for calls without a value, like
+    // in the solver they can only be discarded
+    missing_values: Vec<usize>,
+    frames: Vec<Frame>,
+    result: Option<Value>,
+    max_call_depth: usize,
+    output: OutputCell,
+}
+
+impl Vm {
+    const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            missing_values: Vec::new(),
+            frames: Vec::new(),
+            result: None,
+            max_call_depth: Self::DEFAULT_MAX_CALL_DEPTH,
+            output: Rc::new(RefCell::new(std::io::stdout())),
+        }
+    }
+
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    // print writes to stdout unless another output is set
+    pub fn set_output(&mut self, output: OutputCell) {
+        self.output = output;
+    }
+
+    pub fn run(&mut self, program: &Program) -> Result<Option<Value>, SolverError> {
+        self.stack.clear();
+        self.missing_values.clear();
+        self.result = None;
+        self.frames = vec![Frame {
+            return_address: 0,
+            stack_base: 0,
+            slots: vec![None; program.globals],
+        }];
+
+        let mut address = 0;
+        loop {
+            let span = &program.spans[address];
+            let instruction = &program.instructions[address];
+            address += 1;
+            match instruction {
+                Instruction::Push(value) => self.stack.push(value.clone()),
+                // discarding a missing value keeps the result
+                Instruction::Pop => {
+                    if self.missing_values.last() == Some(&(self.stack.len() - 1)) {
+                        self.missing_values.pop();
+                        self.stack.pop();
+                    } else {
+                        self.result = Some(self.pop());
+                    }
+                }
+                Instruction::Load(slot) => {
+                    let value = self.frames.last().unwrap().slots[*slot].clone();
+                    self.stack
+                        .push(value.ok_or(SolverError::UndefinedVariable(span.clone()))?);
+                }
+                Instruction::Store(slot) => {
+                    self.check_values(1, span)?;
+                    let value = self.stack.last().unwrap().clone();
+                    self.frames.last_mut().unwrap().slots[*slot] = Some(value);
+                }
+                Instruction::LoadGlobal(slot) => {
+                    let value = self.frames[0].slots[*slot].clone();
+                    self.stack
+                        .push(value.ok_or(SolverError::UndefinedVariable(span.clone()))?);
+                }
+                Instruction::StoreGlobal(slot) => {
+                    self.check_values(1, span)?;
+                    let value = self.stack.last().unwrap().clone();
+                    self.frames[0].slots[*slot] = Some(value);
+                }
+                Instruction::Tuple(elements) => {
+                    self.check_values(*elements, span)?;
+                    let elements = self.stack.split_off(self.stack.len() - elements);
+                    self.stack.push(Value::Tuple(elements));
+                }
+                Instruction::Unary(operator) => {
+                    self.check_values(1, span)?;
+                    let value = self.pop();
+                    let value = value
+                        .unary(operator)
+                        .map_err(|error| error.at(span.clone()))?;
+                    self.stack.push(value);
+                }
+                Instruction::Binary(operator) => {
+                    self.check_values(2, span)?;
+                    let right = self.pop();
+                    let left = self.pop();
+                    let value = left
+                        .binary(operator, right)
+                        .map_err(|error| error.at(span.clone()))?;
+                    self.stack.push(value);
+                }
+                Instruction::Jump(target) => address = *target,
+                Instruction::JumpIfFalse(target) => {
+                    self.check_values(1, span)?;
+                    let condition = self.pop();
+                    if !condition.is_truthy() {
+                        address = *target;
+                    }
+                    self.result = Some(condition);
+                }
+                Instruction::Call {
+                    function,
+                    arguments,
+                } => {
+                    if self.frames.len() > self.max_call_depth {
+                        return Err(SolverError::RecursionLimitExceeded(span.clone()));
+                    }
+                    let function = &program.functions[*function];
+                    let entry = function.entry(*arguments).ok_or_else(|| {
+                        SolverError::ArgumentCountMismatch {
+                            function: span.clone(),
+                            expected: function.required_arguments,
+                            found: *arguments,
+                        }
+                    })?;
+                    self.check_values(*arguments, span)?;
+                    // like in the solver the value the caller evaluated last,
+                    // e.g. the last argument or the left operand, is the
+                    // result until the body produces one
+                    let base = self.frames.last().unwrap().stack_base;
+                    if self.stack.len() > base
+                        && self.missing_values.last() != Some(&(self.stack.len() - 1))
+                    {
+                        self.result = self.stack.last().cloned();
+                    }
+                    let mut slots = vec![None; function.slots()];
+                    let values = self.stack.split_off(self.stack.len() - arguments);
+                    for (slot, value) in slots.iter_mut().zip(values) {
+                        *slot = Some(value);
+                    }
+                    self.frames.push(Frame {
+                        return_address: address,
+                        stack_base: self.stack.len(),
+                        slots,
+                    });
+                    address = entry;
+                }
+                Instruction::CallBuiltin { name, arguments } => {
+                    let Some(builtin) = Builtin::lookup(name) else {
+                        return Err(SolverError::UndefinedFunction(span.clone()));
+                    };
+                    self.check_values(*arguments, span)?;
+                    let values = self.stack.split_off(self.stack.len() - arguments);
+                    let value = builtin
+                        .call(&values, &mut *self.output.borrow_mut())
+                        .ok_or(SolverError::DomainError(span.clone()))?;
+                    self.stack.push(value);
+                }
+                Instruction::Return | Instruction::ReturnResult => {
+                    let value = match instruction {
+                        Instruction::Return => {
+                            self.check_values(1, span)?;
+                            Some(self.pop())
+                        }
+                        _ => self.result.clone(),
+                    };
+                    // a return outside of a function ends the program
+                    if self.frames.len() == 1 {
+                        return Ok(value);
+                    }
+                    let frame = self.frames.pop().unwrap();
+                    address = frame.return_address;
+                    self.stack.truncate(frame.stack_base);
+                    self.missing_values
+                        .retain(|missing| *missing < frame.stack_base);
+                    match value {
+                        Some(value) => {
+                            self.result = Some(value.clone());
+                            self.stack.push(value);
+                        }
+                        // a body that never produced a value
+                        None => {
+                            self.missing_values.push(self.stack.len());
+                            self.stack.push(Value::Int(0));
+                        }
+                    }
+                }
+                Instruction::Assert => {
+                    self.check_values(1, span)?;
+                    let condition = self.pop();
+                    if !condition.is_truthy() {
+                        return Err(SolverError::AssertionFailed(span.clone()));
+                    }
+                    self.result = Some(condition);
+                }
+                Instruction::Halt => return Ok(self.result.take()),
+            }
+        }
+    }
+
+    // Fails if one of the values an instruction consumes is missing
+    fn check_values(&self, count: usize, span: &TextSpan) -> Result<(), SolverError> {
+        match self.missing_values.last() {
+            Some(missing) if *missing + count >= self.stack.len() => {
+                Err(SolverError::MissingValue(span.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // the compiler only pops what an instruction before pushed
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Vm;
+    use crate::ast::{
+        parser::Parser,
+        solver::{ASTSolver, SolverError, Value},
+        Ast,
+    };
+    use crate::codegen::compile;
+    use crate::diagnostics::DiagnosticsColletion;
+    use std::{cell::RefCell, rc::Rc};
+
+    fn parse(input: &str) -> Ast {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let ast = Parser::from_input(input.to_string(), Rc::clone(&diagnostics)).parse();
+        assert!(diagnostics.borrow().diagnostics.is_empty());
+        ast
+    }
+
+    fn run(input: &str) -> Result<Option<Value>, SolverError> {
+        let program = compile(&parse(input)).unwrap();
+        Vm::new().run(&program)
+    }
+
+    // the vm has to produce the same result as the solver
+    fn assert_same_as_solver(input: &str) -> Result<Option<Value>, SolverError> {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let expected = ASTSolver::new(diagnostics).evaluate(&parse(input));
+        let result = run(input);
+        match (&result, &expected) {
+            (Err(error), Err(expected)) => assert_eq!(
+                std::mem::discriminant(error),
+                std::mem::discriminant(expected),
+                "{input}"
+            ),
+            _ => assert_eq!(result, expected, "{input}"),
+        }
+        result
+    }
+
+    #[test]
+    fn should_evaluate_expressions_like_solver() {
+        for input in [
+            "2 + 3 * 4 ** 2 / 7 % 5;",
+            "7 / 2.0 - -3;",
+            "2 ** 3 ** 2;",
+            "(1 < 2) + (2 <= 2) * 2 + (3 != 3) * 4;",
+            "~5 & 12 | 3 ^ 1;",
+            "!0 + !!7;",
+            "var a: i32 = 1; a += 2; a * (a = 4);",
+            "var n: i32 = 0; 0 && (n = 1); 1 || (n = 2); n;",
+            "2 && 3.5;",
+            "0 || 0;",
+            "sqrt(16) + max(2, 7) + floor(2.5);",
+            "var s: str = \"foo\"; s = s + \"bar\"; s + s;",
+            "\"a\" == \"a\";",
+            "var n: i32 = 0; (1 ? 10 : (n = 5)) + (0 ? (n = 7) : n + 20);",
+        ] {
+            assert!(assert_same_as_solver(input).is_ok(), "{input}");
+        }
+    }
+
+    #[test]
+    fn should_run_control_flow_like_solver() {
+        for input in [
+            "var i: i32 = 0; var sum: i32 = 0; while i < 10 { i += 1; if i % 2 == 0 { sum += i; } else { sum -= 1; } } sum;",
+            "let a: i32 = 1; if a > 2 { a; }",
+            "let a: i32 = 5; { let a: i32 = 2; a + 1; } a;",
+            "var a: i32 = 5; { a = 7; } a;",
+            "let a: i32 = 1; return a + 1; a;",
+            "assert(1 == 1); 4;",
+            "let x: i32 = { let a: i32 = 2; a * a }; x + { x };",
+            "var i: i32 = 0; var sum: i32 = 0; while i < 10 { i += 1; if i % 2 == 0 { continue; } if i > 7 { break; } sum += i; } sum * 100 + i;",
+            "var i: i32 = 0; while true { i += 1; while 1 { break; } if i == 3 { break; } } i;",
+        ] {
+            assert!(assert_same_as_solver(input).is_ok(), "{input}");
+        }
+    }
+
+    #[test]
+    fn should_call_functions_like_solver() {
+        for input in [
+            "func fib(n) -> i32 { if n < 2 { return n; } return fib(n - 1) + fib(n - 2); } fib(15);",
+            "func f(x) { x + 1; } f(2);",
+            "func f(a, b = a * 2, c = b + 1) -> i32 { return a + b + c; } f(1) * 100 + f(1, 1) * 10 + f(1, 1, 1);",
+            "let g: i32 = 10; func f(x) -> i32 { return x + g; } f(1);",
+            "var g: i32 = 0; func set(x) { g = x; } set(3); g;",
+            "a(4); func a(n) -> i32 { if n == 0 { return 0; } return b(n - 1) + 1; } func b(n) -> i32 { if n == 0 { return 0; } return a(n - 1); }",
+            "func f(n) -> i32 { while 1 { if n > 3 { return n; } n = n + 1; } return 0; } f(0) + 1;",
+            "func f(n) -> i32 { let x: i32 = 1 + { if n > 0 { return n; } 0 }; return x; } f(5) + f(0);",
+            "func f(a) { return (a, (a * 2,)); } f(3);",
+            "func g() { } g(); 5;",
+            "func g() { } 1 + g();",
+            "func g() { } 3; g();",
+            "func g() { } g();",
+            "func g() { } func h() { g(); } h(); 4;",
+        ] {
+            assert!(assert_same_as_solver(input).is_ok(), "{input}");
+        }
+    }
+
+    #[test]
+    fn should_report_errors_like_solver() {
+        assert!(matches!(
+            assert_same_as_solver("let zero: i32 = 0; 1 / zero;"),
+            Err(SolverError::DivisionByZero(_))
+        ));
+        let input = "assert(1 == 2); 3;";
+        assert!(matches!(
+            assert_same_as_solver(input),
+            Err(SolverError::AssertionFailed(span)) if span.literal(input) == "(1 == 2)"
+        ));
+        assert!(matches!(
+            assert_same_as_solver("\"a\" - 1;"),
+            Err(SolverError::StringInArithmetic(_))
+        ));
+        assert!(matches!(
+            assert_same_as_solver("let big: i64 = 4611686018427387904; big * 2;"),
+            Err(SolverError::IntegerOverflow(_))
+        ));
+        assert!(matches!(
+            assert_same_as_solver("sqrt(-1);"),
+            Err(SolverError::DomainError(_))
+        ));
+        for input in [
+            "func g() { } g() + 1;",
+            "func g() { } let x: i32 = g();",
+            "func g() { } (g(), 2);",
+            "func g() { } func h() { g(); } h() * 2;",
+        ] {
+            assert!(
+                matches!(
+                    assert_same_as_solver(input),
+                    Err(SolverError::MissingValue(_))
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn should_run_empty_program() {
+        assert_eq!(assert_same_as_solver(""), Ok(None));
+        assert_eq!(
+            assert_same_as_solver("func f() -> i32 { return 1; }"),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn should_print_to_output() {
+        let program = compile(&parse(
+            "var i: i32 = 0; while i < 3 { print(i * i); i += 1; }",
+        ))
+        .unwrap();
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let mut vm = Vm::new();
+        vm.set_output(output.clone());
+        assert_eq!(vm.run(&program), Ok(Some(Value::Int(0))));
+        assert_eq!(String::from_utf8_lossy(&output.borrow()), "0\n1\n4\n");
+    }
+
+    #[test]
+    fn should_stop_at_recursion_limit() {
+        let program = compile(&parse("func f(n) -> i32 { return f(n + 1); } f(0);")).unwrap();
+        let mut vm = Vm::new();
+        vm.set_max_call_depth(50);
+        assert!(matches!(
+            vm.run(&program),
+            Err(SolverError::RecursionLimitExceeded(_))
+        ));
+    }
+}
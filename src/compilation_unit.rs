@@ -1,77 +1,276 @@
 use crate::{ast, diagnostics};
 use ast::lexer::Token;
+use ast::numeric::{Numeric, Rational};
 use ast::printer::ASTHiglightPrinter;
-use ast::solver::ASTSolver;
+use ast::solver::{ASTSolver, NumberFormat};
 use ast::symbol_checker;
+use ast::type_checker::TypeChecker;
 use diagnostics::printer::DiagnosticsPrinter;
-use diagnostics::{DiagnosticsColletion, DiagnosticsColletionCell};
+use diagnostics::{Diagnostic, DiagnosticsColletion, DiagnosticsColletionCell};
+use std::fmt;
+use std::time::{Duration, Instant};
 use std::{cell::RefCell, rc::Rc};
 
 use crate::source_text::SourceText;
 
+// Time spent in each phase, a phase that didn't run is None
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompileStats {
+    pub lexing: Option<Duration>,
+    pub parsing: Option<Duration>,
+    pub symbol_checking: Option<Duration>,
+    pub type_checking: Option<Duration>,
+    pub solving: Option<Duration>,
+}
+
+impl fmt::Display for CompileStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let phases = [
+            ("lexing", self.lexing),
+            ("parsing", self.parsing),
+            ("symbol checking", self.symbol_checking),
+            ("type checking", self.type_checking),
+            ("solving", self.solving),
+        ];
+        for (name, duration) in phases {
+            match duration {
+                Some(duration) => writeln!(f, "{:<16}{:>12.3?}", name, duration)?,
+                None => writeln!(f, "{:<16}{:>12}", name, "-")?,
+            }
+        }
+        let total: Duration = phases.iter().filter_map(|(_, duration)| *duration).sum();
+        write!(f, "{:<16}{:>12.3?}", "total", total)
+    }
+}
+
+// Lexes, parses and checks the symbols of the source without printing
+// anything. Every stage runs even if an earlier one failed so all diagnostics
+// are returned together, the ast only if none of them is an error.
+#[allow(dead_code)]
+pub fn compile(source: &str) -> Result<ast::Ast, Vec<Diagnostic>> {
+    let diagnostics_colletion = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+    let ast =
+        ast::parser::Parser::from_input(source.to_string(), Rc::clone(&diagnostics_colletion))
+            .parse();
+    symbol_checker::SymbolChecker::new(Rc::clone(&diagnostics_colletion)).check(&ast);
+
+    let mut diagnostics_colletion = diagnostics_colletion.borrow_mut();
+    if diagnostics_colletion.has_errors() {
+        return Err(std::mem::take(&mut diagnostics_colletion.diagnostics));
+    }
+    Ok(ast)
+}
+
 pub struct CompilationUnit {
     pub(crate) ast: ast::Ast,
+    source_text: SourceText,
     diagnostics_colletion: DiagnosticsColletionCell,
+    stats: CompileStats,
 }
 
 impl CompilationUnit {
+    // Parses and checks the input, the diagnostics of every stage are printed
+    // and the first stage with errors stops the compilation.
     pub fn compile(input: &str) -> Result<CompilationUnit, ()> {
+        let mut compilation_unit = Self::parse(input)?;
+        compilation_unit.check()?;
+        Ok(compilation_unit)
+    }
+
+    // Only lexes and parses the input, no symbol or type checks are done.
+    pub fn parse(input: &str) -> Result<CompilationUnit, ()> {
         let source_text = SourceText::new(input.to_string());
-        let mut lexer = ast::lexer::Lexer::new(input.to_string());
+        let diagnostics_colletion = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut stats = CompileStats::default();
+
+        let start = Instant::now();
+        let mut lexer =
+            ast::lexer::Lexer::new(input.to_string(), Rc::clone(&diagnostics_colletion));
         let mut tokens: Vec<Token> = Vec::new();
         while let Some(token) = lexer.next_token() {
             tokens.push(token);
         }
+        stats.lexing = Some(start.elapsed());
 
-        let diagnostics_colletion = Rc::new(RefCell::new(DiagnosticsColletion::new()));
-        let mut ast = ast::Ast::new();
+        let start = Instant::now();
         let mut parser = ast::parser::Parser::new(tokens, Rc::clone(&diagnostics_colletion));
-        while let Some(statement) = parser.next_statement() {
-            ast.add_statement(statement);
-        }
-        ast.visualize();
-
-        let mut highlight_printer = ASTHiglightPrinter::new();
-        ast.visit(&mut highlight_printer);
-        highlight_printer.print_result();
-
-        println!(
-            "Synatx Errors: {}",
-            diagnostics_colletion.borrow_mut().diagnostics.len()
-        );
-        Self::check_diagstics(&source_text, &diagnostics_colletion)?;
-
-        let mut symbol_checker =
-            symbol_checker::SymbolChecker::new(Rc::clone(&diagnostics_colletion));
-        ast.visit(&mut symbol_checker);
-        println!(
-            "Indentifier Errors: {}",
-            diagnostics_colletion.borrow_mut().diagnostics.len()
-        );
+        let ast = parser.parse();
+        stats.parsing = Some(start.elapsed());
         Self::check_diagstics(&source_text, &diagnostics_colletion)?;
 
         Ok(Self {
             ast,
+            source_text,
             diagnostics_colletion,
+            stats,
         })
     }
 
-    pub fn run(&self) {
-        let mut solver = ASTSolver::new();
-        self.ast.visit(&mut solver);
-        solver.print_result();
+    // Runs the symbol and type checks, warnings are printed but only errors fail.
+    pub fn check(&mut self) -> Result<(), ()> {
+        let start = Instant::now();
+        let mut symbol_checker =
+            symbol_checker::SymbolChecker::new(Rc::clone(&self.diagnostics_colletion));
+        symbol_checker.check(&self.ast);
+        self.stats.symbol_checking = Some(start.elapsed());
+        Self::check_diagstics(&self.source_text, &self.diagnostics_colletion)?;
+
+        let start = Instant::now();
+        TypeChecker::new(Rc::clone(&self.diagnostics_colletion)).check(&self.ast);
+        self.stats.type_checking = Some(start.elapsed());
+        Self::check_diagstics(&self.source_text, &self.diagnostics_colletion)
+    }
+
+    pub fn stats(&self) -> &CompileStats {
+        &self.stats
+    }
+
+    pub fn print_ast(&self) {
+        self.ast.visualize();
+
+        let mut highlight_printer = ASTHiglightPrinter::new();
+        self.ast.visit(&mut highlight_printer);
+        highlight_printer.print_result();
+    }
+
+    // The constants are folded with floats before the program is evaluated
+    pub fn run(&mut self, number_format: NumberFormat) -> Result<(), ()> {
+        self.ast = std::mem::replace(&mut self.ast, ast::Ast::new()).fold_constants();
+        let solver = ASTSolver::new(Rc::clone(&self.diagnostics_colletion));
+        self.evaluate(solver, number_format)
+    }
+
+    // Evaluates with exact fractions, nothing is folded with floats before
+    pub fn run_exact(&mut self, number_format: NumberFormat) -> Result<(), ()> {
+        let solver = ASTSolver::<Rational>::with_numeric(Rc::clone(&self.diagnostics_colletion));
+        self.evaluate(solver, number_format)
+    }
+
+    fn evaluate<N: Numeric>(
+        &mut self,
+        mut solver: ASTSolver<N>,
+        number_format: NumberFormat,
+    ) -> Result<(), ()> {
+        let start = Instant::now();
+        solver.set_number_format(number_format);
+        let result = solver.evaluate(&self.ast);
+        self.stats.solving = Some(start.elapsed());
+        Self::check_diagstics(&self.source_text, &self.diagnostics_colletion)?;
+        match result {
+            Ok(_) => solver.print_result(),
+            Err(error) => println!("Solver error: {}", error.message(self.source_text.text())),
+        }
+        Ok(())
     }
 
     fn check_diagstics(
         source_text: &SourceText,
         diagnostics_colletion: &DiagnosticsColletionCell,
     ) -> Result<(), ()> {
-        let diagnostics_messages = &diagnostics_colletion.borrow().diagnostics;
-        if diagnostics_messages.len() > 0 {
-            let diagnostics_printer = DiagnosticsPrinter::new(&source_text, &diagnostics_messages);
-            diagnostics_printer.print();
+        let mut diagnostics_colletion = diagnostics_colletion.borrow_mut();
+        let has_errors = diagnostics_colletion.has_errors();
+        let diagnostics_printer =
+            DiagnosticsPrinter::new(source_text, &diagnostics_colletion.diagnostics);
+        diagnostics_printer.print();
+        // warnings and notes don't stop the compilation, they are only reported once
+        diagnostics_colletion.clear();
+        if has_errors {
             return Err(());
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{compile, CompilationUnit, NumberFormat};
+    use crate::ast::{ASTExpressionKind, ASTStatementKind};
+
+    #[test]
+    fn should_time_every_phase_of_a_run() {
+        let mut compilation_unit = CompilationUnit::compile("let a: i32 = 2; a * 3;").unwrap();
+        compilation_unit.run(NumberFormat::default()).unwrap();
+        let stats = compilation_unit.stats();
+        assert!(stats.lexing.is_some());
+        assert!(stats.parsing.is_some());
+        assert!(stats.symbol_checking.is_some());
+        assert!(stats.type_checking.is_some());
+        assert!(stats.solving.is_some());
+    }
+
+    #[test]
+    fn should_only_fold_constants_when_running_with_floats() {
+        let is_folded = |compilation_unit: &CompilationUnit| {
+            matches!(
+                compilation_unit.ast.statements()[0].kind(),
+                ASTStatementKind::Expr(expr)
+                    if matches!(expr.kind(), ASTExpressionKind::BooleanLiteral(_))
+            )
+        };
+        let mut compilation_unit = CompilationUnit::compile("0.1 + 0.2 == 0.3;").unwrap();
+        compilation_unit.run_exact(NumberFormat::default()).unwrap();
+        assert!(!is_folded(&compilation_unit));
+        compilation_unit.run(NumberFormat::default()).unwrap();
+        assert!(is_folded(&compilation_unit));
+    }
+
+    #[test]
+    fn should_run_programs_without_value() {
+        for input in ["", "func f() {}", "func f() {} f();"] {
+            let mut compilation_unit = CompilationUnit::compile(input).unwrap();
+            assert!(
+                compilation_unit.run(NumberFormat::default()).is_ok(),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn should_compile_clean_program() {
+        let Ok(ast) = compile("let a: i32 = 2; func f(x) -> i32 { return x * a; } f(3);") else {
+            panic!("Expected the compilation to succeed");
+        };
+        assert_eq!(ast.statements().len(), 3);
+    }
+
+    #[test]
+    fn should_return_diagnostics_of_every_stage_together() {
+        let Err(diagnostics) = compile("let a: char = 'ab'; let i32 = 1; b + a;") else {
+            panic!("Expected the compilation to fail");
+        };
+        let messages: Vec<&str> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Character literal must contain exactly one character",
+                "Expected <Identifier>, but found <I32>",
+                "Undefined variable 'b'",
+            ]
+        );
+    }
+
+    #[test]
+    fn should_only_allow_shadowing_in_inner_scope() {
+        assert!(compile("let a: i32 = 1; { let a: i32 = a + 1; a; } a;").is_ok());
+        let Err(diagnostics) = compile("let a: i32 = 1; let a: i32 = 2; a;") else {
+            panic!("Expected the compilation to fail");
+        };
+        assert_eq!(
+            diagnostics[0].message(),
+            "Variable 'a' already declared in this scope"
+        );
+        assert_eq!(diagnostics[0].span().start, 20);
+    }
+
+    #[test]
+    fn should_leave_phases_that_did_not_run_empty() {
+        let compilation_unit = CompilationUnit::parse("1 + 2;").unwrap();
+        let stats = compilation_unit.stats();
+        assert!(stats.parsing.is_some());
+        assert!(stats.symbol_checking.is_none());
+        assert!(stats.solving.is_none());
+    }
+}
@@ -1,27 +1,43 @@
 pub mod printer;
 
 use crate::ast::lexer::{TextSpan, Token, TokenKind};
+use crate::ast::type_checker::Type;
 use std::{cell::RefCell, rc::Rc};
 
-pub enum DiagnosticKind {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
     Error,
     Warning,
+    Note,
 }
 
+#[derive(Debug)]
 pub struct Diagnostic {
     pub(crate) message: String,
-    pub(crate) kind: DiagnosticKind,
+    pub(crate) severity: Severity,
     pub(crate) span: TextSpan,
 }
 
 impl Diagnostic {
-    pub fn new(message: String, kind: DiagnosticKind, span: TextSpan) -> Self {
+    pub fn new(message: String, severity: Severity, span: TextSpan) -> Self {
         Self {
             message,
-            kind,
+            severity,
             span,
         }
     }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn span(&self) -> &TextSpan {
+        &self.span
+    }
 }
 
 pub struct DiagnosticsColletion {
@@ -30,6 +46,15 @@ pub struct DiagnosticsColletion {
 
 pub type DiagnosticsColletionCell = Rc<RefCell<DiagnosticsColletion>>;
 
+impl<'a> IntoIterator for &'a DiagnosticsColletion {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 impl DiagnosticsColletion {
     pub fn new() -> Self {
         Self {
@@ -37,18 +62,63 @@ impl DiagnosticsColletion {
         }
     }
 
+    // Renders every diagnostic with the offending line of `source` and the
+    // span underlined
+    pub fn render(&self, source: &str) -> String {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| printer::render_frame(source, diagnostic))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn print(&self, source: &str) {
+        print!("{}", self.render(source));
+    }
+
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    // All diagnostics in the order they were reported
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.errors().count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
     pub fn clear(&mut self) {
         self.diagnostics.clear();
     }
 
     pub fn report_error(&mut self, message: String, span: TextSpan) {
         self.diagnostics
-            .push(Diagnostic::new(message, DiagnosticKind::Error, span));
+            .push(Diagnostic::new(message, Severity::Error, span));
     }
 
     pub fn report_warning(&mut self, message: String, span: TextSpan) {
         self.diagnostics
-            .push(Diagnostic::new(message, DiagnosticKind::Warning, span));
+            .push(Diagnostic::new(message, Severity::Warning, span));
+    }
+
+    pub fn report_note(&mut self, message: String, span: TextSpan) {
+        self.diagnostics
+            .push(Diagnostic::new(message, Severity::Note, span));
     }
 
     pub fn report_unexpected_token(&mut self, expected_tokenkind: &TokenKind, found_token: &Token) {
@@ -60,29 +130,421 @@ impl DiagnosticsColletion {
             found_token.span.clone(),
         );
     }
+    pub fn report_unknown_character(&mut self, character: char, span: TextSpan) {
+        self.report_error(format!("Unknown character '{}'", character), span);
+    }
+
+    pub fn report_unterminated_block_comment(&mut self, span: TextSpan) {
+        self.report_error("Unterminated block comment".to_string(), span);
+    }
+
+    pub fn report_unterminated_string_literal(&mut self, span: TextSpan) {
+        self.report_error("Unterminated string literal".to_string(), span);
+    }
+
+    pub fn report_unterminated_char_literal(&mut self, span: TextSpan) {
+        self.report_error("Unterminated character literal".to_string(), span);
+    }
+
+    pub fn report_invalid_char_literal(&mut self, span: TextSpan) {
+        self.report_error(
+            "Character literal must contain exactly one character".to_string(),
+            span,
+        );
+    }
+
+    pub fn report_unknown_escape_sequence(&mut self, sequence: &str, span: TextSpan) {
+        self.report_error(format!("Unknown escape sequence '{}'", sequence), span);
+    }
+
+    pub fn report_invalid_unicode_escape(&mut self, escape: &str, span: TextSpan) {
+        self.report_error(format!("Invalid unicode escape '{}'", escape), span);
+    }
+
+    pub fn report_invalid_digit_in_literal(&mut self, digit: char, radix: &str, span: TextSpan) {
+        self.report_error(
+            format!("Invalid digit '{}' in {} literal", digit, radix),
+            span,
+        );
+    }
+
+    pub fn report_missing_digits_in_literal(&mut self, radix: &str, span: TextSpan) {
+        self.report_error(format!("Missing digits in {} literal", radix), span);
+    }
+
+    pub fn report_integer_literal_too_large(&mut self, span: TextSpan) {
+        self.report_error("Integer literal is too large".to_string(), span);
+    }
+
+    pub fn report_missing_exponent_digits(&mut self, span: TextSpan) {
+        self.report_error(
+            "Missing digits in exponent of floating literal".to_string(),
+            span,
+        );
+    }
+
+    pub fn report_invalid_digit_separator(&mut self, span: TextSpan) {
+        self.report_error(
+            "Digit separator '_' must be placed between two digits".to_string(),
+            span,
+        );
+    }
+
+    pub fn report_division_by_zero(&mut self, span: TextSpan) {
+        self.report_error("Division by zero".to_string(), span);
+    }
+
+    pub fn report_integer_overflow(&mut self, span: TextSpan) {
+        self.report_error("Integer overflow".to_string(), span);
+    }
+
+    pub fn report_assertion_failed(&mut self, span: TextSpan) {
+        self.report_error("Assertion failed".to_string(), span);
+    }
+
+    pub fn report_mismatched_types(&mut self, expected: &Type, found: &Type, span: TextSpan) {
+        self.report_error(
+            format!("Mismatched types: expected {}, found {}", expected, found),
+            span,
+        );
+    }
+
+    pub fn report_mixed_numeric_operands(&mut self, left: &Type, right: &Type, operator: &Token) {
+        self.report_error(
+            format!(
+                "Cannot mix {} and {} operands in '{}'",
+                left, right, operator.literal
+            ),
+            operator.span.clone(),
+        );
+    }
+
+    pub fn report_bitwise_on_float(&mut self, span: TextSpan) {
+        self.report_error(
+            "Bitwise operators can't be applied to floats".to_string(),
+            span,
+        );
+    }
+
+    pub fn report_argument_type_mismatch(
+        &mut self,
+        position: usize,
+        expected: &Type,
+        found: &Type,
+        function: &Token,
+    ) {
+        self.report_error(
+            format!(
+                "Argument {} of function '{}' expects {}, but found {}",
+                position, function.literal, expected, found
+            ),
+            function.span.clone(),
+        );
+    }
+
+    pub fn report_unterminated_block(&mut self, span: TextSpan) {
+        self.report_error("Unterminated block, expected <}>".to_string(), span);
+    }
+
+    pub fn report_unexpected_trailing_tokens(&mut self, span: TextSpan) {
+        self.report_error("Unexpected trailing tokens".to_string(), span);
+    }
+
+    pub fn report_unclosed_parenthesis(&mut self, span: TextSpan) {
+        self.report_note("Unclosed '(' opened here".to_string(), span);
+    }
+
+    pub fn report_block_too_deeply_nested(&mut self, span: TextSpan, limit: usize) {
+        self.report_error(
+            format!(
+                "Block too deeply nested, maximum depth of {} exceeded",
+                limit
+            ),
+            span,
+        );
+    }
+
+    pub fn report_expression_too_deeply_nested(&mut self, span: TextSpan, limit: usize) {
+        self.report_error(
+            format!(
+                "Expression too deeply nested, maximum depth of {} exceeded",
+                limit
+            ),
+            span,
+        );
+    }
+
     pub fn report_expected_expression(&mut self, found_token: &Token) {
         self.report_error(
-            format!("Expected expression, but found <{}>", found_token.kind),
+            format!(
+                "Expected expression, found {}",
+                found_token.kind.description()
+            ),
             found_token.span.clone(),
         );
     }
 
-    pub fn report_undefined_variable(&mut self, span: TextSpan) {
-        self.report_error(format!("Not found in this scope"), span);
+    pub fn report_expected_literal_pattern(&mut self, pattern: &str, span: TextSpan) {
+        self.report_error(
+            format!("Expected literal pattern, but found '{}'", pattern),
+            span,
+        );
+    }
+
+    pub fn report_undefined_variable(&mut self, identifier: &Token) {
+        self.report_error(
+            format!("Undefined variable '{}'", identifier.literal),
+            identifier.span.clone(),
+        );
+    }
+
+    pub fn report_variable_used_before_declaration(&mut self, identifier: &Token) {
+        self.report_error(
+            format!("Variable '{}' used before declaration", identifier.literal),
+            identifier.span.clone(),
+        );
+    }
+
+    pub fn report_function_already_defined(&mut self, identifier: &Token) {
+        self.report_error(
+            format!("Function '{}' already defined", identifier.literal),
+            identifier.span.clone(),
+        );
+    }
+
+    pub fn report_variable_already_declared(&mut self, identifier: &Token) {
+        self.report_error(
+            format!(
+                "Variable '{}' already declared in this scope",
+                identifier.literal
+            ),
+            identifier.span.clone(),
+        );
+    }
+
+    pub fn report_duplicate_parameter(&mut self, identifier: &Token) {
+        self.report_error(
+            format!("Duplicate parameter '{}'", identifier.literal),
+            identifier.span.clone(),
+        );
+    }
+
+    pub fn report_parameter_without_default(&mut self, identifier: &Token) {
+        self.report_error(
+            format!(
+                "Parameter '{}' needs a default value, it follows a parameter with one",
+                identifier.literal
+            ),
+            identifier.span.clone(),
+        );
+    }
+
+    pub fn report_unused_variable(&mut self, identifier: &Token) {
+        self.report_warning(
+            format!("Unused variable '{}'", identifier.literal),
+            identifier.span.clone(),
+        );
+    }
+
+    pub fn report_unused_function(&mut self, identifier: &Token) {
+        self.report_warning(
+            format!("Unused function '{}'", identifier.literal),
+            identifier.span.clone(),
+        );
+    }
+
+    pub fn report_outside_of_loop(&mut self, keyword: &Token) {
+        self.report_error(
+            format!("'{}' outside of a loop", keyword.literal),
+            keyword.span.clone(),
+        );
+    }
+
+    pub fn report_unreachable_code(&mut self, span: TextSpan) {
+        self.report_warning("Unreachable code after return".to_string(), span);
+    }
+
+    pub fn report_undefined_function(&mut self, identifier: &Token) {
+        self.report_error(
+            format!("Undefined function '{}'", identifier.literal),
+            identifier.span.clone(),
+        );
+    }
+
+    pub fn report_missing_value(&mut self, span: TextSpan) {
+        self.report_error("Expression has no value".to_string(), span);
+    }
+
+    pub fn report_recursion_limit_exceeded(&mut self, span: TextSpan, limit: usize) {
+        self.report_error(
+            format!("Maximum recursion depth of {} exceeded", limit),
+            span,
+        );
+    }
+
+    pub fn report_iteration_limit_exceeded(&mut self, span: TextSpan, limit: usize) {
+        self.report_error(
+            format!("Loop exceeded the maximum of {} iterations", limit),
+            span,
+        );
+    }
+
+    pub fn report_tuple_in_arithmetic(&mut self, span: TextSpan) {
+        self.report_error(
+            "Tuples can't be used in arithmetic expressions".to_string(),
+            span,
+        );
+    }
+
+    pub fn report_string_in_arithmetic(&mut self, span: TextSpan) {
+        self.report_error(
+            "Strings can't be used in arithmetic expressions".to_string(),
+            span,
+        );
+    }
+
+    pub fn report_domain_error(&mut self, identifier: &Token) {
+        self.report_error(
+            format!("Argument out of the domain of '{}'", identifier.literal),
+            identifier.span.clone(),
+        );
     }
 
     pub fn report_number_of_function_arguments_mismatch(
         &mut self,
-        span: TextSpan,
+        identifier: &Token,
         expected: usize,
         found: usize,
     ) {
         self.report_error(
             format!(
-                "Function {} expects {} arguments but {} were given",
-                span.literal, expected, found
+                "Function '{}' expects {} arguments but got {}",
+                identifier.literal, expected, found
             ),
-            span,
+            identifier.span.clone(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        printer::DiagnosticsPrinter, Diagnostic, DiagnosticsColletion, DiagnosticsColletionCell,
+        Severity,
+    };
+    use crate::ast::lexer::{TextSpan, Token, TokenKind};
+    use crate::ast::parser::Parser;
+    use crate::source_text::SourceText;
+    use std::{cell::RefCell, rc::Rc};
+
+    fn parse(input: &str) -> DiagnosticsColletionCell {
+        let diagnostics = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        let mut parser = Parser::from_input(input.to_string(), Rc::clone(&diagnostics));
+        while parser.next_statement().is_some() {}
+        diagnostics
+    }
+
+    #[test]
+    fn should_render_caret_under_span() {
+        let source = "let = 5;";
+        let diagnostics = parse(source);
+        // the rest of the broken declaration is skipped
+        let rendered = diagnostics.borrow().render(source);
+        assert_eq!(
+            rendered,
+            "\
+error: Expected <Identifier>, but found <=>
+ --> 1:5
+  |
+1 | let = 5;
+  |     ^
+"
+        );
+    }
+
+    #[test]
+    fn should_query_reported_diagnostics() {
+        let source = "let = 5";
+        let diagnostics = parse(source);
+        let diagnostics = diagnostics.borrow();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(!diagnostics.is_empty());
+        assert_eq!(diagnostics.error_count(), 1);
+        let error = diagnostics.errors().next().unwrap();
+        assert_eq!(error.message(), "Expected <Identifier>, but found <=>");
+        assert_eq!(error.severity(), Severity::Error);
+        assert_eq!(error.span().literal(source), "=");
+        assert_eq!(error.span().start, 4);
+        assert_eq!(
+            (&*diagnostics)
+                .into_iter()
+                .map(Diagnostic::message)
+                .collect::<Vec<&str>>(),
+            vec!["Expected <Identifier>, but found <=>"]
         );
+
+        let mut diagnostics = DiagnosticsColletion::new();
+        assert!(diagnostics.is_empty());
+        diagnostics.report_warning("unused".to_string(), TextSpan::new(0, 0));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics.errors().count(), 0);
+    }
+
+    #[test]
+    fn should_report_line_of_error_on_third_line() {
+        let source = "let a: i32 = 1;\nlet b: i32 = 2;\nlet = 3;";
+        let diagnostics = parse(source);
+        let diagnostics = diagnostics.borrow();
+        let diagnostic = &diagnostics.diagnostics[0];
+        assert_eq!(diagnostic.span.start, 36);
+        assert_eq!((diagnostic.span.line, diagnostic.span.column), (3, 5));
+        assert!(diagnostics.render(source).contains(" --> 3:5\n"));
+
+        let source_text = SourceText::new(source.to_string());
+        let printed = DiagnosticsPrinter::new(&source_text, &diagnostics.diagnostics)
+            .stringify_diagnostic(diagnostic);
+        assert!(printed.contains(" 3 | "), "{printed}");
+        assert!(printed.contains("(3:5)"), "{printed}");
+    }
+
+    #[test]
+    fn should_underline_whole_span_on_later_line() {
+        let source = "let a: i32 = 1;\nlet b: i32 = a + ünknown;\n";
+        let mut diagnostics = DiagnosticsColletion::new();
+        diagnostics.report_undefined_variable(&Token::new(
+            TokenKind::Identifier,
+            TextSpan::new(33, 41),
+            "ünknown".to_string(),
+        ));
+        assert_eq!(
+            diagnostics.render(source),
+            "\
+error: Undefined variable 'ünknown'
+ --> 2:18
+  |
+2 | let b: i32 = a + ünknown;
+  |                  ^^^^^^^
+"
+        );
+    }
+
+    #[test]
+    fn should_label_severities() {
+        let source = "let a: i32 = 1;";
+        let mut diagnostics = DiagnosticsColletion::new();
+        diagnostics.report_warning("Unused".to_string(), TextSpan::new(4, 5));
+        diagnostics.report_note("Declared here".to_string(), TextSpan::new(0, 3));
+        assert!(!diagnostics.has_errors());
+
+        let rendered = diagnostics.render(source);
+        let labels: Vec<&str> = rendered
+            .lines()
+            .filter(|line| line.contains(": ") && !line.contains('|'))
+            .collect();
+        assert_eq!(labels, vec!["warning: Unused", "note: Declared here"]);
+
+        diagnostics.report_error("Broken".to_string(), TextSpan::new(0, 3));
+        assert!(diagnostics.has_errors());
+        assert_eq!(diagnostics.error_count(), 1);
     }
 }
@@ -1,4 +1,5 @@
-use super::Diagnostic;
+use super::{Diagnostic, Severity};
+use crate::ast::lexer::TextSpan;
 use crate::source_text::SourceText;
 use termion::color;
 
@@ -25,33 +26,81 @@ impl<'a> DiagnosticsPrinter<'a> {
     //             ^^^^^^^ Not found in this scope
     pub fn stringify_diagnostic(&self, diagnostic: &Diagnostic) -> String {
         let (line, col) = self.source_text.get_location(diagnostic.span.start);
-        let line_number = self.source_text.get_linenumber(diagnostic.span.start) - 1;
-        let symbol_len = diagnostic.span.literal.len();
-        let symbol_end_col = col + diagnostic.span.literal.len();
+        let line_number = self.source_text.get_linenumber(diagnostic.span.start);
+        // only the part of the span on this line is underlined
+        let col = col.min(line.len());
+        let symbol_len = diagnostic
+            .span
+            .literal(self.source_text.text())
+            .lines()
+            .next()
+            .map_or(0, |literal| literal.len())
+            .min(line.len() - col);
+        let symbol_end_col = col + symbol_len;
         let prefix = line[..col].to_string();
         let error_symbol = line[col..symbol_end_col].to_string();
         let suffix = line[symbol_end_col..].to_string();
 
-        let message_color: Box<dyn color::Color> = match diagnostic.kind {
-            super::DiagnosticKind::Error => Box::new(color::Red),
-            super::DiagnosticKind::Warning => Box::new(color::Yellow),
+        let message_color: Box<dyn color::Color> = match diagnostic.severity {
+            Severity::Error => Box::new(color::Red),
+            Severity::Warning => Box::new(color::Yellow),
+            Severity::Note => Box::new(color::Cyan),
         };
+        let label = severity_label(diagnostic.severity);
 
         let line_number_str = format!("{:2} | ", line_number);
-        let whitespace = " ".repeat(col + line_number_str.len());
+        let whitespace = " ".repeat(prefix.chars().count() + line_number_str.len());
 
         format!(
             // "{}{line_number_str}{}{prefix}{error_symbol}{suffix}\n{whitespace}{}{}\n{whitespace}|\n{whitespace}+-- {}{}",
-            "{}{line_number_str}{}{prefix}{error_symbol}{suffix}\n{whitespace}{}{} {}({}:{}){}",
+            "{}{line_number_str}{}{prefix}{error_symbol}{suffix}\n{whitespace}{}{} {}: {}({}:{}){}",
             color::Fg(color::Blue),
             color::Fg(color::Reset),
             color::Fg(message_color.as_ref()),
-            "^".repeat(symbol_len),
+            "^".repeat(error_symbol.chars().count().max(1)),
+            label,
             diagnostic.message,
             line_number,
-            col,
+            prefix.chars().count() + 1,
             color::Fg(color::Reset)
         )
         .to_string()
     }
 }
+
+fn severity_label(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+    }
+}
+
+// Renders a diagnostic without colors in the style of rustc:
+//
+// error: Expected <Identifier>, but found <=>
+//  --> 1:5
+//   |
+// 1 | let = 5;
+//   |     ^
+pub fn render_frame(source: &str, diagnostic: &Diagnostic) -> String {
+    let (line_number, column) = TextSpan::line_column(source, diagnostic.span.start);
+    let line = source.lines().nth(line_number - 1).unwrap_or("");
+    let underline_len = diagnostic
+        .span
+        .literal(source)
+        .lines()
+        .next()
+        .map_or(0, |literal| literal.chars().count())
+        .max(1);
+
+    let kind = severity_label(diagnostic.severity);
+    let gutter = " ".repeat(line_number.to_string().len());
+
+    format!(
+        "{kind}: {}\n{gutter}--> {line_number}:{column}\n{gutter} |\n{line_number} | {line}\n{gutter} | {}{}\n",
+        diagnostic.message,
+        " ".repeat(column - 1),
+        "^".repeat(underline_len),
+    )
+}
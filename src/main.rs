@@ -1,26 +1,73 @@
 mod ast;
+mod cli;
+// the bytecode backend isn't reachable from the command line yet
+#[allow(dead_code)]
+mod codegen;
 mod compilation_unit;
 mod diagnostics;
+mod repl;
 mod source_text;
 
-use std::fs;
+use std::{fs, process::ExitCode};
 
+use crate::ast::lexer::Lexer;
+use crate::cli::{Mode, Options};
 use crate::compilation_unit::CompilationUnit;
+use crate::repl::Repl;
 
-fn main() -> Result<(), ()> {
-    // func a() { return 10; }
-    let input = "\
-func a(arg1, arg2) {
-  let c = arg2 / 071
-  return arg1 * c
-}
+fn main() -> ExitCode {
+    let options = match Options::from_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, Options::USAGE);
+            return ExitCode::from(2);
+        }
+    };
 
-return a(0x02, 7.67)
-";
+    let result = match options.mode {
+        Mode::Repl => Repl::new().run(),
+        _ => match fs::read_to_string(&options.path) {
+            Ok(content) => run(&content, &options),
+            Err(error) => {
+                eprintln!("Can't read '{}': {}", options.path, error);
+                Err(())
+            }
+        },
+    };
 
-    let content = fs::read_to_string("math.txt").unwrap();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(()) => ExitCode::FAILURE,
+    }
+}
 
-    let compilation_unit = CompilationUnit::compile(content.as_str())?;
-    compilation_unit.run();
+fn run(content: &str, options: &Options) -> Result<(), ()> {
+    let compilation_unit = match options.mode {
+        Mode::Tokens => {
+            for token in Lexer::tokenize(content) {
+                println!("{}", token);
+            }
+            return Ok(());
+        }
+        Mode::Ast => {
+            let compilation_unit = CompilationUnit::parse(content)?;
+            compilation_unit.print_ast();
+            compilation_unit
+        }
+        Mode::Check => CompilationUnit::compile(content)?,
+        Mode::Eval => {
+            let mut compilation_unit = CompilationUnit::compile(content)?;
+            if options.exact {
+                compilation_unit.run_exact(options.number_format)?;
+            } else {
+                compilation_unit.run(options.number_format)?;
+            }
+            compilation_unit
+        }
+        Mode::Repl => unreachable!("the repl is started without a file"),
+    };
+    if options.time {
+        eprintln!("{}", compilation_unit.stats());
+    }
     Ok(())
 }
@@ -0,0 +1,123 @@
+use crate::ast::{
+    parser::Parser,
+    solver::{ASTSolver, Value},
+};
+use crate::diagnostics::{
+    printer::DiagnosticsPrinter, DiagnosticsColletion, DiagnosticsColletionCell,
+};
+use crate::source_text::SourceText;
+use std::{
+    cell::RefCell,
+    io::{self, BufRead, Write},
+    rc::Rc,
+};
+
+pub struct Repl {
+    solver: ASTSolver,
+    diagnostics_colletion: DiagnosticsColletionCell,
+}
+
+impl Repl {
+    const PROMPT: &'static str = "> ";
+
+    pub fn new() -> Self {
+        let diagnostics_colletion = Rc::new(RefCell::new(DiagnosticsColletion::new()));
+        Self {
+            solver: ASTSolver::new(Rc::clone(&diagnostics_colletion)),
+            diagnostics_colletion,
+        }
+    }
+
+    // Reads lines from stdin until the end of input, printing the value of
+    // every line that produced one.
+    pub fn run(&mut self) -> Result<(), ()> {
+        let stdin = io::stdin();
+        let mut line = String::new();
+        loop {
+            print!("{}", Self::PROMPT);
+            io::stdout().flush().map_err(|_| ())?;
+
+            line.clear();
+            if stdin.lock().read_line(&mut line).map_err(|_| ())? == 0 {
+                println!();
+                return Ok(());
+            }
+
+            if let Ok(Some(value)) = self.evaluate_line(&line) {
+                println!("{}", value);
+            }
+        }
+    }
+
+    // Evaluates a single line, functions and variables of previous lines stay
+    // defined even if the line fails to parse or evaluate.
+    pub fn evaluate_line(&mut self, line: &str) -> Result<Option<Value>, ()> {
+        let mut input = line.trim_end().to_string();
+        if input.trim().is_empty() {
+            return Ok(None);
+        }
+        // the semicolon after a single expression is optional
+        if !input.ends_with(';') && !input.ends_with('}') {
+            input.push(';');
+        }
+
+        let mut parser = Parser::from_input(input.clone(), Rc::clone(&self.diagnostics_colletion));
+        let ast = parser.parse();
+        self.check_diagnostics(&input)?;
+
+        let result = self.solver.evaluate(&ast);
+        self.check_diagnostics(&input)?;
+        match result {
+            Ok(value) => Ok(value),
+            Err(error) => {
+                println!("Solver error: {}", error.message(&input));
+                Err(())
+            }
+        }
+    }
+
+    fn check_diagnostics(&self, input: &str) -> Result<(), ()> {
+        let mut diagnostics_colletion = self.diagnostics_colletion.borrow_mut();
+        if diagnostics_colletion.is_empty() {
+            return Ok(());
+        }
+
+        let source_text = SourceText::new(input.to_string());
+        DiagnosticsPrinter::new(&source_text, &diagnostics_colletion.diagnostics).print();
+        let has_errors = diagnostics_colletion.has_errors();
+        diagnostics_colletion.clear();
+        if has_errors {
+            return Err(());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Repl;
+    use crate::ast::solver::Value;
+
+    #[test]
+    fn should_keep_functions_between_lines() {
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.evaluate_line("func square(x){ return x*x; }"),
+            Ok(None)
+        );
+        assert_eq!(repl.evaluate_line("square(4)"), Ok(Some(Value::Int(16))));
+    }
+
+    #[test]
+    fn should_keep_bindings_after_blank_lines_and_errors() {
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.evaluate_line("var a: i32 = 2;"),
+            Ok(Some(Value::Int(2)))
+        );
+        assert_eq!(repl.evaluate_line(""), Ok(None));
+        assert_eq!(repl.evaluate_line("let = ;"), Err(()));
+        assert_eq!(repl.evaluate_line("b + 1"), Err(()));
+        assert_eq!(repl.evaluate_line("a + 1"), Ok(Some(Value::Int(3))));
+    }
+}
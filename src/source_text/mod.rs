@@ -7,13 +7,8 @@ impl SourceText {
         Self { input }
     }
 
-    pub fn from_file(filename: &str) -> Self {
-        match std::fs::read_to_string(filename) {
-            Ok(input) => Self { input },
-            _ => Self {
-                input: "".to_string(),
-            },
-        }
+    pub fn text(&self) -> &str {
+        &self.input
     }
 
     pub fn get_location(&self, index: usize) -> (String, usize) {
@@ -21,26 +16,35 @@ impl SourceText {
         (self.get_line(line_number - 1), self.get_column(index))
     }
 
+    // Byte offset of `index` from the start of its line
     pub fn get_column(&self, index: usize) -> usize {
-        if self.input[0..index].lines().count() > 0 {
-            self.input[0..index].lines().last().unwrap().len()
-        } else {
-            index
-        }
+        let before = &self.input[..index.min(self.input.len())];
+        before.len() - before.rfind('\n').map_or(0, |i| i + 1)
     }
 
+    // 1-based, an index right after a newline is on the next line
     pub fn get_linenumber(&self, index: usize) -> usize {
-        if index == 0 {
-            return 1;
-        }
-        self.input[0..index].lines().count()
+        let before = &self.input[..index.min(self.input.len())];
+        before.matches('\n').count() + 1
     }
 
     pub fn get_line(&self, row: usize) -> String {
-        if self.input.lines().count() > 0 {
-            self.input.lines().nth(row).unwrap().to_string()
-        } else {
-            self.input.clone()
-        }
+        // the line after a trailing newline is empty
+        self.input.lines().nth(row).unwrap_or("").to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SourceText;
+
+    #[test]
+    fn should_locate_start_of_line() {
+        let source = SourceText::new("let a: i32 = 1;\nlet b: i32 = 2;\n= 3;".to_string());
+        assert_eq!(source.get_linenumber(0), 1);
+        assert_eq!(source.get_linenumber(16), 2);
+        assert_eq!(source.get_column(16), 0);
+        assert_eq!(source.get_linenumber(32), 3);
+        assert_eq!(source.get_location(34), ("= 3;".to_string(), 2));
     }
 }